@@ -0,0 +1,245 @@
+// File: src/backtest/fill_model.rs
+// 排队感知的成交模拟：每边最多一张挂单，记录挂单时排在我们前面的数量 (queue_ahead)
+// 成交流从相邻两次盘口快照推断：最优价位 (及被击穿的价位) 上减少的数量视为主动成交，更深价位的减少视为撤单
+use rust_decimal::Decimal;
+
+use crate::core::{BookSide, OrderBookUpdate, Side};
+
+#[derive(Clone, Debug)]
+pub struct RestingOrder {
+    pub price: Decimal,
+    pub remaining: Decimal,   // 剩余份数
+    pub queue_ahead: Decimal, // 同价位排在我们前面的份数
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct SimFill {
+    pub side: Side, // 我们的方向
+    pub price: Decimal,
+    pub shares: Decimal,
+    pub taker: bool, // 挂单时就穿过了对手价 (立即成交)
+}
+
+#[derive(Default)]
+pub struct QueueFillModel {
+    prev: Option<OrderBookUpdate>,
+    bid: Option<RestingOrder>,
+    ask: Option<RestingOrder>,
+}
+
+fn level_size(side: &BookSide, price: Decimal) -> Decimal {
+    side.iter().find(|(p, _)| *p == price).map(|(_, s)| *s).unwrap_or(Decimal::ZERO)
+}
+
+// 推断的主动成交 [(价格, 数量)]：上一次快照中价格不差于新最优价的档位减少的数量
+// is_bid = true 时推断的是打买盘的卖单 (价格 >= 新最优买价)
+fn inferred_trades(prev: &BookSide, next: &BookSide, is_bid: bool) -> Vec<(Decimal, Decimal)> {
+    let Some(&(best, _)) = next.first() else { return Vec::new() }; // 一侧为空多半是数据异常，不推断
+    prev.iter()
+        .filter(|(p, _)| if is_bid { *p >= best } else { *p <= best })
+        .filter_map(|&(p, s)| {
+            let traded = s - level_size(next, p);
+            (traded > Decimal::ZERO).then_some((p, traded))
+        })
+        .collect()
+}
+
+// 用推断的成交撮合我们的挂单；through(p) = 该价位的成交是否越过了我们的价格
+fn match_order(order: &mut RestingOrder, trades: &[(Decimal, Decimal)], through: impl Fn(Decimal) -> bool) -> Decimal {
+    let mut filled = Decimal::ZERO;
+    for &(p, qty) in trades {
+        if order.remaining <= Decimal::ZERO {
+            break;
+        }
+        let available = if through(p) {
+            qty
+        } else if p == order.price {
+            // 同价位：先消耗排在前面的队列
+            let eaten = qty.min(order.queue_ahead);
+            order.queue_ahead -= eaten;
+            qty - eaten
+        } else {
+            continue;
+        };
+        let fill = available.min(order.remaining);
+        order.remaining -= fill;
+        filled += fill;
+    }
+    filled
+}
+
+impl QueueFillModel {
+    /// 新快照到达：推断上一次快照以来的成交并撮合挂单，返回我们的成交
+    pub fn on_book(&mut self, book: &OrderBookUpdate) -> Vec<SimFill> {
+        let mut fills = Vec::new();
+        if let Some(prev) = &self.prev {
+            if let Some(order) = self.bid.as_mut() {
+                let sells = inferred_trades(&prev.bids, &book.bids, true);
+                let price = order.price;
+                let shares = match_order(order, &sells, |p| p < price);
+                if shares > Decimal::ZERO {
+                    fills.push(SimFill { side: Side::Buy, price, shares, taker: false });
+                }
+            }
+            if let Some(order) = self.ask.as_mut() {
+                let buys = inferred_trades(&prev.asks, &book.asks, false);
+                let price = order.price;
+                let shares = match_order(order, &buys, |p| p > price);
+                if shares > Decimal::ZERO {
+                    fills.push(SimFill { side: Side::Sell, price, shares, taker: false });
+                }
+            }
+        }
+
+        // 前面的人撤单后队列只会变短 (保守假设：撤单都发生在我们后面，只在档位总量不足时截断)
+        if let Some(order) = self.bid.as_mut() {
+            order.queue_ahead = order.queue_ahead.min(level_size(&book.bids, order.price));
+        }
+        if let Some(order) = self.ask.as_mut() {
+            order.queue_ahead = order.queue_ahead.min(level_size(&book.asks, order.price));
+        }
+        self.bid.take_if(|o| o.remaining <= Decimal::ZERO);
+        self.ask.take_if(|o| o.remaining <= Decimal::ZERO);
+
+        self.prev = Some(book.clone());
+        fills
+    }
+
+    /// 挂单 (替换同方向的旧单)：同价位保留队列位置和剩余份数，改价则排到新价位的队尾
+    /// 穿过对手最优价的挂单立即按挂单价全部成交
+    pub fn place(&mut self, side: Side, price: Decimal, shares: Decimal) -> Option<SimFill> {
+        let book = self.prev.as_ref()?;
+        let (slot, own, opposite) = match side {
+            Side::Buy => (&mut self.bid, &book.bids, &book.asks),
+            Side::Sell => (&mut self.ask, &book.asks, &book.bids),
+        };
+
+        let crosses = opposite.first().is_some_and(|&(best, _)| match side {
+            Side::Buy => price >= best,
+            Side::Sell => price <= best,
+        });
+        if crosses {
+            *slot = None;
+            return Some(SimFill { side, price, shares, taker: true });
+        }
+
+        *slot = Some(match slot.take() {
+            // 同价位重报不补量：部分成交后的剩余份数保留 (只允许改小)，否则补量等于免费插队
+            Some(o) if o.price == price => RestingOrder { remaining: o.remaining.min(shares), ..o },
+            _ => RestingOrder { price, remaining: shares, queue_ahead: level_size(own, price) },
+        });
+        None
+    }
+
+    pub fn resting_price(&self, side: Side) -> Option<Decimal> {
+        match side {
+            Side::Buy => self.bid.as_ref().map(|o| o.price),
+            Side::Sell => self.ask.as_ref().map(|o| o.price),
+        }
+    }
+
+    pub fn cancel(&mut self, side: Side) {
+        match side {
+            Side::Buy => self.bid = None,
+            Side::Sell => self.ask = None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{Exchange, StageStamps};
+    use rust_decimal_macros::dec;
+
+    fn book(bids: &[(Decimal, Decimal)], asks: &[(Decimal, Decimal)]) -> OrderBookUpdate {
+        OrderBookUpdate {
+            exchange: Exchange::Polymarket,
+            symbol_id: 1,
+            timestamp_ns: 0,
+            trace_id: 0,
+            bids: bids.iter().copied().collect(),
+            asks: asks.iter().copied().collect(),
+            stamps: StageStamps::default(),
+        }
+    }
+
+    #[test]
+    fn trades_at_our_price_deplete_the_queue_before_filling_us() {
+        let mut model = QueueFillModel::default();
+        let asks = [(dec!(0.52), dec!(100))];
+        model.on_book(&book(&[(dec!(0.50), dec!(100))], &asks));
+        assert!(model.place(Side::Buy, dec!(0.50), dec!(30)).is_none());
+
+        // 70 份成交在我们前面；之后新加入的 50 份排在我们后面
+        assert!(model.on_book(&book(&[(dec!(0.50), dec!(30))], &asks)).is_empty());
+        assert!(model.on_book(&book(&[(dec!(0.50), dec!(80))], &asks)).is_empty());
+        assert_eq!(model.bid.as_ref().unwrap().queue_ahead, dec!(30));
+
+        // 再成交 40 份：前面 30 份，轮到我们 10 份
+        let fills = model.on_book(&book(&[(dec!(0.50), dec!(40))], &asks));
+        assert_eq!(fills.len(), 1);
+        assert_eq!((fills[0].side, fills[0].price, fills[0].shares, fills[0].taker), (Side::Buy, dec!(0.50), dec!(10), false));
+        assert_eq!(model.bid.as_ref().unwrap().remaining, dec!(20));
+    }
+
+    #[test]
+    fn only_trades_through_our_price_fill_past_the_queue() {
+        let mut model = QueueFillModel::default();
+        let bids = [(dec!(0.48), dec!(100))];
+        model.on_book(&book(&bids, &[(dec!(0.51), dec!(10)), (dec!(0.52), dec!(50)), (dec!(0.53), dec!(40))]));
+        assert!(model.place(Side::Sell, dec!(0.52), dec!(25)).is_none());
+
+        // 触及：更优价位 0.51 被吃光、我们这档成交 20 份 (都排在前面)，不成交
+        assert!(model.on_book(&book(&bids, &[(dec!(0.52), dec!(30)), (dec!(0.53), dec!(40))])).is_empty());
+
+        // 击穿：0.52 剩下的 30 份全部成交，0.53 成交 15 份 -> 价格越过我们，成交 15 份
+        let fills = model.on_book(&book(&bids, &[(dec!(0.53), dec!(25))]));
+        assert_eq!(fills.len(), 1);
+        assert_eq!((fills[0].side, fills[0].price, fills[0].shares), (Side::Sell, dec!(0.52), dec!(15)));
+    }
+
+    #[test]
+    fn crossing_order_fills_immediately_as_taker() {
+        let mut model = QueueFillModel::default();
+        // 还没有盘口：无法撮合也不挂单
+        assert!(model.place(Side::Buy, dec!(0.52), dec!(10)).is_none());
+        assert_eq!(model.resting_price(Side::Buy), None);
+
+        model.on_book(&book(&[(dec!(0.50), dec!(100))], &[(dec!(0.52), dec!(100))]));
+        model.place(Side::Buy, dec!(0.50), dec!(10));
+        let fill = model.place(Side::Buy, dec!(0.52), dec!(10)).unwrap();
+        assert_eq!((fill.side, fill.price, fill.shares, fill.taker), (Side::Buy, dec!(0.52), dec!(10), true));
+        // 穿价单不会留在簿上，旧挂单也被替换
+        assert_eq!(model.resting_price(Side::Buy), None);
+
+        assert!(model.place(Side::Sell, dec!(0.51), dec!(10)).is_none());
+        assert_eq!(model.resting_price(Side::Sell), Some(dec!(0.51)));
+    }
+
+    #[test]
+    fn same_price_requote_keeps_priority_but_not_a_refill() {
+        let mut model = QueueFillModel::default();
+        let asks = [(dec!(0.52), dec!(100))];
+        model.on_book(&book(&[(dec!(0.50), dec!(5)), (dec!(0.49), dec!(20))], &asks));
+        model.place(Side::Buy, dec!(0.50), dec!(10));
+
+        // 0.49 成交 4 份 (击穿我们) -> 部分成交，剩 6 份，前面已无人排队
+        let fills = model.on_book(&book(&[(dec!(0.49), dec!(16))], &asks));
+        assert_eq!(fills[0].shares, dec!(4));
+
+        // 同价位按原金额重报：保留队列位置和剩余的 6 份
+        model.place(Side::Buy, dec!(0.50), dec!(10));
+        let order = model.bid.as_ref().unwrap();
+        assert_eq!((order.remaining, order.queue_ahead), (dec!(6), dec!(0)));
+        let fills = model.on_book(&book(&[(dec!(0.48), dec!(50))], &asks));
+        assert_eq!(fills[0].shares, dec!(6));
+        assert_eq!(model.resting_price(Side::Buy), None);
+
+        // 改价则重新排队并按新份数挂单
+        model.place(Side::Buy, dec!(0.49), dec!(10));
+        model.place(Side::Buy, dec!(0.48), dec!(10));
+        let order = model.bid.as_ref().unwrap();
+        assert_eq!((order.remaining, order.queue_ahead), (dec!(10), dec!(50)));
+    }
+}
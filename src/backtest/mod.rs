@@ -0,0 +1,335 @@
+// File: src/backtest/mod.rs
+// 确定性回测：按录制时间顺序把盘口快照喂给报价模型 (Strategy) + RiskManager，挂单成交由 fill_model 模拟
+// 时钟完全由数据驱动 (SimClock 按录制时间推进)，同样的数据与参数总是得到同样的结果
+// 假设：Opinion 上的成交流与参考市场 (Polymarket) 一致；不模拟对冲腿；报价阶梯只模拟第 0 档
+pub mod fill_model;
+pub mod sweep;
+
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::sync::Arc;
+
+use crate::backtest::fill_model::{QueueFillModel, SimFill};
+use crate::config::{MarketSpec, RiskConfig};
+use crate::core::{Exchange, OrderBookUpdate, Side, StageStamps, TradeSignal, LOGIC_TAG_QUOTE};
+use crate::infrastructure::clock::{SharedClock, SimClock};
+use crate::infrastructure::journal::{JournalEntry, JournalReader};
+use crate::model::strategy::{build_strategy, Strategy};
+use crate::model::portfolio::Portfolio;
+use crate::model::risk::RiskManager;
+use crate::venues::polymarket::parse_asset_id;
+use crate::venues::rules::MarketRules;
+
+/// 回测输入：(录制时间 ns, 盘口快照)，按时间排序
+pub type BookTape = Vec<(i64, OrderBookUpdate)>;
+
+/// 从日志读取时间范围内的所有盘口快照
+pub fn load_tape(dir: &str, from_ns: i64, to_ns: i64) -> Result<BookTape, String> {
+    let mut tape = Vec::new();
+    for record in JournalReader::open(dir).range(from_ns, to_ns)? {
+        let record = record?;
+        if let JournalEntry::Book(book) = record.entry {
+            tape.push((record.recorded_ns, *book));
+        }
+    }
+    // 稳定排序：同一时刻的快照保持录制顺序
+    tape.sort_by_key(|(ts, _)| *ts);
+    Ok(tape)
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct MarketReport {
+    pub market_id: u64,
+    pub quotes_placed: u64, // 新挂单 (含改价) 次数
+    pub fills: u64,
+    pub taker_fills: u64,
+    pub bought_shares: f64,
+    pub sold_shares: f64,
+    pub max_abs_inventory: f64,
+    pub final_inventory: f64,
+    pub final_cash: f64,
+    pub ignored_ladder_quotes: u64, // 第 1 档及以外的报价次数 (只模拟第 0 档，这些报价不参与撮合)
+}
+
+/// 每处理一次盘口后的状态 (库存路径 + 组合权益曲线)
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct PathPoint {
+    pub ts_ns: i64,
+    pub market_id: u64,
+    pub mid: f64,
+    pub inventory: f64,
+    pub cash: f64,
+    pub total_pnl: f64, // 组合累计盈亏 (RiskManager 口径)
+    pub drawdown: f64,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct BacktestReport {
+    pub books: usize,
+    pub start_ns: i64,
+    pub end_ns: i64,
+    pub total_pnl: f64,
+    pub max_drawdown: f64,
+    pub kill_switch_at_ns: Option<i64>,
+    pub quotes_placed: u64,
+    pub fills: u64,
+    pub fill_rate: f64, // fills / quotes_placed
+    pub ignored_ladder_quotes: u64, // > 0 时成交和 PnL 都没有包含更深档位的贡献
+    pub markets: Vec<MarketReport>,
+    #[serde(skip)]
+    pub path: Vec<PathPoint>,
+}
+
+impl BacktestReport {
+    /// 把库存路径 / 权益曲线写成 CSV
+    pub fn write_path_csv(&self, path: &str) -> Result<(), String> {
+        let file = File::create(path).map_err(|e| format!("Cannot create {}: {}", path, e))?;
+        let mut out = BufWriter::new(file);
+        let mut write = || -> std::io::Result<()> {
+            writeln!(out, "ts_ns,market_id,mid,inventory,cash,total_pnl,drawdown")?;
+            for p in &self.path {
+                writeln!(out, "{},{},{},{},{},{},{}", p.ts_ns, p.market_id, p.mid, p.inventory, p.cash, p.total_pnl, p.drawdown)?;
+            }
+            out.flush()
+        };
+        write().map_err(|e| format!("Cannot write {}: {}", path, e))
+    }
+}
+
+// 单个市场的回测状态 (报价模型与账本在 Portfolio 里，风控从那里读持仓)
+struct MarketSim {
+    order_size_usd: Decimal,
+    rules: MarketRules,
+    model: QueueFillModel,
+    report: MarketReport,
+}
+
+impl MarketSim {
+    fn apply_fill(&mut self, strategy: &mut dyn Strategy, fill: SimFill) {
+        let shares = fill.shares.to_f64().unwrap_or(0.0);
+        let gross = (fill.price * fill.shares).to_f64().unwrap_or(0.0);
+        let (change, cash_flow) = match fill.side {
+            Side::Buy => (shares, -gross),
+            Side::Sell => (-shares, gross),
+        };
+        // 成交后的即时改价 (on_fill 返回的报价) 不单独模拟：同一个盘口的第 4 步或下一个盘口会重新报价
+        let _ = strategy.on_fill(change, cash_flow);
+
+        let r = &mut self.report;
+        r.fills += 1;
+        r.taker_fills += fill.taker as u64;
+        match fill.side {
+            Side::Buy => r.bought_shares += shares,
+            Side::Sell => r.sold_shares += shares,
+        }
+        r.max_abs_inventory = r.max_abs_inventory.max(strategy.ledger().current_inventory_shares.abs());
+    }
+}
+
+/// 运行一次回测 (纯函数：不读写文件、不连接总线)
+pub fn run_backtest(specs: &[MarketSpec], risk_cfg: &RiskConfig, tape: &[(i64, OrderBookUpdate)]) -> BacktestReport {
+    let sim_clock = Arc::new(SimClock::default());
+    let clock: SharedClock = sim_clock.clone();
+    let mut portfolio = Portfolio::new();
+    let mut markets: HashMap<u64, MarketSim> = specs.iter()
+        .filter_map(|spec| {
+            let ref_id = parse_asset_id(&spec.poly_asset_id)?.low_u64();
+            portfolio.add_market(ref_id, build_strategy(spec.opinion_market_id, spec.config.clone(), spec.rules.clone(), None, clock.clone()));
+            Some((ref_id, MarketSim {
+                order_size_usd: spec.order_size_usd,
+                rules: spec.rules.with_default_tick(spec.config.tick_size),
+                model: QueueFillModel::default(),
+                report: MarketReport { market_id: spec.opinion_market_id, ..Default::default() },
+            }))
+        })
+        .collect();
+    let mut risk = RiskManager::new(risk_cfg, clock);
+    let mut report = BacktestReport::default();
+
+    for (ts_ns, book) in tape {
+        sim_clock.set(*ts_ns);
+        // 只用参考行情 (Polymarket) 驱动；录制里的 Opinion 盘口按 Opinion Market ID 编号，不能混用
+        if book.exchange != Exchange::Polymarket {
+            continue;
+        }
+        let Some(sim) = markets.get_mut(&book.symbol_id) else { continue };
+        let (Some(&(best_bid, _)), Some(&(best_ask, _))) = (book.bids.first(), book.asks.first()) else { continue };
+        if best_bid.is_zero() || best_ask.is_zero() {
+            continue;
+        }
+        let mid = (best_bid + best_ask) / Decimal::TWO;
+        let mid_f64 = mid.to_f64().unwrap_or(0.0);
+        let market_id = sim.report.market_id;
+        portfolio.mark(book.symbol_id, mid_f64);
+
+        if report.books == 0 {
+            report.start_ns = *ts_ns;
+        }
+        report.books += 1;
+        report.end_ns = *ts_ns;
+
+        // 1. 熔断之后不再挂单 (每个市场在自己的下一次行情时撤单)
+        if risk.is_kill_switch_active {
+            sim.model.cancel(Side::Buy);
+            sim.model.cancel(Side::Sell);
+        }
+
+        // 2. 上一轮的挂单先和这一轮推断出的成交撮合
+        let Some(strategy) = portfolio.by_ref_mut(book.symbol_id) else { continue };
+        for fill in sim.model.on_book(book) {
+            sim.apply_fill(strategy, fill);
+        }
+
+        // 3. 盯市 + 回撤检查 (与引擎相同的口径)
+        let pnl_change = strategy.ledger_mut().calculate_equity_change(mid_f64);
+        risk.update_pnl_and_check_kill(pnl_change);
+        report.max_drawdown = report.max_drawdown.max(risk.current_drawdown);
+
+        // 4. 重新报价：通过风控的一边挂单，否则撤掉该边
+        if !risk.is_kill_switch_active {
+            let mut quote = strategy.on_book(mid, sim.order_size_usd);
+            let inventory = strategy.ledger().current_inventory_shares;
+            if risk.reduce_only(&portfolio, market_id) {
+                quote.reduce_only(inventory);
+            }
+            // 与引擎相同的不穿价保护 (回测里行情盘口就是模拟的报价场馆)
+            quote.keep_passive(Some(best_bid), Some(best_ask), &sim.rules);
+            sim.report.ignored_ladder_quotes += (quote.bids.len().saturating_sub(1) + quote.asks.len().saturating_sub(1)) as u64;
+            for (side, top) in [(Side::Buy, quote.bids.first()), (Side::Sell, quote.asks.first())] {
+                let (price, size_usd) = top.map(|l| (l.price, l.size_usd)).unwrap_or_default();
+                let signal = TradeSignal {
+                    strategy_id: 1,
+                    target_exchange: Exchange::OpinionLabs,
+                    symbol_id: market_id,
+                    side,
+                    price,
+                    size_usd,
+                    logic_tag: LOGIC_TAG_QUOTE,
+                    post_only: false,
+                    level: 0,
+                    created_at_ns: *ts_ns,
+                    trace_id: book.trace_id,
+                    stamps: StageStamps::default(),
+                };
+                if price.is_zero() || size_usd.is_zero() || !risk.check_quote(&signal, &portfolio, 0.0) {
+                    sim.model.cancel(side);
+                    continue;
+                }
+                if sim.model.resting_price(side) != Some(price) {
+                    sim.report.quotes_placed += 1;
+                }
+                let shares = (size_usd / price).round_dp(6);
+                if let Some(fill) = sim.model.place(side, price, shares) {
+                    let Some(strategy) = portfolio.by_ref_mut(book.symbol_id) else { continue };
+                    sim.apply_fill(strategy, fill);
+                }
+            }
+        }
+
+        let (inventory, cash) = portfolio.by_opinion(market_id)
+            .map_or((0.0, 0.0), |s| (s.ledger().current_inventory_shares, s.ledger().current_cash_balance));
+        report.path.push(PathPoint {
+            ts_ns: *ts_ns,
+            market_id,
+            mid: mid_f64,
+            inventory,
+            cash,
+            total_pnl: risk.total_pnl,
+            drawdown: risk.current_drawdown,
+        });
+    }
+
+    report.total_pnl = risk.total_pnl;
+    report.kill_switch_at_ns = risk.kill_switch_at_ns;
+    let mut market_reports: Vec<MarketReport> = markets.into_values()
+        .map(|sim| {
+            let ledger = portfolio.by_opinion(sim.report.market_id).map(|s| s.ledger());
+            MarketReport {
+                final_inventory: ledger.map_or(0.0, |l| l.current_inventory_shares),
+                final_cash: ledger.map_or(0.0, |l| l.current_cash_balance),
+                ..sim.report
+            }
+        })
+        .collect();
+    market_reports.sort_by_key(|r| r.market_id);
+    report.quotes_placed = market_reports.iter().map(|r| r.quotes_placed).sum();
+    report.fills = market_reports.iter().map(|r| r.fills).sum();
+    report.ignored_ladder_quotes = market_reports.iter().map(|r| r.ignored_ladder_quotes).sum();
+    if report.quotes_placed > 0 {
+        report.fill_rate = report.fills as f64 / report.quotes_placed as f64;
+    }
+    report.markets = market_reports;
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::strategy::{StrategyConfig, StrategyModel};
+    use rust_decimal_macros::dec;
+
+    fn book(bids: &[(Decimal, Decimal)], asks: &[(Decimal, Decimal)]) -> OrderBookUpdate {
+        OrderBookUpdate {
+            exchange: Exchange::Polymarket,
+            symbol_id: 123,
+            timestamp_ns: 0,
+            trace_id: 0,
+            bids: bids.iter().copied().collect(),
+            asks: asks.iter().copied().collect(),
+            stamps: StageStamps::default(),
+        }
+    }
+
+    #[test]
+    fn tiny_tape_fill_is_marked_to_market_in_pnl() {
+        // 网格模型：中间价 0.50 两侧各 2 tick -> 买 0.48 / 卖 0.52
+        let spec = MarketSpec {
+            poly_asset_id: "123".to_string(),
+            opinion_market_id: 7,
+            order_size_usd: dec!(10),
+            config: StrategyConfig { model: StrategyModel::Grid, maturity_timestamp_ms: i64::MAX, ..Default::default() },
+            rules: MarketRules::default(),
+        };
+        let bids = [(dec!(0.49), dec!(100)), (dec!(0.48), dec!(50))];
+        let tape = vec![
+            (1_000_000_000, book(&bids, &[(dec!(0.51), dec!(100)), (dec!(0.52), dec!(50)), (dec!(0.53), dec!(40))])),
+            // 卖盘被扫到 0.53：越过我们 0.52 的卖单 -> 全部成交
+            (2_000_000_000, book(&bids, &[(dec!(0.53), dec!(10))])),
+        ];
+
+        let report = run_backtest(&[spec], &RiskConfig::default(), &tape);
+        let m = &report.markets[0];
+        assert_eq!((report.books, m.fills, m.taker_fills), (2, 1, 0));
+        assert_eq!(m.bought_shares, 0.0);
+        assert!(m.sold_shares > 0.0);
+        assert_eq!(m.final_inventory, -m.sold_shares);
+        assert!((m.final_cash - m.sold_shares * 0.52).abs() < 1e-9);
+
+        // 盯市在新中间价 0.51：第一笔成交的价差收益 (0.52 - 0.51) 计入 PnL
+        assert!((report.total_pnl - (m.final_cash + m.final_inventory * 0.51)).abs() < 1e-9);
+        assert!((report.total_pnl - m.sold_shares * 0.01).abs() < 1e-9);
+        assert_eq!(report.max_drawdown, 0.0);
+        let last = report.path.last().unwrap();
+        assert_eq!((last.mid, last.inventory), (0.51, m.final_inventory));
+    }
+
+    #[test]
+    fn deeper_ladder_levels_are_reported_as_ignored() {
+        let spec = |ladder_levels| MarketSpec {
+            poly_asset_id: "123".to_string(),
+            opinion_market_id: 7,
+            order_size_usd: dec!(10),
+            config: StrategyConfig { model: StrategyModel::Grid, maturity_timestamp_ms: i64::MAX, ladder_levels, ..Default::default() },
+            rules: MarketRules::default(),
+        };
+        let tape = vec![(1_000_000_000, book(&[(dec!(0.49), dec!(100))], &[(dec!(0.51), dec!(100))]))];
+        assert_eq!(run_backtest(&[spec(1)], &RiskConfig::default(), &tape).ignored_ladder_quotes, 0);
+        // 3 档：每边 2 个更深的档位没有模拟
+        let report = run_backtest(&[spec(3)], &RiskConfig::default(), &tape);
+        assert_eq!((report.ignored_ladder_quotes, report.markets[0].ignored_ladder_quotes), (4, 4));
+    }
+}
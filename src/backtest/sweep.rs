@@ -0,0 +1,479 @@
+// File: src/backtest/sweep.rs
+// 参数扫描：在 StrategyConfig 的参数空间上批量回测 (网格 / 随机搜索)，多核并行，按指标排序
+// 支持 Walk-Forward：按时间把数据切成 folds + 1 段，第 i 段之前的数据选参数，第 i 段做样本外检验
+//
+// 扫描文件示例 (TOML):
+//   mode = "grid"        # grid | random
+//   samples = 100        # random 模式的抽样次数
+//   seed = 42
+//   folds = 3            # 0 = 不切分，全部数据排名
+//   rank_by = "sharpe"   # sharpe | pnl | calmar
+//   sharpe_interval_secs = 60
+//   [params]
+//   risk_aversion_gamma = [0.01, 0.05, 0.1]               # 候选值列表
+//   liquidity_k = { min = 1000, max = 20000, steps = 4, log = true } # 区间 (grid 模式必须给 steps)
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use crate::backtest::{run_backtest, BacktestReport};
+use crate::config::{MarketSpec, RiskConfig};
+use crate::core::OrderBookUpdate;
+use crate::model::strategy::StrategyConfig;
+use crate::reload::patch_strategy;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchMode {
+    #[default]
+    Grid,
+    Random,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RankBy {
+    #[default]
+    Sharpe,
+    Pnl,
+    Calmar, // PnL / 最大回撤
+}
+
+/// 单个参数的取值空间
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ParamSpace {
+    Values(Vec<toml::Value>),
+    Range {
+        min: f64,
+        max: f64,
+        #[serde(default)]
+        steps: Option<usize>,
+        #[serde(default)]
+        log: bool, // 按对数均匀取值 (gamma、k 这类跨数量级的参数)
+    },
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SweepSpec {
+    pub mode: SearchMode,
+    pub samples: usize,
+    pub seed: u64,
+    pub folds: usize,
+    pub rank_by: RankBy,
+    pub sharpe_interval_secs: u64,
+    pub params: BTreeMap<String, ParamSpace>,
+}
+
+impl Default for SweepSpec {
+    fn default() -> Self {
+        Self {
+            mode: SearchMode::Grid,
+            samples: 50,
+            seed: 42,
+            folds: 0,
+            rank_by: RankBy::Sharpe,
+            sharpe_interval_secs: 60,
+            params: BTreeMap::new(),
+        }
+    }
+}
+
+impl SweepSpec {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let content = fs::read_to_string(path).map_err(|e| format!("Cannot read sweep file {}: {}", path, e))?;
+        let spec: SweepSpec = toml::from_str(&content).map_err(|e| format!("Invalid sweep file {}: {}", path, e))?;
+        spec.validate().map_err(|e| format!("Invalid sweep file {}: {}", path, e))?;
+        Ok(spec)
+    }
+
+    /// 解析之后的取值检查 (保证 candidates 至少有一组、区间都能取值)
+    pub fn validate(&self) -> Result<(), String> {
+        if self.params.is_empty() {
+            return Err("[params] is empty".to_string());
+        }
+        if self.mode == SearchMode::Random && self.samples == 0 {
+            return Err("samples must be > 0 in random mode".to_string());
+        }
+        if self.sharpe_interval_secs == 0 {
+            return Err("sharpe_interval_secs must be > 0".to_string());
+        }
+        if self.params.contains_key("ladder_levels") {
+            return Err("params.ladder_levels cannot be swept: the backtest only simulates ladder level 0".to_string());
+        }
+        for (key, space) in &self.params {
+            match space {
+                ParamSpace::Values(v) if v.is_empty() => return Err(format!("params.{} has no values", key)),
+                ParamSpace::Range { min, max, .. } if min > max => {
+                    return Err(format!("params.{}: min ({}) must be <= max ({})", key, min, max));
+                }
+                ParamSpace::Range { min, log: true, .. } if *min <= 0.0 => {
+                    return Err(format!("params.{}: a log range needs min > 0 (got {})", key, min));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// 展开成候选参数组 (每组是叠加在各市场现有参数之上的覆盖表)
+    pub fn candidates(&self) -> Result<Vec<toml::Table>, String> {
+        let defaults = toml::Table::try_from(StrategyConfig::default()).map_err(|e| e.to_string())?;
+        for key in self.params.keys() {
+            if !defaults.contains_key(key) {
+                return Err(format!("params: unknown strategy field '{}'", key));
+            }
+        }
+        // 整数字段 (min_spread_bps 等) 的区间取值需要取整；浮点保留 6 位有效数字，排名表更易读
+        let typed = |key: &str, x: f64| match defaults.get(key) {
+            Some(toml::Value::Integer(_)) => toml::Value::Integer(x.round() as i64),
+            _ => toml::Value::Float(format!("{:.5e}", x).parse().unwrap_or(x)),
+        };
+
+        match self.mode {
+            SearchMode::Grid => {
+                let mut out = vec![toml::Table::new()];
+                for (key, space) in &self.params {
+                    let values: Vec<toml::Value> = match space {
+                        ParamSpace::Values(v) => v.clone(),
+                        ParamSpace::Range { min, max, steps: Some(n), log } => {
+                            grid_points(*min, *max, *n, *log).into_iter().map(|x| typed(key, x)).collect()
+                        }
+                        ParamSpace::Range { steps: None, .. } => {
+                            return Err(format!("params.{}: grid mode needs `steps` for a range", key));
+                        }
+                    };
+                    out = out.into_iter()
+                        .flat_map(|base| values.iter().map(move |v| {
+                            let mut t = base.clone();
+                            t.insert(key.clone(), v.clone());
+                            t
+                        }))
+                        .collect();
+                }
+                Ok(out)
+            }
+            SearchMode::Random => {
+                let mut rng = StdRng::seed_from_u64(self.seed);
+                Ok((0..self.samples).map(|_| {
+                    self.params.iter().map(|(key, space)| {
+                        let value = match space {
+                            ParamSpace::Values(v) => v[rng.gen_range(0..v.len())].clone(),
+                            ParamSpace::Range { min, max, log, .. } => {
+                                let u: f64 = rng.gen();
+                                typed(key, if *log { (min.ln() + u * (max.ln() - min.ln())).exp() } else { min + u * (max - min) })
+                            }
+                        };
+                        (key.clone(), value)
+                    }).collect()
+                }).collect())
+            }
+        }
+    }
+}
+
+fn grid_points(min: f64, max: f64, steps: usize, log: bool) -> Vec<f64> {
+    if steps <= 1 {
+        return vec![min];
+    }
+    (0..steps).map(|i| {
+        let u = i as f64 / (steps - 1) as f64;
+        if log { (min.ln() + u * (max.ln() - min.ln())).exp() } else { min + u * (max - min) }
+    }).collect()
+}
+
+/// 参数组的单行展示: "k1=v1 k2=v2"
+pub fn format_params(params: &toml::Table) -> String {
+    params.iter().map(|(k, v)| format!("{}={}", k, v)).collect::<Vec<_>>().join(" ")
+}
+
+/// 一次回测的评价指标
+#[derive(Clone, Copy, Debug, Default, Serialize)]
+pub struct RunMetrics {
+    pub pnl: f64,
+    pub sharpe: f64, // 按 sharpe_interval_secs 采样 PnL 增量，折算到日
+    pub max_drawdown: f64,
+    pub inventory_variance: f64, // 各市场库存方差之和
+    pub fills: u64,
+    pub fill_rate: f64,
+    pub killed: bool,
+}
+
+impl RunMetrics {
+    pub fn from_report(report: &BacktestReport, interval_ns: i64) -> Self {
+        Self {
+            pnl: report.total_pnl,
+            sharpe: sharpe(report, interval_ns),
+            max_drawdown: report.max_drawdown,
+            inventory_variance: inventory_variance(report),
+            fills: report.fills,
+            fill_rate: report.fill_rate,
+            killed: report.kill_switch_at_ns.is_some(),
+        }
+    }
+
+    fn score(&self, rank_by: RankBy) -> f64 {
+        match rank_by {
+            RankBy::Sharpe => self.sharpe,
+            RankBy::Pnl => self.pnl,
+            RankBy::Calmar if self.max_drawdown > 0.0 => self.pnl / self.max_drawdown,
+            RankBy::Calmar => self.pnl,
+        }
+    }
+}
+
+fn sharpe(report: &BacktestReport, interval_ns: i64) -> f64 {
+    // 每个采样区间末的累计盈亏 (空区间沿用上一个值)
+    let mut closes: Vec<f64> = Vec::new();
+    for p in &report.path {
+        let bucket = ((p.ts_ns - report.start_ns) / interval_ns) as usize;
+        let last = closes.last().copied().unwrap_or(0.0);
+        closes.resize(bucket + 1, last);
+        closes[bucket] = p.total_pnl;
+    }
+    let deltas: Vec<f64> = std::iter::once(0.0).chain(closes.iter().copied())
+        .collect::<Vec<_>>()
+        .windows(2)
+        .map(|w| w[1] - w[0])
+        .collect();
+    let (mean, var) = mean_var(&deltas);
+    if var <= 0.0 {
+        return 0.0;
+    }
+    mean / var.sqrt() * (86_400e9 / interval_ns as f64).sqrt()
+}
+
+fn inventory_variance(report: &BacktestReport) -> f64 {
+    let mut by_market: BTreeMap<u64, Vec<f64>> = BTreeMap::new();
+    for p in &report.path {
+        by_market.entry(p.market_id).or_default().push(p.inventory);
+    }
+    by_market.values().map(|xs| mean_var(xs).1).sum()
+}
+
+fn mean_var(xs: &[f64]) -> (f64, f64) {
+    if xs.is_empty() {
+        return (0.0, 0.0);
+    }
+    let n = xs.len() as f64;
+    let mean = xs.iter().sum::<f64>() / n;
+    let var = xs.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n;
+    (mean, var)
+}
+
+/// 一组候选参数的结果
+#[derive(Clone, Debug, Serialize)]
+pub struct SweepRow {
+    pub params: toml::Table,
+    pub metrics: RunMetrics,
+}
+
+/// Walk-Forward 单折结果：样本内选出的参数 + 样本外表现
+#[derive(Clone, Debug, Serialize)]
+pub struct FoldResult {
+    pub fold: usize,
+    pub train_books: usize,
+    pub test_books: usize,
+    pub chosen: SweepRow,          // 样本内最优
+    pub out_of_sample: RunMetrics, // 同一组参数在下一段数据上的表现
+}
+
+/// 多核并行地跑所有候选参数 (结果顺序与 candidates 一致)
+fn evaluate_all(
+    candidates: &[toml::Table],
+    specs: &[MarketSpec],
+    risk: &RiskConfig,
+    tape: &[(i64, OrderBookUpdate)],
+    interval_ns: i64,
+    threads: usize,
+) -> Result<Vec<SweepRow>, String> {
+    // 先在主线程把参数套到每个市场上 (非法组合直接报错，不浪费算力)
+    let patched: Vec<Vec<MarketSpec>> = candidates.iter()
+        .map(|params| specs.iter()
+            .map(|spec| Ok(MarketSpec { config: patch_strategy(&spec.config, params)?, ..spec.clone() }))
+            .collect::<Result<Vec<_>, String>>()
+            .map_err(|e| format!("params {}: {}", params, e)))
+        .collect::<Result<_, _>>()?;
+
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<RunMetrics>>> = Mutex::new(vec![None; candidates.len()]);
+    std::thread::scope(|s| {
+        for _ in 0..threads.max(1) {
+            s.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let Some(market_specs) = patched.get(i) else { break };
+                let report = run_backtest(market_specs, risk, tape);
+                results.lock().unwrap()[i] = Some(RunMetrics::from_report(&report, interval_ns));
+            });
+        }
+    });
+
+    Ok(candidates.iter().cloned()
+        .zip(results.into_inner().unwrap())
+        .map(|(params, metrics)| SweepRow { params, metrics: metrics.unwrap_or_default() })
+        .collect())
+}
+
+fn rank(rows: &mut [SweepRow], rank_by: RankBy) {
+    rows.sort_by(|a, b| b.metrics.score(rank_by).total_cmp(&a.metrics.score(rank_by)));
+}
+
+pub enum SweepOutcome {
+    Ranked(Vec<SweepRow>),
+    WalkForward(Vec<FoldResult>),
+}
+
+/// 运行扫描：folds = 0 时对全部数据排名，否则做锚定式 Walk-Forward (训练集逐段扩大)
+pub fn run_sweep(
+    spec: &SweepSpec,
+    specs: &[MarketSpec],
+    risk: &RiskConfig,
+    tape: &[(i64, OrderBookUpdate)],
+    threads: usize,
+) -> Result<SweepOutcome, String> {
+    let candidates = spec.candidates()?;
+    let interval_ns = spec.sharpe_interval_secs as i64 * 1_000_000_000;
+
+    if spec.folds == 0 {
+        let mut rows = evaluate_all(&candidates, specs, risk, tape, interval_ns, threads)?;
+        rank(&mut rows, spec.rank_by);
+        return Ok(SweepOutcome::Ranked(rows));
+    }
+
+    // 按时间等分成 folds + 1 段
+    let (Some(first), Some(last)) = (tape.first(), tape.last()) else {
+        return Err("no book data in the selected range".to_string());
+    };
+    let (start, span) = (first.0, (last.0 - first.0).max(1));
+    let segments = spec.folds as i64 + 1;
+    let boundary = |k: i64| tape.partition_point(|(ts, _)| (ts - start) as i128 * (segments as i128) < (span as i128) * (k as i128));
+
+    let mut folds = Vec::new();
+    for k in 1..segments {
+        let (split, end) = (boundary(k), if k + 1 == segments { tape.len() } else { boundary(k + 1) });
+        let (train, test) = (&tape[..split], &tape[split..end]);
+        if train.is_empty() || test.is_empty() {
+            return Err(format!("fold {} has no data; use fewer folds or a longer range", k));
+        }
+
+        let mut rows = evaluate_all(&candidates, specs, risk, train, interval_ns, threads)?;
+        rank(&mut rows, spec.rank_by);
+        let Some(chosen) = rows.into_iter().next() else {
+            return Err("no candidate parameters".to_string());
+        };
+        let test_specs: Vec<MarketSpec> = specs.iter()
+            .map(|s| Ok(MarketSpec { config: patch_strategy(&s.config, &chosen.params)?, ..s.clone() }))
+            .collect::<Result<_, String>>()?;
+        let out_of_sample = RunMetrics::from_report(&run_backtest(&test_specs, risk, test), interval_ns);
+
+        folds.push(FoldResult { fold: k as usize, train_books: train.len(), test_books: test.len(), chosen, out_of_sample });
+    }
+    Ok(SweepOutcome::WalkForward(folds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtest::PathPoint;
+    use crate::core::{Exchange, StageStamps};
+
+    fn spec(raw: &str) -> Result<SweepSpec, String> {
+        let spec: SweepSpec = toml::from_str(raw).map_err(|e| e.to_string())?;
+        spec.validate()?;
+        Ok(spec)
+    }
+
+    #[test]
+    fn invalid_spaces_are_rejected_at_parse_time() {
+        let err = |raw: &str| spec(raw).unwrap_err();
+        assert_eq!(err("mode = \"random\"\nsamples = 0\nfolds = 2\n[params]\nmin_spread_bps = [50]"), "samples must be > 0 in random mode");
+        assert!(err("[params]\nliquidity_k = { min = 2000, max = 1000, steps = 2 }").contains("params.liquidity_k: min"));
+        assert!(err("[params]\nliquidity_k = { min = 0, max = 1000, steps = 2, log = true }").contains("log range needs min > 0"));
+        assert!(err("[params]\nmin_spread_bps = []").contains("has no values"));
+        assert!(err("[params]").contains("empty"));
+        assert!(err("[params]\nladder_levels = [1, 3]").contains("level 0"));
+        // grid 模式不看 samples
+        assert!(spec("samples = 0\n[params]\nmin_spread_bps = [50]").is_ok());
+    }
+
+    #[test]
+    fn grid_mode_expands_the_cartesian_product() {
+        let s = spec("[params]\nrisk_aversion_gamma = [0.01, 0.1]\nmin_spread_bps = { min = 10, max = 30, steps = 3 }\nliquidity_k = { min = 1000, max = 100000, steps = 3, log = true }").unwrap();
+        let candidates = s.candidates().unwrap();
+        assert_eq!(candidates.len(), 2 * 3 * 3);
+        let values = |key: &str| {
+            let mut v: Vec<String> = candidates.iter().map(|c| c[key].to_string()).collect();
+            v.sort();
+            v.dedup();
+            v
+        };
+        // 整数字段取整，对数区间按数量级均匀
+        assert_eq!(values("min_spread_bps"), ["10", "20", "30"]);
+        assert_eq!(values("liquidity_k"), ["1000.0", "10000.0", "100000.0"]);
+        assert_eq!(values("risk_aversion_gamma"), ["0.01", "0.1"]);
+
+        assert!(spec("[params]\ngamma = [1.0]").unwrap().candidates().unwrap_err().contains("unknown strategy field"));
+        assert!(spec("[params]\nliquidity_k = { min = 1, max = 2 }").unwrap().candidates().unwrap_err().contains("needs `steps`"));
+    }
+
+    #[test]
+    fn random_mode_is_reproducible_for_a_seed() {
+        let raw = |seed: u64| format!("mode = \"random\"\nsamples = 20\nseed = {}\n[params]\nliquidity_k = {{ min = 1000, max = 20000, log = true }}\nmin_spread_bps = [40, 60]", seed);
+        let a = spec(&raw(7)).unwrap().candidates().unwrap();
+        assert_eq!(a.len(), 20);
+        assert_eq!(a, spec(&raw(7)).unwrap().candidates().unwrap());
+        assert_ne!(a, spec(&raw(8)).unwrap().candidates().unwrap());
+        for c in &a {
+            let k = c["liquidity_k"].as_float().unwrap();
+            assert!((1000.0..=20000.0).contains(&k), "{}", k);
+            assert!(matches!(c["min_spread_bps"].as_integer(), Some(40 | 60)));
+        }
+    }
+
+    fn report(pnl: &[f64]) -> BacktestReport {
+        let path = pnl.iter().enumerate()
+            .map(|(i, &total_pnl)| PathPoint { ts_ns: i as i64 * 1_000_000_000, market_id: 1, mid: 0.5, inventory: 0.0, cash: 0.0, total_pnl, drawdown: 0.0 })
+            .collect();
+        BacktestReport { path, ..Default::default() }
+    }
+
+    #[test]
+    fn sharpe_is_zero_without_variance() {
+        let interval = 1_000_000_000;
+        assert_eq!(sharpe(&report(&[]), interval), 0.0);
+        assert_eq!(sharpe(&report(&[0.0; 10]), interval), 0.0);
+        // 每个区间赚同样多：方差为 0，不除零
+        assert_eq!(sharpe(&report(&[1.0, 2.0, 3.0, 4.0]), interval), 0.0);
+
+        let s = sharpe(&report(&[1.0, 3.0, 4.0, 6.0]), interval);
+        assert!(s.is_finite() && s > 0.0, "{}", s);
+    }
+
+    #[test]
+    fn walk_forward_splits_the_tape_by_time() {
+        // 10 条快照，1 秒一条；2 折 -> 3 段，分界点在 3 秒和 6 秒
+        let tape: Vec<(i64, OrderBookUpdate)> = (0..10)
+            .map(|i| (i * 1_000_000_000, OrderBookUpdate {
+                exchange: Exchange::Polymarket,
+                symbol_id: 1,
+                timestamp_ns: 0,
+                trace_id: 0,
+                bids: Default::default(),
+                asks: Default::default(),
+                stamps: StageStamps::default(),
+            }))
+            .collect();
+        let s = spec("folds = 2\n[params]\nmin_spread_bps = [50, 60]").unwrap();
+        let Ok(SweepOutcome::WalkForward(folds)) = run_sweep(&s, &[], &RiskConfig::default(), &tape, 2) else { panic!("expected walk-forward") };
+        let splits: Vec<_> = folds.iter().map(|f| (f.fold, f.train_books, f.test_books)).collect();
+        assert_eq!(splits, [(1, 3, 3), (2, 6, 4)]);
+
+        let s = spec("folds = 20\n[params]\nmin_spread_bps = [50]").unwrap();
+        assert!(run_sweep(&s, &[], &RiskConfig::default(), &tape, 2).is_err_and(|e| e.contains("has no data")));
+    }
+}
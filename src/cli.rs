@@ -0,0 +1,121 @@
+// File: src/cli.rs
+// 命令行定义：每个组件都可以单独启动，通过现有的 ZMQ 拓扑跨主机组网
+// 例如: 主机 A 跑 `feed poly` + `engine`，主机 B 跑 `exec` (bus.* 指向对方地址即可)
+use clap::{Parser, Subcommand, ValueEnum};
+
+#[derive(Parser, Debug)]
+#[command(name = "enterprise_market_maker", version, about = "Polymarket -> Opinion cross-venue market maker")]
+pub struct Cli {
+    /// 配置文件路径
+    #[arg(short, long, global = true, env = "MM_CONFIG", default_value = "config.toml")]
+    pub config: String,
+
+    /// 不带子命令时等同于 `all`
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// 行情/成交数据源 (发布到 bus.market_data_bind)
+    Feed {
+        #[command(subcommand)]
+        source: FeedSource,
+    },
+    /// 策略引擎 (订阅行情，发布交易信号；支持参数热更新)
+    Engine,
+    /// 执行层 (订阅交易信号，签名并下单)
+    Exec,
+    /// 单进程启动所有组件
+    All,
+    /// 录制总线上的行情 / 成交 / 信号到压缩日志 (recorder.dir)
+    Record,
+    /// 读取录制的日志
+    Journal {
+        #[command(subcommand)]
+        action: JournalAction,
+    },
+    /// 一次性撤掉所有场馆的全部挂单
+    CancelAll,
+    /// 打印场馆余额、挂单与本地持仓状态
+    Status,
+    /// 用录制的盘口回测当前配置的策略与风控参数 (报告以 JSON 打印到 stdout)
+    Backtest {
+        /// 起始时间 (RFC 3339)；默认今天 0 点 (UTC)
+        #[arg(long)]
+        from: Option<String>,
+        /// 结束时间 (RFC 3339)；默认现在
+        #[arg(long)]
+        to: Option<String>,
+        /// 把库存路径 / 权益曲线写到 CSV 文件
+        #[arg(long)]
+        path_csv: Option<String>,
+    },
+    /// 参数扫描：在录制数据上批量回测 StrategyConfig 的参数组合 (网格 / 随机，支持 Walk-Forward)
+    Sweep {
+        /// 扫描定义文件 (TOML，格式见 src/backtest/sweep.rs)
+        spec: String,
+        /// 起始时间 (RFC 3339)；默认今天 0 点 (UTC)
+        #[arg(long)]
+        from: Option<String>,
+        /// 结束时间 (RFC 3339)；默认现在
+        #[arg(long)]
+        to: Option<String>,
+        /// 并行线程数 (默认 = CPU 核数)
+        #[arg(long)]
+        threads: Option<usize>,
+        /// 只打印排名前 N 的参数组
+        #[arg(long, default_value_t = 20)]
+        top: usize,
+    },
+    /// 把录制的盘口快照 (JSON Lines) 回放到行情总线
+    Replay {
+        /// 录制文件路径
+        file: String,
+        /// 回放倍速 (0 = 不等待，尽快发送)
+        #[arg(long, default_value_t = 1.0)]
+        speed: f64,
+    },
+    /// 配置管理
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum FeedSource {
+    /// Polymarket 盘口 + 对冲腿成交回报
+    Poly,
+    /// Opinion 盘口 + 成交回报
+    Opinion,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum JournalAction {
+    /// 按时间范围把记录打印为 JSON Lines (每行 {"recorded_ns", "kind", "data"})
+    Dump {
+        /// 起始时间 (RFC 3339，例如 2024-05-01T00:00:00Z)；默认今天 0 点 (UTC)
+        #[arg(long)]
+        from: Option<String>,
+        /// 结束时间 (RFC 3339)；默认现在
+        #[arg(long)]
+        to: Option<String>,
+        /// 只输出某一类记录
+        #[arg(long, value_enum)]
+        kind: Option<RecordFilter>,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum RecordFilter {
+    Book,
+    Inventory,
+    Signal,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigAction {
+    /// 校验配置文件并打印每个市场合并后的参数
+    Check,
+}
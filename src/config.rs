@@ -0,0 +1,462 @@
+// File: src/config.rs
+// 全系统声明式配置 (TOML)：端点、市场列表、策略参数、风控限额、对冲参数、持久化路径
+// 所有字段都有默认值 (与之前硬编码的值一致)，启动时统一校验，非法配置直接拒绝启动
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+
+use crate::gateway::recorder::RecorderConfig;
+use crate::infrastructure::clock::ClockMode;
+use crate::infrastructure::logging::LogConfig;
+use crate::infrastructure::metrics::MetricsConfig;
+use crate::model::strategy::{PriceSpace, StrategyConfig, StrategyModel};
+use crate::model::hedger::HedgeConfig;
+use crate::venues::paper::PaperConfig;
+use crate::venues::polymarket::parse_asset_id;
+use crate::venues::rules::{validate_ticks, MarketRules, TickBand};
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AppConfig {
+    pub log: LogConfig,
+    pub metrics: MetricsConfig,
+    pub bus: BusConfig,
+    pub venues: VenueConfig,
+    pub engine: EngineConfig,
+    pub risk: RiskConfig,
+    pub hedge: HedgeConfig,
+    pub recorder: RecorderConfig,
+    pub paper: PaperConfig,
+    /// 全局策略参数，只需写出与默认值不同的字段
+    pub strategy: toml::Table,
+    pub markets: Vec<MarketConfig>,
+}
+
+/// ZMQ 总线拓扑
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BusConfig {
+    pub market_data_bind: String,    // Feed 发布行情/成交 (PUB bind)
+    pub market_data_connect: String, // 引擎订阅行情/成交 (SUB connect)
+    pub signal_bind: String,         // 引擎发布交易信号 (PUB bind)
+    pub signal_connect: String,      // 执行层订阅交易信号 (SUB connect)
+}
+
+impl Default for BusConfig {
+    fn default() -> Self {
+        Self {
+            market_data_bind: "tcp://*:5555".to_string(),
+            market_data_connect: "tcp://localhost:5555".to_string(),
+            signal_bind: "tcp://*:5556".to_string(),
+            signal_connect: "tcp://localhost:5556".to_string(),
+        }
+    }
+}
+
+/// 场馆接入地址 (私钥等敏感信息仍然只从环境变量读取)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct VenueConfig {
+    pub poly_ws_url: String,
+    pub poly_clob_url: String,
+    pub opinion_api_url: String,
+    pub opinion_book_poll_ms: u64, // Opinion 盘口轮询间隔 (0 = 不订阅 Opinion 盘口)
+    pub open_orders_poll_ms: u64,  // 执行层挂单对账间隔，发现已成交的报价档位 (0 = 关闭)
+}
+
+impl Default for VenueConfig {
+    fn default() -> Self {
+        Self {
+            poly_ws_url: "wss://ws-poly.polymarket.com".to_string(),
+            poly_clob_url: "https://clob.polymarket.com".to_string(),
+            opinion_api_url: "https://api.opinionlabs.xyz".to_string(),
+            opinion_book_poll_ms: 500,
+            open_orders_poll_ms: 2000,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EngineConfig {
+    pub state_file: String,
+    pub audit_log: String,       // 参数热更新审计日志
+    pub order_size_usd: Decimal, // 默认单笔下单金额 (可按市场覆盖)
+    pub clock: ClockMode,        // system | replay (到期 / 清仓窗口按回放行情的时间计算)
+    pub post_only: bool,         // 报价以 post-only 发出 (场馆支持时)，穿价的报价由场馆拒绝而不是成交
+}
+
+impl Default for EngineConfig {
+    fn default() -> Self {
+        Self {
+            state_file: "./data/strategy_state.json".to_string(),
+            audit_log: "./data/param_audit.log".to_string(),
+            order_size_usd: dec!(50),
+            clock: ClockMode::System,
+            post_only: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RiskConfig {
+    pub max_drawdown_usd: f64,   // 最多允许亏损
+    pub max_order_size_usd: f64, // 单笔订单上限 (防肥手指)
+    // 持仓硬上限 (0 = 不限)：超过后拒绝任何加仓的报价
+    pub max_position_shares: f64,  // 单市场持仓份额
+    pub max_position_usd: f64,     // 单市场持仓市值
+    pub max_portfolio_shares: f64, // 组合 Σ|份额|
+    pub max_portfolio_usd: f64,    // 组合 Σ|市值|
+    pub soft_limit_ratio: f64,     // 达到 硬上限 x 该比例 后只报减仓一侧
+}
+
+impl Default for RiskConfig {
+    fn default() -> Self {
+        Self {
+            max_drawdown_usd: 100.0,
+            max_order_size_usd: 500.0,
+            max_position_shares: 0.0,
+            max_position_usd: 2500.0,
+            max_portfolio_shares: 0.0,
+            max_portfolio_usd: 10000.0,
+            soft_limit_ratio: 0.8,
+        }
+    }
+}
+
+/// 配置文件中的单个市场 (原始形式)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MarketConfig {
+    pub poly_asset_id: String,
+    pub opinion_market_id: u64,
+    #[serde(default)]
+    pub order_size_usd: Option<Decimal>,
+    /// 按市场覆盖的策略参数 (叠加在全局 [strategy] 之上)
+    #[serde(default)]
+    pub strategy: toml::Table,
+    /// Opinion 报价市场的分段 tick 表 (为空时整个价格区间都用 strategy.tick_size)
+    #[serde(default)]
+    pub ticks: Vec<TickBand>,
+    /// Opinion 报价市场的最小下单份额 (0 = 不限)
+    #[serde(default)]
+    pub min_size_shares: Decimal,
+}
+
+/// 单个做市市场的定义 (合并默认值之后)：参考价来自 Polymarket，报价发往 Opinion
+#[derive(Clone, Debug)]
+pub struct MarketSpec {
+    pub poly_asset_id: String,   // Polymarket Asset ID (参考行情)
+    pub opinion_market_id: u64,  // Opinion Market ID (报价市场)
+    pub order_size_usd: Decimal,
+    pub config: StrategyConfig,
+    pub rules: MarketRules,      // 报价市场的交易规则 (tick 表为空时用 config.tick_size)
+}
+
+impl AppConfig {
+    /// 读取并校验配置文件；任何错误都带上文件路径和字段名
+    pub fn load(path: &str) -> Result<Self, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Cannot read config file {}: {}", path, e))?;
+        let cfg: AppConfig = toml::from_str(&content)
+            .map_err(|e| format!("Invalid config file {}: {}", path, e))?;
+        cfg.validate().map_err(|e| format!("Invalid config file {}: {}", path, e))?;
+        Ok(cfg)
+    }
+
+    /// 合并全局与单市场覆盖，得到每个市场最终的策略参数
+    pub fn market_specs(&self) -> Result<Vec<MarketSpec>, String> {
+        self.markets.iter().map(|m| {
+            let config = merge_strategy(&[&self.strategy, &m.strategy])
+                .map_err(|e| format!("markets[{}].strategy: {}", m.opinion_market_id, e))?;
+            Ok(MarketSpec {
+                poly_asset_id: m.poly_asset_id.clone(),
+                opinion_market_id: m.opinion_market_id,
+                order_size_usd: m.order_size_usd.unwrap_or(self.engine.order_size_usd),
+                config,
+                rules: MarketRules { ticks: m.ticks.clone(), min_size_shares: m.min_size_shares },
+            })
+        }).collect()
+    }
+
+    /// 行情与对冲执行共用的 Polymarket Asset IDs
+    pub fn poly_asset_ids(&self) -> Vec<String> {
+        self.markets.iter().map(|m| m.poly_asset_id.clone()).collect()
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        // 1. 端点
+        for (name, value) in [
+            ("bus.market_data_bind", &self.bus.market_data_bind),
+            ("bus.market_data_connect", &self.bus.market_data_connect),
+            ("bus.signal_bind", &self.bus.signal_bind),
+            ("bus.signal_connect", &self.bus.signal_connect),
+            ("paper.fill_bind", &self.paper.fill_bind),
+            ("paper.fill_connect", &self.paper.fill_connect),
+        ] {
+            if !value.starts_with("tcp://") && !value.starts_with("ipc://") && !value.starts_with("inproc://") {
+                return Err(format!("{} must be a ZMQ endpoint (tcp:// | ipc:// | inproc://), got '{}'", name, value));
+            }
+        }
+        for (name, value) in [
+            ("venues.poly_ws_url", &self.venues.poly_ws_url),
+            ("venues.poly_clob_url", &self.venues.poly_clob_url),
+            ("venues.opinion_api_url", &self.venues.opinion_api_url),
+        ] {
+            url::Url::parse(value).map_err(|e| format!("{} is not a valid URL ('{}'): {}", name, value, e))?;
+        }
+
+        tracing_subscriber::EnvFilter::try_new(&self.log.level)
+            .map_err(|e| format!("log.level '{}' is not a valid filter: {}", self.log.level, e))?;
+        self.metrics.bind.parse::<std::net::SocketAddr>()
+            .map_err(|e| format!("metrics.bind must be <ip>:<port>, got '{}': {}", self.metrics.bind, e))?;
+
+        // 2. 引擎与风控
+        if self.engine.state_file.is_empty() {
+            return Err("engine.state_file must not be empty".to_string());
+        }
+        if self.recorder.dir.is_empty() {
+            return Err("recorder.dir must not be empty".to_string());
+        }
+        if self.recorder.flush_interval_ms == 0 {
+            return Err("recorder.flush_interval_ms must be > 0".to_string());
+        }
+        if self.paper.fee_bps > 10_000 {
+            return Err(format!("paper.fee_bps must be <= 10000, got {}", self.paper.fee_bps));
+        }
+        validate_risk(&self.risk).map_err(|e| format!("risk.{}", e))?;
+
+        // 3. 对冲
+        if self.hedge.threshold_shares <= 0.0 {
+            return Err(format!("hedge.threshold_shares must be > 0, got {}", self.hedge.threshold_shares));
+        }
+        if self.hedge.max_slippage < Decimal::ZERO || self.hedge.max_slippage >= Decimal::ONE {
+            return Err(format!("hedge.max_slippage must be in [0, 1), got {}", self.hedge.max_slippage));
+        }
+        if self.hedge.max_hedge_usd <= Decimal::ZERO {
+            return Err(format!("hedge.max_hedge_usd must be > 0, got {}", self.hedge.max_hedge_usd));
+        }
+        if self.hedge.pending_timeout_ms <= 0 {
+            return Err(format!("hedge.pending_timeout_ms must be > 0, got {}", self.hedge.pending_timeout_ms));
+        }
+
+        // 4. 市场
+        if self.markets.is_empty() {
+            return Err("at least one [[markets]] entry is required".to_string());
+        }
+        let mut seen_opinion = HashSet::new();
+        let mut seen_poly = HashSet::new();
+        for m in &self.markets {
+            let poly_id = parse_asset_id(&m.poly_asset_id)
+                .ok_or_else(|| format!("markets: poly_asset_id '{}' is not a decimal or 0x-hex token id", m.poly_asset_id))?;
+            if !seen_poly.insert(poly_id.low_u64()) {
+                return Err(format!("markets: duplicate poly_asset_id '{}'", m.poly_asset_id));
+            }
+            if !seen_opinion.insert(m.opinion_market_id) {
+                return Err(format!("markets: duplicate opinion_market_id {}", m.opinion_market_id));
+            }
+            validate_ticks(&m.ticks).map_err(|e| format!("markets[{}].ticks: {}", m.opinion_market_id, e))?;
+            if m.min_size_shares < Decimal::ZERO {
+                return Err(format!("markets[{}].min_size_shares must be >= 0, got {}", m.opinion_market_id, m.min_size_shares));
+            }
+        }
+
+        let global = merge_strategy(&[&self.strategy]).map_err(|e| format!("strategy: {}", e))?;
+        validate_strategy(&global).map_err(|e| format!("strategy.{}", e))?;
+
+        for spec in self.market_specs()? {
+            let order_size = spec.order_size_usd;
+            if order_size <= Decimal::ZERO {
+                return Err(format!("markets[{}].order_size_usd must be > 0, got {}", spec.opinion_market_id, order_size));
+            }
+            validate_order_size(spec.opinion_market_id, order_size, &self.risk)?;
+            validate_strategy(&spec.config)
+                .map_err(|e| format!("markets[{}].strategy.{}", spec.opinion_market_id, e))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// 交叉校验：单笔下单金额不能超过风控的单笔上限，否则每一笔报价都会被肥手指检查拒绝
+/// (启动时的 config check 与运行时的风控热更新共用)
+pub fn validate_order_size(market_id: u64, order_size_usd: Decimal, risk: &RiskConfig) -> Result<(), String> {
+    if order_size_usd > Decimal::from_f64_retain(risk.max_order_size_usd).unwrap_or(Decimal::MAX) {
+        return Err(format!("markets[{}].order_size_usd {} exceeds risk.max_order_size_usd {}",
+            market_id, order_size_usd, risk.max_order_size_usd));
+    }
+    Ok(())
+}
+
+/// 签名私钥：只从环境变量 PRIVATE_KEY 读取 (生产环境安全做法，不写进配置文件)
+/// 由入口读取后显式传给各组件，组件内部不再直接读环境变量
+pub fn private_key() -> String {
+    std::env::var("PRIVATE_KEY").unwrap_or("0xYOUR_PRIVATE_KEY_HERE".to_string())
+}
+
+// 以 StrategyConfig::default() 为底，依次叠加覆盖表；拒绝未知字段 (防止拼写错误被静默忽略)
+fn merge_strategy(overrides: &[&toml::Table]) -> Result<StrategyConfig, String> {
+    let mut base = toml::Table::try_from(StrategyConfig::default()).map_err(|e| e.to_string())?;
+    for layer in overrides {
+        for (key, value) in layer.iter() {
+            if !base.contains_key(key) {
+                return Err(format!("unknown field '{}'", key));
+            }
+            base.insert(key.clone(), value.clone());
+        }
+    }
+    base.try_into().map_err(|e: toml::de::Error| e.message().to_string())
+}
+
+pub(crate) fn validate_risk(cfg: &RiskConfig) -> Result<(), String> {
+    if cfg.max_drawdown_usd <= 0.0 {
+        return Err(format!("max_drawdown_usd must be > 0, got {}", cfg.max_drawdown_usd));
+    }
+    if cfg.max_order_size_usd <= 0.0 {
+        return Err(format!("max_order_size_usd must be > 0, got {}", cfg.max_order_size_usd));
+    }
+    for (name, limit) in [
+        ("max_position_shares", cfg.max_position_shares),
+        ("max_position_usd", cfg.max_position_usd),
+        ("max_portfolio_shares", cfg.max_portfolio_shares),
+        ("max_portfolio_usd", cfg.max_portfolio_usd),
+    ] {
+        if limit < 0.0 {
+            return Err(format!("{} must be >= 0 (0 = unlimited), got {}", name, limit));
+        }
+    }
+    if cfg.soft_limit_ratio <= 0.0 || cfg.soft_limit_ratio > 1.0 {
+        return Err(format!("soft_limit_ratio must be in (0, 1], got {}", cfg.soft_limit_ratio));
+    }
+    Ok(())
+}
+
+pub(crate) fn validate_strategy(cfg: &StrategyConfig) -> Result<(), String> {
+    if cfg.price_space == PriceSpace::Logit && cfg.model != StrategyModel::As {
+        return Err(format!("price_space = \"logit\" is only supported by model = \"as\", got {:?}", cfg.model));
+    }
+    if cfg.risk_aversion_gamma <= 0.0 {
+        return Err(format!("risk_aversion_gamma must be > 0, got {}", cfg.risk_aversion_gamma));
+    }
+    if cfg.liquidity_k <= 0.0 {
+        return Err(format!("liquidity_k must be > 0, got {}", cfg.liquidity_k));
+    }
+    if cfg.min_spread_bps >= 10_000 {
+        return Err(format!("min_spread_bps must be < 10000, got {}", cfg.min_spread_bps));
+    }
+    if cfg.tick_size <= 0.0 || cfg.tick_size >= 1.0 {
+        return Err(format!("tick_size must be in (0, 1), got {}", cfg.tick_size));
+    }
+    if cfg.max_inventory_usd <= 0.0 {
+        return Err(format!("max_inventory_usd must be > 0, got {}", cfg.max_inventory_usd));
+    }
+    if cfg.size_inventory_skew < 0.0 {
+        return Err(format!("size_inventory_skew must be >= 0, got {}", cfg.size_inventory_skew));
+    }
+    if cfg.size_vol_target < 0.0 {
+        return Err(format!("size_vol_target must be >= 0, got {}", cfg.size_vol_target));
+    }
+    if cfg.ladder_levels == 0 || cfg.ladder_levels > 20 {
+        return Err(format!("ladder_levels must be in 1..=20, got {}", cfg.ladder_levels));
+    }
+    if cfg.ladder_step_ticks == 0 {
+        return Err("ladder_step_ticks must be >= 1".to_string());
+    }
+    if cfg.ladder_step_vol_mult < 0.0 {
+        return Err(format!("ladder_step_vol_mult must be >= 0, got {}", cfg.ladder_step_vol_mult));
+    }
+    if cfg.ladder_size_decay <= 0.0 || cfg.ladder_size_decay > 1.0 {
+        return Err(format!("ladder_size_decay must be in (0, 1], got {}", cfg.ladder_size_decay));
+    }
+    if cfg.glft_arrival_a <= 0.0 {
+        return Err(format!("glft_arrival_a must be > 0, got {}", cfg.glft_arrival_a));
+    }
+    if cfg.grid_half_spread_ticks == 0 {
+        return Err("grid_half_spread_ticks must be >= 1".to_string());
+    }
+    if cfg.maturity_timestamp_ms <= 0 {
+        return Err(format!("maturity_timestamp_ms must be a unix timestamp in ms, got {}", cfg.maturity_timestamp_ms));
+    }
+    if cfg.terminal_dumping_factor < 0.0 {
+        return Err(format!("terminal_dumping_factor must be >= 0, got {}", cfg.terminal_dumping_factor));
+    }
+    if cfg.closing_window_seconds < 0 {
+        return Err(format!("closing_window_seconds must be >= 0, got {}", cfg.closing_window_seconds));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINIMAL: &str = r#"
+[strategy]
+risk_aversion_gamma = 0.1
+
+[[markets]]
+poly_asset_id = "0x7b"
+opinion_market_id = 7
+
+[[markets]]
+poly_asset_id = "456"
+opinion_market_id = 8
+order_size_usd = 20
+strategy = { min_spread_bps = 80, model = "grid" }
+"#;
+
+    fn parse(extra: &str) -> Result<AppConfig, String> {
+        let cfg: AppConfig = toml::from_str(&format!("{}{}", extra, MINIMAL)).map_err(|e| e.to_string())?;
+        cfg.validate()?;
+        Ok(cfg)
+    }
+
+    #[test]
+    fn defaults_fill_in_missing_sections() {
+        let cfg = parse("").unwrap();
+        assert_eq!(cfg.bus.signal_bind, "tcp://*:5556");
+        assert_eq!(cfg.hedge.pending_timeout_ms, 3000);
+        assert_eq!(cfg.poly_asset_ids(), vec!["0x7b".to_string(), "456".to_string()]);
+    }
+
+    #[test]
+    fn market_overrides_are_layered_on_the_global_strategy() {
+        let specs = parse("").unwrap().market_specs().unwrap();
+        // 全局 [strategy] 覆盖默认值，单市场表再覆盖全局
+        assert_eq!(specs[0].config.risk_aversion_gamma, 0.1);
+        assert_eq!(specs[0].config.min_spread_bps, StrategyConfig::default().min_spread_bps);
+        assert_eq!(specs[0].order_size_usd, EngineConfig::default().order_size_usd);
+        assert_eq!((specs[1].config.risk_aversion_gamma, specs[1].config.min_spread_bps), (0.1, 80));
+        assert_eq!(specs[1].config.model, StrategyModel::Grid);
+        assert_eq!(specs[1].order_size_usd, Decimal::from(20));
+
+        let mut typo = toml::Table::new();
+        typo.insert("risk_aversion".to_string(), toml::Value::Float(0.1));
+        assert_eq!(merge_strategy(&[&typo]).unwrap_err(), "unknown field 'risk_aversion'");
+    }
+
+    #[test]
+    fn invalid_fields_are_rejected_with_their_name() {
+        let cases = [
+            ("[bus]\nsignal_bind = \"localhost:5556\"\n", "bus.signal_bind"),
+            ("[hedge]\npending_timeout_ms = 0\n", "hedge.pending_timeout_ms"),
+            ("[hedge]\nmax_slippage = 1\n", "hedge.max_slippage"),
+            ("[risk]\nsoft_limit_ratio = 0\n", "risk.soft_limit_ratio"),
+            ("[engine]\norder_size_usd = 900\n", "exceeds risk.max_order_size_usd"),
+        ];
+        for (extra, field) in cases {
+            let err = parse(extra).unwrap_err();
+            assert!(err.contains(field), "{} -> {}", field, err);
+        }
+
+        let mut cfg = parse("").unwrap();
+        cfg.markets[1].opinion_market_id = 7;
+        assert!(cfg.validate().unwrap_err().contains("duplicate opinion_market_id 7"));
+        let mut cfg = parse("").unwrap();
+        cfg.markets[1].strategy.insert("tick_size".to_string(), toml::Value::Float(0.0));
+        assert!(cfg.validate().unwrap_err().contains("markets[8].strategy.tick_size"));
+    }
+}
@@ -1,8 +1,8 @@
 use rust_decimal::Decimal;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use smallvec::SmallVec;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[repr(u8)]
 pub enum Exchange {
     Polymarket = 1,
@@ -10,30 +10,70 @@ pub enum Exchange {
     Unknown = 0,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Side {
     Buy,
     Sell,
 }
 
+// 单边盘口: [(价格, 数量)]，按优先级排序 (bids 降序, asks 升序)
+pub type BookSide = SmallVec<[(Decimal, Decimal); 10]>;
+
+// ⚠️ Decimal 默认的反序列化依赖 deserialize_any，bincode 不支持
+// 总线上的 Decimal 统一按字符串编码 (序列化本来就是字符串，两边格式一致)
+#[derive(Deserialize)]
+struct WireDecimal(#[serde(with = "rust_decimal::serde::str")] Decimal);
+
+fn deserialize_book_side<'de, D: Deserializer<'de>>(d: D) -> Result<BookSide, D::Error> {
+    let levels = SmallVec::<[(WireDecimal, WireDecimal); 10]>::deserialize(d)?;
+    Ok(levels.into_iter().map(|(p, s)| (p.0, s.0)).collect())
+}
+
+// 各阶段的单调时钟时间戳 (CLOCK_MONOTONIC 纳秒，0 = 未记录)，随消息一路传递
+// 单调时钟只在同一台主机内可比；跨主机部署时跨进程的阶段会被跳过 (见 latency::observe)
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct StageStamps {
+    pub ws_recv: u64,       // Feed 收到 WS 消息
+    pub md_publish: u64,    // Feed 发布到 "MD" 总线
+    pub engine_recv: u64,   // 引擎收到行情
+    pub quote_done: u64,    // calculate_quotes 完成
+    pub risk_done: u64,     // 风控检查完成 (信号发出)
+    pub exec_recv: u64,     // 执行层收到信号
+    pub sign_done: u64,     // 签名完成
+    pub http_response: u64, // 场馆 HTTP 响应
+}
+
 // 1. 行情数据快照 (来自 Polymarket Feed)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OrderBookUpdate {
     pub exchange: Exchange,
     pub symbol_id: u64, // Polymarket Asset ID (Hash)
     pub timestamp_ns: i64,
-    pub bids: SmallVec<[(Decimal, Decimal); 10]>,
-    pub asks: SmallVec<[(Decimal, Decimal); 10]>,
+    #[serde(default)]
+    pub trace_id: u64,  // 追踪 ID (由 Feed 生成，报价信号沿用)
+    #[serde(deserialize_with = "deserialize_book_side")]
+    pub bids: BookSide,
+    #[serde(deserialize_with = "deserialize_book_side")]
+    pub asks: BookSide,
+    #[serde(default)]
+    pub stamps: StageStamps,
 }
 
 // 2. 库存更新事件 (来自 Opinion Feed)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InventoryUpdate {
-    pub symbol_id: u64, // Opinion Market ID
+    pub exchange: Exchange, // 成交发生的场馆 (Opinion 报价 / Polymarket 对冲)
+    pub symbol_id: u64, // 该场馆的 Market ID
     pub change: f64,    // 仓位变化 (如 +10.0, -5.0)
     pub cost_usd: f64,
+    pub trace_id: u64,  // 成交订单的追踪 ID (0 = 未知)
 }
 
+// TradeSignal.logic_tag 约定: 1 = 做市报价, 2 = 对冲 (IOC), 99 = 全部撤单
+// 做市报价按 (市场, 方向, level) 管理：size_usd = 0 的报价信号表示撤掉该档
+pub const LOGIC_TAG_QUOTE: u8 = 1;
+pub const LOGIC_TAG_HEDGE: u8 = 2;
+
 // 3. 交易信号 (策略 -> 执行)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TradeSignal {
@@ -41,8 +81,15 @@ pub struct TradeSignal {
     pub target_exchange: Exchange,
     pub symbol_id: u64, // Opinion Market ID
     pub side: Side,
+    #[serde(with = "rust_decimal::serde::str")]
     pub price: Decimal,
+    #[serde(with = "rust_decimal::serde::str")]
     pub size_usd: Decimal,
     pub logic_tag: u8,
+    #[serde(default)]
+    pub post_only: bool, // 只做 Maker：会立即成交的订单由场馆拒绝 (场馆不支持时忽略)
+    pub level: u8,      // 报价阶梯档位 (0 = 最优档；对冲 / 熔断信号为 0)
     pub created_at_ns: i64,
+    pub trace_id: u64,  // 触发该信号的行情 / 成交的追踪 ID
+    pub stamps: StageStamps,
 }
\ No newline at end of file
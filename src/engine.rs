@@ -3,45 +3,66 @@
 use std::thread;
 use std::sync::{mpsc, Arc, atomic::{AtomicBool, Ordering}};
 use std::fs;
+use std::collections::HashMap;
 use std::time::Duration;
+use rust_decimal::Decimal;
 use rust_decimal::prelude::ToPrimitive;
 use rust_decimal_macros::dec;
 
 // 引入核心模块
-use crate::core::{OrderBookUpdate, InventoryUpdate, TradeSignal, Exchange, Side};
-use crate::model::as_logic::{OpinionGridStrategy, StrategyConfig, PersistState};
+use crate::core::{OrderBookUpdate, InventoryUpdate, TradeSignal, Exchange, Side, StageStamps, LOGIC_TAG_QUOTE};
+use crate::model::strategy::{build_strategy, PersistState, Quote, QuoteLevel, Strategy, StrategyConfig};
 use crate::model::risk::RiskManager;
+use crate::model::hedger::DeltaHedger;
+use crate::config::{AppConfig, RiskConfig};
+use crate::reload::{diff, patch_risk, patch_strategy, spawn_config_watcher, write_audit_log, ParamPatch, ParamUpdate};
+use crate::model::portfolio::Portfolio;
+use crate::gateway::opinion_feed::KEEPALIVE_POLLS;
+use crate::venues::polymarket::parse_asset_id;
+use crate::venues::rules::MarketRules;
+use crate::infrastructure::clock::{Clock, ClockMode, ReplayClock, SharedClock, SystemClock};
+use crate::infrastructure::latency::mono_ns;
+use crate::infrastructure::logging::TraceId;
+use crate::infrastructure::metrics::{topic_label, CASH, DECODE_FAILURES, INVENTORY};
 use crate::infrastructure::messaging::{ZmqSubscriber, ZmqPublisher};
+use tracing::{debug, error, info, info_span, warn};
 
 // --- [Part 1] IO Worker: 异步持久化 ---
 // 这个函数会在后台启动一个线程，专门负责把策略状态写入硬盘
-fn spawn_persistence_worker(file_path: String) -> mpsc::Sender<PersistState> {
+// 所有市场共用一个状态文件: { "markets": { "<opinion_market_id>": {...} } }
+fn spawn_persistence_worker(file_path: String, initial: HashMap<u64, (f64, f64)>) -> mpsc::Sender<PersistState> {
     let (tx, rx) = mpsc::channel::<PersistState>();
 
     thread::spawn(move || {
-        println!("💾 [IO Worker] Monitoring state file: {}", file_path);
+        info!(path = %file_path, "💾 IO worker monitoring state file");
+
+        // 每个市场的最新状态 (从磁盘恢复的市场也要保留，否则第一次写入会把它们覆盖掉)
+        let now = chrono::Utc::now().timestamp();
+        let mut latest: HashMap<u64, PersistState> = initial.into_iter()
+            .map(|(id, (inv, cash))| (id, PersistState { market_id: id, inventory_shares: inv, cash_balance: cash, timestamp: now }))
+            .collect();
         
         // 循环接收来自策略线程的状态更新
-        loop {
-            // 阻塞等待，直到有数据发过来
-            let mut latest_state = match rx.recv() {
-                Ok(s) => s,
-                Err(_) => break, // 通道关闭，线程退出
-            };
+        // 阻塞等待，直到有数据发过来 (通道关闭时线程退出)
+        while let Ok(state) = rx.recv() {
+            latest.insert(state.market_id, state);
 
             // ⚡ 排水机制 (Draining): 
-            // 如果积压了多条更新 (比如高频成交时)，只取最后一条最新的状态写入
+            // 如果积压了多条更新 (比如高频成交时)，每个市场只保留最后一条最新的状态写入
             // 这是防止 IO 瓶颈的关键
             while let Ok(newer_state) = rx.try_recv() {
-                latest_state = newer_state;
+                latest.insert(newer_state.market_id, newer_state);
             }
 
             // 序列化并写入临时文件
-            let json = serde_json::json!({
-                "inventory_shares": latest_state.inventory_shares,
-                "cash_balance": latest_state.cash_balance,
-                "timestamp": latest_state.timestamp
-            });
+            let markets: serde_json::Map<String, serde_json::Value> = latest.values()
+                .map(|st| (st.market_id.to_string(), serde_json::json!({
+                    "inventory_shares": st.inventory_shares,
+                    "cash_balance": st.cash_balance,
+                    "timestamp": st.timestamp
+                })))
+                .collect();
+            let json = serde_json::json!({ "markets": markets });
             
             // 原子写入: write -> rename，防止断电导致文件损坏
             let temp_path = format!("{}.tmp", file_path);
@@ -56,21 +77,134 @@ fn spawn_persistence_worker(file_path: String) -> mpsc::Sender<PersistState> {
     tx
 }
 
-// 辅助函数: 系统启动时读取初始状态
-fn load_initial_state(file_path: &str) -> (f64, f64) {
+// 辅助函数: 系统启动时读取初始状态 (Opinion Market ID -> (库存, 现金))
+// legacy_market_id: 旧版单市场格式 (顶层 inventory_shares) 归属的市场
+pub fn load_initial_state(file_path: &str, legacy_market_id: Option<u64>) -> HashMap<u64, (f64, f64)> {
+    let mut states = HashMap::new();
+
     if let Ok(content) = fs::read_to_string(file_path) {
         if let Ok(v) = serde_json::from_str::<serde_json::Value>(&content) {
-            let inv = v["inventory_shares"].as_f64().unwrap_or(0.0);
-            let cash = v["cash_balance"].as_f64().unwrap_or(0.0);
-            return (inv, cash);
+            if let Some(markets) = v["markets"].as_object() {
+                for (id, st) in markets {
+                    if let Ok(id) = id.parse::<u64>() {
+                        let inv = st["inventory_shares"].as_f64().unwrap_or(0.0);
+                        let cash = st["cash_balance"].as_f64().unwrap_or(0.0);
+                        states.insert(id, (inv, cash));
+                    }
+                }
+            } else if let (Some(inv), Some(id)) = (v["inventory_shares"].as_f64(), legacy_market_id) {
+                // 兼容旧版单市场状态文件
+                let cash = v["cash_balance"].as_f64().unwrap_or(0.0);
+                info!(market_id = id, "♻️ Migrating legacy state file");
+                states.insert(id, (inv, cash));
+            }
         }
     }
     // 如果文件不存在，默认从 0 开始
-    (0.0, 0.0)
+    states
+}
+
+// 定时回调间隔 (Strategy::on_timer)
+const TIMER_INTERVAL_MS: i64 = 1000;
+// Opinion 盘口超过这么多个轮询间隔没有收到就不再用于不穿价保护 (轮询失败 / 行情中断)
+// 行情网关对没有变化的盘口每 KEEPALIVE_POLLS 轮重发一次，安静的盘口不会过期
+const OPINION_TOP_MAX_POLLS: i64 = KEEPALIVE_POLLS as i64 + 1;
+
+// 报价意图 -> 交易信号：每档一个信号；上次报过、这次没有的档位发 size_usd = 0，由执行层撤单
+struct QuotePublisher<'a> {
+    pub_sock: &'a ZmqPublisher,
+    clock: SharedClock,
+    // 每个 (Opinion 市场, 方向) 上一次报出的阶梯档数
+    ladder_depth: HashMap<(u64, Side), usize>,
+    // 不穿价保护：Opinion 市场的交易规则与最新盘口 (本地接收时间 ns, 买一, 卖一)
+    rules: HashMap<u64, MarketRules>,
+    opinion_tops: HashMap<u64, (i64, Option<Decimal>, Option<Decimal>)>,
+    opinion_top_max_age_ns: i64,
+    post_only: bool,
+}
+
+impl QuotePublisher<'_> {
+    /// [报价场馆盘口] 记录 Opinion 市场的买一 / 卖一
+    /// 按本地接收时间计算盘口年龄：场馆时间戳与本机时钟之间的偏差不影响过期判断
+    fn on_opinion_book(&mut self, update: &OrderBookUpdate) {
+        let best_bid = update.bids.first().map(|x| x.0);
+        let best_ask = update.asks.first().map(|x| x.0);
+        self.opinion_tops.insert(update.symbol_id, (self.clock.now_ns(), best_bid, best_ask));
+    }
+
+    // 最新的 Opinion 买一 / 卖一；快照过期时删掉，不拿旧盘口去截报价
+    fn opinion_top(&mut self, market_id: u64) -> Option<(Option<Decimal>, Option<Decimal>)> {
+        let &(ts_ns, best_bid, best_ask) = self.opinion_tops.get(&market_id)?;
+        let age_ns = self.clock.now_ns() - ts_ns;
+        if age_ns > self.opinion_top_max_age_ns {
+            self.opinion_tops.remove(&market_id);
+            debug!(market_id, age_ms = age_ns / 1_000_000, "⌛ Stale Opinion book top dropped");
+            return None;
+        }
+        Some((best_bid, best_ask))
+    }
+
+    fn publish(&mut self, risk_manager: &RiskManager, portfolio: &Portfolio, market_id: u64, mut quote: Quote, trace_id: u64, stamps: StageStamps) {
+        let inventory = portfolio.position(market_id).map_or(0.0, |(shares, _)| shares);
+        // 持仓达到软上限 (单市场或组合)：只保留减仓一侧
+        if risk_manager.reduce_only(portfolio, market_id) {
+            quote.reduce_only(inventory);
+            debug!(inventory, "🚧 Soft position limit: quoting the reducing side only");
+        }
+
+        // 参考价跑得比报价场馆快时，报价可能穿过 Opinion 盘口：截到盘口以内，保持被动成交
+        if let (Some((best_bid, best_ask)), Some(rules)) = (self.opinion_top(market_id), self.rules.get(&market_id)) {
+            if quote.keep_passive(best_bid, best_ask, rules) {
+                debug!(best_bid = ?best_bid, best_ask = ?best_ask, "🧲 Quote clamped inside the Opinion book");
+            }
+        }
+
+        let now_ns = self.clock.now_ns();
+        let mut signals = Vec::new();
+        for (side, levels) in [(Side::Buy, &quote.bids), (Side::Sell, &quote.asks)] {
+            let prev_depth = self.ladder_depth.insert((market_id, side), levels.len()).unwrap_or(0);
+            let withdrawn = (levels.len()..prev_depth).map(|_| QuoteLevel { price: dec!(0), size_usd: dec!(0) });
+            for (level, l) in levels.iter().copied().chain(withdrawn).enumerate() {
+                signals.push(TradeSignal {
+                    strategy_id: 1,
+                    target_exchange: Exchange::OpinionLabs,
+                    symbol_id: market_id,
+                    side,
+                    price: l.price,
+                    size_usd: l.size_usd,
+                    logic_tag: LOGIC_TAG_QUOTE,
+                    post_only: self.post_only,
+                    level: level as u8,
+                    created_at_ns: now_ns,
+                    trace_id,
+                    stamps,
+                });
+            }
+        }
+
+        // 发送前风控审查 (Pre-Trade Check)
+        // 撤档信号不开新仓，直接放行；被拒绝的档位改为撤档，不让旧价格的挂单留在场馆上
+        // 每次发布替换整边阶梯，所以同一方向已放行档位的份额之和就是该方向挂单 + 在途的全部敞口
+        let mut committed: HashMap<Side, f64> = HashMap::new();
+        for mut signal in signals {
+            if !signal.size_usd.is_zero() {
+                let side_committed = committed.entry(signal.side).or_insert(0.0);
+                if risk_manager.check_quote(&signal, portfolio, *side_committed) {
+                    let shares = (signal.size_usd / signal.price).to_f64().unwrap_or(0.0);
+                    *side_committed += if signal.side == Side::Buy { shares } else { -shares };
+                } else {
+                    signal.price = dec!(0);
+                    signal.size_usd = dec!(0);
+                }
+            }
+            signal.stamps.risk_done = mono_ns();
+            self.pub_sock.send_signal(&signal);
+        }
+    }
 }
 
 // --- [Main] 策略引擎主函数 ---
-pub fn run_strategy_engine() {
+pub fn run_strategy_engine(cfg: AppConfig, config_path: Option<String>) {
     // 1. 设置优雅退出信号 (Graceful Shutdown)
     // 使用 AtomicBool 在不同线程间共享运行状态
     let running = Arc::new(AtomicBool::new(true));
@@ -79,61 +213,124 @@ pub fn run_strategy_engine() {
     // 捕获 Ctrl+C 信号 (需要 cargo.toml 添加 `ctrlc` 依赖)
     // 如果没有 ctrlc 库，可以手动用其他方式触发，或者依赖外部关闭 channel
     if let Err(e) = ctrlc::set_handler(move || {
-        println!("\n🛑 [SIGINT] Received Ctrl+C! Initiating Graceful Shutdown...");
+        warn!("🛑 Received Ctrl+C! Initiating graceful shutdown...");
         r.store(false, Ordering::SeqCst);
     }) {
-        eprintln!("⚠️ Warning: Failed to set Ctrl-C handler: {}", e);
+        warn!(error = %e, "⚠️ Failed to set Ctrl-C handler");
     }
 
     // 2. 初始化网络层
     // Sub: 接收行情 (Feed) 和 成交回报 (Execution)
-    let sub = ZmqSubscriber::new("tcp://localhost:5555", ""); 
+    let sub = ZmqSubscriber::new(&cfg.bus.market_data_connect, ""); 
+    // 带超时接收：行情静默时也能及时响应 Ctrl+C 和参数热更新
+    sub.set_recv_timeout(100);
+    // 模拟盘：成交回报由执行层的模拟场馆发布
+    if cfg.paper.enabled {
+        sub.connect(&cfg.paper.fill_connect);
+    }
     // Pub: 发送交易信号 (Signals)
-    let pub_sock = ZmqPublisher::new("tcp://localhost:5556");
+    let pub_sock = ZmqPublisher::new(&cfg.bus.signal_bind);
+
+    // 时钟：实盘用墙上时钟；消费回放行情时跟随行情时间戳
+    let replay_clock = Arc::new(ReplayClock::default());
+    let clock: SharedClock = match cfg.engine.clock {
+        ClockMode::System => Arc::new(SystemClock),
+        ClockMode::Replay => {
+            info!("⏪ Engine clock follows market data timestamps");
+            replay_clock.clone()
+        }
+    };
 
     // 3. 初始化持久化层
-    let state_file = "./data/strategy_state.json".to_string();
-    let _ = fs::create_dir_all("./data");
+    let state_file = cfg.engine.state_file.clone();
+    if let Some(dir) = std::path::Path::new(&state_file).parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    // 配置已在启动时校验过，这里不会失败
+    let markets = cfg.market_specs().unwrap_or_default();
     
+    // 加载历史账本 (所有市场)
+    let legacy_market_id = if markets.len() == 1 { Some(markets[0].opinion_market_id) } else { None };
+    let initial_states = load_initial_state(&state_file, legacy_market_id);
     // 启动 IO 线程
-    let persist_tx = spawn_persistence_worker(state_file.clone());
-    // 加载历史账本
-    let (init_inv, init_cash) = load_initial_state(&state_file);
-
-    // 4. 初始化策略模块 (手工参数配置)
-    let config = StrategyConfig {
-        risk_aversion_gamma: 0.05, // 风险厌恶系数
-        liquidity_k: 5000.0,       // 市场流动性估算
-        min_spread_bps: 50,        // 最小价差 0.5% (覆盖 Gas 和 手续费)
-        tick_size: 0.01,           // 价格最小跳动单位
-        max_inventory_usd: 2000.0, // 此字段仅用于计算辅助，真实限制由 RiskManager 负责
-        
-        // 时间相关参数 (Part 3)
-        // 请替换为真实的市场结束时间戳 (毫秒)
-        maturity_timestamp_ms: 1735689599000, 
-        terminal_dumping_factor: 10.0, // 临近结束时风险厌恶翻 10 倍
-        closing_window_seconds: 3600,  // 最后 1 小时进入清仓模式
+    let persist_tx = spawn_persistence_worker(state_file.clone(), initial_states.clone());
+
+    // 4. 初始化策略模块：每个市场一个独立实例
+    let mut portfolio = Portfolio::new();
+    // 参考行情 ID -> 单笔下单金额
+    let mut order_sizes: HashMap<u64, Decimal> = HashMap::new();
+    // Opinion Market ID -> 交易规则 (不穿价保护用)
+    let mut market_rules: HashMap<u64, MarketRules> = HashMap::new();
+    for spec in markets {
+        let Some(ref_id) = parse_asset_id(&spec.poly_asset_id).map(|id| id.low_u64()) else {
+            warn!(poly_asset_id = %spec.poly_asset_id, "⚠️ Invalid Polymarket asset id, market skipped");
+            continue;
+        };
+
+        // 按配置选择报价模型，注入持久化通道
+        info!(market_id = spec.opinion_market_id, model = ?spec.config.model, "🧩 Strategy model");
+        market_rules.insert(spec.opinion_market_id, spec.rules.with_default_tick(spec.config.tick_size));
+        let mut strategy = build_strategy(spec.opinion_market_id, spec.config, spec.rules, Some(persist_tx.clone()), clock.clone());
+        // 恢复之前的“真金白银”状态
+        let (init_inv, init_cash) = initial_states.get(&spec.opinion_market_id).copied().unwrap_or((0.0, 0.0));
+        strategy.ledger_mut().restore_state(init_inv, init_cash);
+        export_position(strategy.as_ref());
+        portfolio.add_market(ref_id, strategy);
+        order_sizes.insert(ref_id, spec.order_size_usd);
+    }
+    drop(persist_tx);
+    let mut quotes = QuotePublisher {
+        pub_sock: &pub_sock,
+        clock: clock.clone(),
+        ladder_depth: HashMap::new(),
+        rules: market_rules,
+        opinion_tops: HashMap::new(),
+        opinion_top_max_age_ns: OPINION_TOP_MAX_POLLS * cfg.venues.opinion_book_poll_ms as i64 * 1_000_000,
+        post_only: cfg.engine.post_only,
     };
-    
-    // 注入持久化通道
-    let mut strategy = OpinionGridStrategy::new(config, Some(persist_tx));
-    // 恢复之前的“真金白银”状态
-    strategy.restore_state(init_inv, init_cash);
 
     // 5. 初始化风控模块 (Part 4)
-    let mut risk_manager = RiskManager::new(
-        100.0, // max_drawdown_usd: 最多允许亏损 100 U
-        500.0  // max_order_size_usd: 单笔订单最大 500 U (防肥手指)
+    let mut risk_manager = RiskManager::new(&cfg.risk, clock.clone());
+    let mut risk_cfg = cfg.risk.clone();
+
+    // 6. 初始化跨场馆对冲器 (Opinion 敞口 -> Polymarket IOC 对冲)
+    let mut hedger = DeltaHedger::new(
+        cfg.hedge.clone(),
+        portfolio.opinion_pairs(), // Opinion Market ID -> Polymarket symbol_id
+        (risk_manager.stop_loss_price_floor, risk_manager.stop_loss_price_ceiling), // 对冲限价不超出风控价格保护
     );
 
-    println!("🧠 [Engine] Active. Cash Ledger: ${:.2} | Inventory: {}", init_cash, init_inv);
+    // 7. 参数热更新通道 (配置文件监听 + 总线控制消息)
+    let (param_tx, param_rx) = mpsc::channel::<ParamUpdate>();
+    if let Some(path) = config_path {
+        spawn_config_watcher(path, param_tx.clone());
+    }
+
+    let summary = portfolio.summary();
+    info!(markets = summary.markets, cash = summary.cash_balance, net_inventory = summary.net_inventory_shares, "🧠 Engine active");
 
     // --- 主循环 ---
+    let mut last_timer_ms = 0;
     while running.load(Ordering::SeqCst) {
-        // 尝试接收消息 (非阻塞或带超时，以便能响应 Ctrl+C)
-        // 假设 recv_raw_bytes 内部是阻塞的，建议在 ZmqSubscriber 实现里加 timeout
-        // 这里为了代码通用性，假设它能正常返回
-        let msg = match sub.recv_raw_bytes() {
+        // 0. 在两个 tick 之间应用参数热更新 (单线程，天然原子)
+        while let Ok(update) = param_rx.try_recv() {
+            apply_param_update(update, &mut portfolio, &mut risk_manager, &mut risk_cfg, &order_sizes, &cfg.engine.audit_log);
+        }
+
+        // 0b. 定时回调 (行情静默时也会执行，例如到期后撤掉报价)
+        let now_ms = clock.now_ms();
+        if now_ms - last_timer_ms >= TIMER_INTERVAL_MS {
+            last_timer_ms = now_ms;
+            for id in portfolio.market_ids() {
+                let Some(strategy) = portfolio.by_opinion_mut(id) else { continue };
+                if let Some(quote) = strategy.on_timer(now_ms) {
+                    quotes.publish(&risk_manager, &portfolio, id, quote, 0, StageStamps::default());
+                }
+            }
+        }
+
+        // 尝试接收消息 (带超时，以便能响应 Ctrl+C)
+        let (topic, msg) = match sub.recv_with_topic() {
             Some(m) => m,
             None => {
                 // 没有消息时短暂休眠，避免 CPU 空转
@@ -142,80 +339,123 @@ pub fn run_strategy_engine() {
                 continue; 
             }
         };
+        let recv_ns = mono_ns();
+
+        // --- 分支 C: 控制消息 (参数热更新，JSON) ---
+        if topic == b"CT" {
+            match ParamPatch::from_json(&msg) {
+                Ok(patch) => { let _ = param_tx.send(ParamUpdate::Patch(patch)); }
+                Err(e) => {
+                    DECODE_FAILURES.with_label_values(&["CT"]).inc();
+                    error!(error = %e, "❌ Bad control message");
+                }
+            }
+            continue;
+        }
 
         // --- 分支 A: 处理行情更新 (Market Data) ---
-        if let Ok(update) = bincode::deserialize::<OrderBookUpdate>(&msg) {
+        if let Some(update) = decode::<OrderBookUpdate>(&topic, b"MD", &msg) {
+            // A0. 记录对冲场馆盘口
+            replay_clock.observe(update.timestamp_ns);
+            hedger.on_book(&update);
+
+            // 报价场馆自己的盘口只用于不穿价保护，不驱动报价
+            if update.exchange == Exchange::OpinionLabs {
+                quotes.on_opinion_book(&update);
+                continue;
+            }
+
+            // 只处理已配置市场的参考行情
+            let Some(strategy) = portfolio.by_ref_mut(update.symbol_id) else { continue };
+            let _span = info_span!("book", trace_id = %TraceId(update.trace_id), symbol_id = update.symbol_id).entered();
+
             // A1. 计算中间价
-            let best_bid = update.bids.get(0).map(|x| x.0).unwrap_or(dec!(0));
-            let best_ask = update.asks.get(0).map(|x| x.0).unwrap_or(dec!(0));
+            let best_bid = update.bids.first().map(|x| x.0).unwrap_or(dec!(0));
+            let best_ask = update.asks.first().map(|x| x.0).unwrap_or(dec!(0));
             
             // 如果数据异常 (0报价)，跳过
             if best_bid.is_zero() || best_ask.is_zero() { continue; }
             let mid_price = (best_bid + best_ask) / dec!(2);
             let mid_f64 = mid_price.to_f64().unwrap_or(0.0);
+            let opinion_market_id = strategy.market_id();
 
             // A2. [关键] 实时风控检查 (Mark-to-Market PnL)
             // 即使没有成交，价格变动也会导致持仓市值变化，必须实时计算回撤
-            let pnl_change = strategy.calculate_equity_change(mid_f64);
+            // 对冲腿 (Polymarket) 的盈亏也必须计入回撤
+            // 其他市场的价格没有变化，所以单个市场的权益变动就是组合层的权益变动
+            let pnl_change = strategy.ledger_mut().calculate_equity_change(mid_f64)
+                + hedger.calculate_equity_change(update.symbol_id, mid_f64);
             
             if risk_manager.update_pnl_and_check_kill(pnl_change) {
                 // 🚨 触发熔断！
-                println!("🛑 System Halted due to Risk Trigger (Drawdown Limit).");
-                send_emergency_cancel(&pub_sock);
+                error!("🛑 System halted due to risk trigger (drawdown limit)");
+                send_emergency_cancel(&pub_sock, clock.as_ref());
                 break; // 立即跳出循环，停止策略
             }
 
-            // A3. 计算策略报价 (AS Model Logic)
-            let (new_bid, new_ask) = strategy.calculate_quotes(mid_price);
-
-            // A4. 构建交易信号
-            let now_ns = chrono::Utc::now().timestamp_nanos();
-            let size_usd = dec!(50); // 默认单笔下单金额，可根据 inventory 动态调整
-
-            // 双边报价 (Bid & Ask)
-            let signals = vec![
-                TradeSignal {
-                    strategy_id: 1,
-                    target_exchange: Exchange::OpinionLabs,
-                    symbol_id: update.symbol_id, // 需注意 ID 映射，这里简化为直接使用
-                    side: Side::Buy,
-                    price: new_bid,
-                    size_usd,
-                    logic_tag: 1,
-                    created_at_ns: now_ns,
-                },
-                TradeSignal {
-                    strategy_id: 1,
-                    target_exchange: Exchange::OpinionLabs,
-                    symbol_id: update.symbol_id,
-                    side: Side::Sell,
-                    price: new_ask,
-                    size_usd,
-                    logic_tag: 1,
-                    created_at_ns: now_ns,
-                }
-            ];
-
-            // A5. 发送前风控审查 (Pre-Trade Check)
-            for signal in signals {
-                // 只有通过风控检查的信号才会被发送
-                if risk_manager.check_signal(&signal) {
-                    pub_sock.send_signal(&signal);
+            // A2b. 重试未完成的对冲 (之前深度不足，或 IOC 单超时未成交)
+            if let Some(mut hedge) = hedger.check_hedge(update.symbol_id, clock.now_ms(), update.trace_id) {
+                if risk_manager.check_signal(&hedge) {
+                    hedge.stamps = StageStamps { engine_recv: recv_ns, risk_done: mono_ns(), ..update.stamps };
+                    pub_sock.send_signal(&hedge);
                 }
             }
+
+            // A3. 计算策略报价：报价模型返回双边报价阶梯，金额随库存调整
+            let base_size_usd = order_sizes.get(&update.symbol_id).copied().unwrap_or(dec!(50));
+            let quote = strategy.on_book(mid_price, base_size_usd);
+            let stamps = StageStamps { engine_recv: recv_ns, quote_done: mono_ns(), ..update.stamps };
+            portfolio.mark(update.symbol_id, mid_f64);
+            debug!(mid = %mid_price, bid = ?quote.bids.first(), ask = ?quote.asks.first(),
+                bid_levels = quote.bids.len(), ask_levels = quote.asks.len(), "📐 Quotes");
+
+            // A4. 构建交易信号并经过风控发送
+            quotes.publish(&risk_manager, &portfolio, opinion_market_id, quote, update.trace_id, stamps);
         } 
         // --- 分支 B: 处理成交/库存更新 (Fills) ---
-        else if let Ok(inv_update) = bincode::deserialize::<InventoryUpdate>(&msg) {
+        else if let Some(inv_update) = decode::<InventoryUpdate>(&topic, b"IV", &msg) {
+            let _span = info_span!("fill", trace_id = %TraceId(inv_update.trace_id), exchange = ?inv_update.exchange, symbol_id = inv_update.symbol_id).entered();
+
             // B1. 更新策略状态 (这是最真实的账本更新)
             // inv_update.cost_usd 必须是真实的现金流 (Gateway 层计算)
-            strategy.on_fill(inv_update.change, inv_update.cost_usd);
+            // 只有 Opinion 上的报价成交才进入策略账本，Polymarket 成交属于对冲腿
+            if inv_update.exchange == Exchange::OpinionLabs {
+                match portfolio.by_opinion_mut(inv_update.symbol_id) {
+                    Some(strategy) => {
+                        let requote = strategy.on_fill(inv_update.change, inv_update.cost_usd);
+                        export_position(strategy);
+                        if let Some(quote) = requote {
+                            let stamps = StageStamps { engine_recv: recv_ns, quote_done: mono_ns(), ..Default::default() };
+                            quotes.publish(&risk_manager, &portfolio, inv_update.symbol_id, quote, inv_update.trace_id, stamps);
+                        }
+                    }
+                    None => warn!("⚠️ Fill for unknown market"),
+                }
+            }
             
-            println!("💵 [Fill Confirmed] Cash: ${:.2} | Inv: {} | Delta Cost: ${:.2}", 
-                strategy.current_cash_balance, 
-                strategy.current_inventory_shares,
-                inv_update.cost_usd
+            let summary = portfolio.summary();
+            info!(
+                change = inv_update.change,
+                delta_cost = inv_update.cost_usd,
+                cash = summary.cash_balance,
+                net_inventory = summary.net_inventory_shares,
+                gross_exposure = summary.gross_exposure_usd,
+                equity = summary.equity,
+                "💵 Fill confirmed"
             );
             
+            // B2. 跨场馆对冲：净敞口超过阈值时发出 Polymarket IOC 单
+            let event_key = hedger.on_fill(inv_update.exchange, inv_update.symbol_id, inv_update.change, inv_update.cost_usd);
+            if let Some(mut hedge) = hedger.check_hedge(event_key, clock.now_ms(), inv_update.trace_id) {
+                if risk_manager.check_signal(&hedge) {
+                    hedge.stamps.engine_recv = recv_ns;
+                    hedge.stamps.risk_done = mono_ns();
+                    info!(side = ?hedge.side, size_usd = %hedge.size_usd, price = %hedge.price,
+                        net_exposure = hedger.net_exposure(event_key), "🛡️ Hedge");
+                    pub_sock.send_signal(&hedge);
+                }
+            }
+
             // 注意：这里不需要显式调用 risk_manager 更新 PnL
             // 因为下一次行情到来时，calculate_equity_change 会自动基于最新的 Cash 和 Inv 计算出准确的权益
         }
@@ -223,19 +463,125 @@ pub fn run_strategy_engine() {
 
     // --- 退出清理逻辑 (Post-Loop) ---
     // 无论是 Ctrl+C 还是 熔断退出，都会执行这里
-    println!("🧹 [Shutdown] Engine stopped. Sending EMERGENCY CANCEL ALL...");
+    warn!("🧹 Engine stopped. Sending EMERGENCY CANCEL ALL...");
+
+    // 报价模型的最后一次报价意图 (默认全部撤掉)，随后仍然发送全部撤单
+    for id in portfolio.market_ids() {
+        let Some(strategy) = portfolio.by_opinion_mut(id) else { continue };
+        let quote = strategy.on_shutdown();
+        quotes.publish(&risk_manager, &portfolio, id, quote, 0, StageStamps::default());
+    }
     
     // 发送多次以防丢包
     for _ in 0..3 {
-        send_emergency_cancel(&pub_sock);
+        send_emergency_cancel(&pub_sock, clock.as_ref());
         thread::sleep(Duration::from_millis(100));
     }
     
-    println!("👋 [Shutdown] Graceful exit complete.");
+    info!("👋 Graceful exit complete");
+}
+
+// 辅助函数: 按主题解码总线消息 (不同结构体的 bincode 编码可能互相“解码成功”，不能靠试错区分)
+fn decode<T: serde::de::DeserializeOwned>(topic: &[u8], expected: &[u8], msg: &[u8]) -> Option<T> {
+    if topic != expected {
+        return None;
+    }
+    let decoded = bincode::deserialize(msg).ok();
+    if decoded.is_none() {
+        DECODE_FAILURES.with_label_values(&[topic_label(topic)]).inc();
+    }
+    decoded
+}
+
+// 辅助函数: 导出单个市场的账本指标
+fn export_position(strategy: &dyn Strategy) {
+    let market = strategy.market_id().to_string();
+    INVENTORY.with_label_values(&[&market]).set(strategy.ledger().current_inventory_shares);
+    CASH.with_label_values(&[&market]).set(strategy.ledger().current_cash_balance);
+}
+
+// 辅助函数: 整体应用参数更新 —— 先计算并校验全部新参数，任何一项失败则整批拒绝
+fn apply_param_update(
+    update: ParamUpdate,
+    portfolio: &mut Portfolio,
+    risk_manager: &mut RiskManager,
+    risk_cfg: &mut RiskConfig,
+    order_sizes: &HashMap<u64, Decimal>,
+    audit_log: &str,
+) {
+    let (source, resolved) = match update {
+        ParamUpdate::Reload { source, strategies, risk } => (source, Ok((strategies, risk))),
+        ParamUpdate::Patch(patch) => {
+            let source = if patch.source.is_empty() { "control".to_string() } else { format!("control:{}", patch.source) };
+            (source, resolve_patch(&patch, portfolio, risk_cfg, order_sizes))
+        }
+    };
+    let (strategies, new_risk) = match resolved {
+        Ok(r) => r,
+        Err(e) => {
+            error!(source = %source, error = %e, "❌ Parameter update rejected");
+            return;
+        }
+    };
+
+    // 0. 报价模型在启动时创建，不能热切换
+    if let Some(id) = strategies.iter()
+        .find(|(id, c)| portfolio.by_opinion(**id).is_some_and(|s| s.config().model != c.model))
+        .map(|(id, _)| *id)
+    {
+        error!(source = %source, market_id = id, "❌ Parameter update rejected: changing strategy.model needs a restart");
+        return;
+    }
+
+    // 1. 记录变化 (新旧值)
+    let mut changes = Vec::new();
+    for (id, new_cfg) in &strategies {
+        match portfolio.by_opinion(*id) {
+            Some(s) => changes.extend(diff(&format!("market:{}", id), s.config(), new_cfg)),
+            None => warn!(market_id = id, "⚠️ Market is not running, adding or removing markets needs a restart"),
+        }
+    }
+    changes.extend(diff("risk", risk_cfg, &new_risk));
+    if changes.is_empty() {
+        return;
+    }
+
+    // 2. 一次性生效
+    for (id, new_cfg) in strategies {
+        if let Some(s) = portfolio.by_opinion_mut(id) {
+            s.set_config(new_cfg);
+        }
+    }
+    risk_manager.set_limits(&new_risk);
+    *risk_cfg = new_risk;
+
+    write_audit_log(audit_log, &source, &changes);
+}
+
+// 辅助函数: 把控制消息的增量叠加到当前参数上
+// order_sizes: 参考行情 ID -> 单笔下单金额 (风控限额与它做交叉校验)
+fn resolve_patch(patch: &ParamPatch, portfolio: &Portfolio, risk_cfg: &RiskConfig, order_sizes: &HashMap<u64, Decimal>) -> Result<(HashMap<u64, StrategyConfig>, RiskConfig), String> {
+    let mut strategies = HashMap::new();
+    if !patch.strategy.is_empty() {
+        let ids = match patch.market_id {
+            Some(id) => vec![id],
+            None => portfolio.market_ids(),
+        };
+        for id in ids {
+            let current = portfolio.by_opinion(id).ok_or_else(|| format!("unknown market {}", id))?;
+            let next = patch_strategy(current.config(), &patch.strategy).map_err(|e| format!("market {}: {}", id, e))?;
+            strategies.insert(id, next);
+        }
+    }
+    let running_sizes: Vec<(u64, Decimal)> = portfolio.opinion_pairs().into_iter()
+        .filter_map(|(opinion_id, ref_id)| Some((opinion_id, *order_sizes.get(&ref_id)?)))
+        .collect();
+    let risk = patch_risk(risk_cfg, &patch.risk, &running_sizes).map_err(|e| format!("risk: {}", e))?;
+    Ok((strategies, risk))
 }
 
 // 辅助函数: 发送紧急撤单信号 (Kill Switch Signal)
-fn send_emergency_cancel(pub_sock: &ZmqPublisher) {
+fn send_emergency_cancel(pub_sock: &ZmqPublisher, clock: &dyn Clock) {
     let kill_signal = TradeSignal {
         strategy_id: 0,
         target_exchange: Exchange::OpinionLabs,
@@ -244,7 +590,63 @@ fn send_emergency_cancel(pub_sock: &ZmqPublisher) {
         price: dec!(0),
         size_usd: dec!(0),
         logic_tag: 99, // <--- 99 号令：执行层识别为“全部撤单”
-        created_at_ns: chrono::Utc::now().timestamp_nanos(),
+        post_only: false,
+        level: 0,
+        created_at_ns: clock.now_ns(),
+        trace_id: 0,
+        stamps: StageStamps::default(),
     };
     pub_sock.send_signal(&kill_signal);
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::clock::SimClock;
+    use crate::testing::free_port;
+
+    const POLL_NS: i64 = 500_000_000;
+
+    fn opinion_book(timestamp_ns: i64) -> OrderBookUpdate {
+        OrderBookUpdate {
+            exchange: Exchange::OpinionLabs,
+            symbol_id: 7,
+            timestamp_ns,
+            trace_id: 0,
+            bids: [(dec!(0.45), dec!(100))].into_iter().collect(),
+            asks: [(dec!(0.55), dec!(100))].into_iter().collect(),
+            stamps: StageStamps::default(),
+        }
+    }
+
+    #[test]
+    fn quiet_but_live_opinion_book_keeps_its_top() {
+        let pub_sock = ZmqPublisher::new(&format!("tcp://127.0.0.1:{}", free_port()));
+        let sim = Arc::new(SimClock::default());
+        let mut quotes = QuotePublisher {
+            pub_sock: &pub_sock,
+            clock: sim.clone(),
+            ladder_depth: HashMap::new(),
+            rules: HashMap::new(),
+            opinion_tops: HashMap::new(),
+            opinion_top_max_age_ns: OPINION_TOP_MAX_POLLS * POLL_NS,
+            post_only: false,
+        };
+        let start = 10 * 3_600_000_000_000;
+        sim.set(start);
+        // 场馆时钟比本机慢一小时，且盘口一直没有变化：网关每 KEEPALIVE_POLLS 轮重发同一个快照 (同一个场馆时间戳)
+        let book = opinion_book(start - 3_600_000_000_000);
+        quotes.on_opinion_book(&book);
+        for poll in 1..=10 {
+            sim.set(start + poll * POLL_NS);
+            if poll % KEEPALIVE_POLLS as i64 == 0 {
+                quotes.on_opinion_book(&book);
+            }
+            assert_eq!(quotes.opinion_top(7), Some((Some(dec!(0.45)), Some(dec!(0.55)))), "poll {}", poll);
+        }
+
+        // 行情中断：最后一次收到之后超过 OPINION_TOP_MAX_POLLS 个轮询间隔，盘口被删掉
+        sim.set(start + (10 + OPINION_TOP_MAX_POLLS) * POLL_NS + 1);
+        assert_eq!(quotes.opinion_top(7), None);
+        assert!(quotes.opinion_tops.is_empty());
+    }
+}
@@ -1,117 +1,446 @@
-// File: src/execution/loop.rs
+// File: src/execution/event_loop.rs
 
+use crate::infrastructure::latency::{self, mono_ns};
+use crate::infrastructure::logging::TraceId;
+use crate::infrastructure::metrics::{exchange_label, DECODE_FAILURES, ORDERS_DROPPED, SIGN_LATENCY, SUBMIT_LATENCY};
 use crate::infrastructure::messaging::ZmqSubscriber;
-use crate::execution::opinion_maker::{OpinionMakerGateway, SignedOrder};
-use crate::core::TradeSignal;
-use std::sync::Arc;
+use crate::execution::ladder::{slot_key, LadderBook, SlotTicket};
+use crate::venues::{ExchangeAdapter, SignedOrder};
+use crate::venues::opinion::OpinionMakerGateway;
+use crate::venues::paper::start_paper_venues;
+use crate::venues::polymarket::PolymarketClobGateway;
+use crate::venues::rules::load_market_rules;
+use crate::config::AppConfig;
+use crate::core::{Exchange, TradeSignal, LOGIC_TAG_QUOTE};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use tokio::sync::mpsc; // 使用 Tokio 的异步通道
-use std::time::Duration;
+use tokio::time::MissedTickBehavior;
+use std::time::{Duration, Instant};
+use tracing::{error, info, info_span, warn, Instrument};
 
-pub async fn run_execution_loop() {
+/// 运行直到 running 被置为 false (退出时发送流水线随之关闭，已发出的请求不等待)
+pub async fn run_execution_loop(cfg: AppConfig, private_key: String, running: Arc<AtomicBool>) {
     // 1. 初始化 ZMQ 订阅者 (监听 "SG" 也就是 Signal 信号)
-    let sub = ZmqSubscriber::new("tcp://localhost:5556", "SG");
-    
-    // 从环境变量读取私钥 (生产环境安全做法)
-    let pk = std::env::var("PRIVATE_KEY").unwrap_or("0xYOUR_PRIVATE_KEY_HERE".to_string());
-    
-    // 初始化 Gateway (复用 HTTP Client)
-    let gateway = Arc::new(OpinionMakerGateway::new(&pk, "https://api.opinionlabs.xyz"));
+    // 接收超时 100ms：没有信号时也能及时检查退出标志
+    let sub = ZmqSubscriber::new(&cfg.bus.signal_connect, "SG");
+    sub.set_recv_timeout(100);
 
-    println!("🔫 [Execution] Ready. Listening for signals...");
+    // 初始化各场馆 Gateway (复用 HTTP Client)，按 TradeSignal.target_exchange 路由
+    // 模拟盘模式下换成内存撮合的模拟场馆，不发出任何真实订单
+    let venues = Arc::new(if cfg.paper.enabled { start_paper_venues(&cfg) } else { connect_venues(&cfg, &private_key).await });
+
+    // 启动自检：打印账户余额和残留挂单 (上次异常退出可能留下未撤的订单)
+    for venue in venues.values() {
+        log_venue_snapshot(venue.as_ref()).await;
+    }
+
+    // 交易规则 (tick 表、最小下单量)：配置兜底，场馆能提供的以场馆为准
+    let rules = load_market_rules(&cfg, &venues).await;
+
+    // 报价阶梯账本：每个档位最多一张挂单，改价时撤旧挂新
+    let ladder = Arc::new(Mutex::new(LadderBook::default()));
+    if cfg.venues.open_orders_poll_ms > 0 {
+        let interval = Duration::from_millis(cfg.venues.open_orders_poll_ms);
+        tokio::spawn(reconcile_ladder(venues.clone(), ladder.clone(), interval, running.clone()));
+    }
+
+    info!("🔫 Ready. Listening for signals...");
 
     // ------------------------------------------------------------------
     // 🌊 流水线 Part A: 广播员 (Broadcaster) - IO 密集型
     // ------------------------------------------------------------------
     // 创建一个缓冲区为 1000 的通道。如果网络卡顿，积压超过 1000 个订单则开始丢弃，防止内存爆掉
-    let (tx, mut rx) = mpsc::channel::<SignedOrder>(1000);
+    // 报价单附带档位凭证，发送结果要回写阶梯账本
+    let (tx, mut rx) = mpsc::channel::<(SignedOrder, Option<SlotTicket>)>(1000);
 
-    let gateway_io = gateway.clone();
+    let venues_io = venues.clone();
+    let ladder_io = ladder.clone();
     tokio::spawn(async move {
-        println!("📡 [Broadcaster] Online... (Pipeline Started)");
+        info!("📡 Broadcaster online (pipeline started)");
         
         // 持续从通道里接收“已签名”的订单
-        while let Some(signed_order) = rx.recv().await {
-            let gw = gateway_io.clone();
+        while let Some((signed_order, ticket)) = rx.recv().await {
+            // 签名时已确定场馆，这里一定能找到
+            let Some(gw) = venues_io.get(&signed_order.exchange).cloned() else { continue };
             
             // 🔥 并发发送：对每个订单都开一个轻量级 Task
             // 依赖 HTTP Keep-Alive 和 connection pooling 来管理 TCP 连接
+            let span = info_span!("submit",
+                trace_id = %TraceId(signed_order.trace_id),
+                exchange = ?signed_order.exchange,
+                client_order_id = %signed_order.order_id_tag);
+            let exchange = exchange_label(signed_order.exchange);
+            let ladder = ladder_io.clone();
             tokio::spawn(async move {
                 // 这里的 submit_order 是纯网络请求
-                match gw.submit_order(signed_order).await {
-                    Ok(_id) => {
-                        // 高频模式下建议关闭普通日志，减少 IO 开销
-                        // println!("✅ Sent: {}", id); 
+                let started = Instant::now();
+                let mut stamps = signed_order.stamps;
+                let result = gw.submit_order(signed_order).await;
+                SUBMIT_LATENCY.with_label_values(&[exchange]).observe(started.elapsed().as_secs_f64());
+                stamps.http_response = mono_ns();
+                latency::observe(&stamps);
+                match result {
+                    Ok(id) => {
+                        // order_id 是成交回报与追踪 ID 关联的纽带
+                        info!(order_id = %id, "✅ Sent");
+                        // 发送期间该档位已经有了新报价：这张单作废
+                        if let Some(stale) = ticket.and_then(|t| ladder.lock().unwrap().submitted(t, &id)) {
+                            spawn_cancel(gw, stale);
+                        }
                     },
                     Err(e) => {
-                        // 只打印错误日志
-                        eprintln!("❌ Send Error: {}", e);
+                        error!(error = %e, "❌ Send error");
+                        if let Some(t) = ticket {
+                            ladder.lock().unwrap().failed(t);
+                        }
                     }
                 }
-            });
+            }.instrument(span));
         }
     });
 
     // ------------------------------------------------------------------
     // ✍️ 流水线 Part B: 签名员 (Signer) - CPU 密集型 & 主循环
     // ------------------------------------------------------------------
-    loop {
-        // 阻塞接收 ZMQ 消息
-        // (注: 真实场景如果想响应 Ctrl+C 退出，可以在 ZMQ 层做非阻塞处理，
-        // 但这里为了代码清晰，假设接收到 Kill Signal 后由 Gateway 负责清理)
+    while running.load(Ordering::SeqCst) {
+        // 带超时接收 ZMQ 消息 (退出前的撤单由 Kill Signal / cancel-all 负责)
         if let Some(msg) = sub.recv_raw_bytes() {
-            if let Ok(signal) = bincode::deserialize::<TradeSignal>(&msg) {
+            if let Ok(mut signal) = bincode::deserialize::<TradeSignal>(&msg) {
+                signal.stamps.exec_recv = mono_ns();
                 
                 // 🛑 优先级 0: 熔断信号检查 (Kill Switch)
                 // 必须在签名之前检查，确保最高优先级处理
+                // 熔断时撤掉所有场馆的挂单 (包括对冲腿)，而不仅是 target_exchange
                 if signal.logic_tag == 99 {
-                    let gw_cancel = gateway.clone();
-                    
-                    // 立即启动一个独立任务去执行撤单
-                    tokio::spawn(async move {
-                        
-                        // ♻️ 重试机制：尝试 3 次，防止网络抖动导致撤单失败
-                        for i in 1..=3 {
-                            match gw_cancel.cancel_all().await {
-                                Ok(_) => {
-                                    println!("✅ [EXEC] Emergency Cancel SUCCESS (Attempt {})", i);
-                                    break; // 成功即退出
-                                },
-                                Err(e) => {
-                                    eprintln!("❌ [EXEC] Cancel Failed (Attempt {}): {:?}", i, e);
-                                    // 失败稍微等一下再试
-                                    tokio::time::sleep(Duration::from_millis(200)).await;
-                                }
-                            }
-                        }
-                    });
+                    ladder.lock().unwrap().clear();
+                    for venue in venues.values() {
+                        let gw_cancel = venue.clone();
+                        // 立即启动一个独立任务去执行撤单
+                        tokio::spawn(cancel_all_with_retry(gw_cancel));
+                    }
                     
                     // 收到熔断信号后，跳过当前循环，不处理后续逻辑
                     continue; 
                 }
 
-                // 🚀 优先级 1: 正常订单处理
-                let gw_signer = gateway.clone();
+                // 🚀 优先级 1: 正常订单处理 (按目标场馆路由)
+                let Some(gw_signer) = venues.get(&signal.target_exchange).cloned() else {
+                    warn!(trace_id = %TraceId(signal.trace_id), exchange = ?signal.target_exchange, "⚠️ No adapter, dropping signal");
+                    continue;
+                };
+
+                // 签名前最后一道价格检查：按方向取整到 tick (买向下、卖向上)
+                // 不足最小下单量：报价改成撤档，其它订单直接丢弃
+                if !signal.size_usd.is_zero() {
+                    if let Some(r) = rules.get(&(signal.target_exchange, signal.symbol_id)) {
+                        signal.price = r.round(signal.price, signal.side);
+                        if !r.meets_min_size(signal.price, signal.size_usd) {
+                            warn!(trace_id = %TraceId(signal.trace_id), exchange = ?signal.target_exchange, symbol_id = signal.symbol_id,
+                                price = %signal.price, size_usd = %signal.size_usd, min_size_shares = %r.min_size_shares, "⚠️ Below venue minimum size");
+                            if signal.logic_tag != LOGIC_TAG_QUOTE {
+                                continue;
+                            }
+                            signal.size_usd = Decimal::ZERO;
+                        }
+                    }
+                }
+
+                // 做市报价按档位管理：未变化的档位不重复下单，改价先撤旧单，size_usd = 0 表示撤档
+                let mut ticket = None;
+                if signal.logic_tag == LOGIC_TAG_QUOTE {
+                    let key = slot_key(&signal);
+                    let mut book = ladder.lock().unwrap();
+                    if signal.size_usd.is_zero() {
+                        if let Some(old) = book.withdraw(key) {
+                            spawn_cancel(gw_signer.clone(), old);
+                        }
+                        continue;
+                    }
+                    let Some((t, old)) = book.quote(key, signal.price, signal.size_usd) else { continue };
+                    if let Some(old) = old {
+                        spawn_cancel(gw_signer.clone(), old);
+                    }
+                    ticket = Some(t);
+                }
                 let tx_inner = tx.clone();
+                let ladder_sign = ladder.clone();
+                let span = info_span!("sign",
+                    trace_id = %TraceId(signal.trace_id),
+                    exchange = ?signal.target_exchange,
+                    symbol_id = signal.symbol_id,
+                    side = ?signal.side,
+                    price = %signal.price,
+                    size_usd = %signal.size_usd);
                 
                 // 为了不阻塞 ZMQ 接收下一个信号，我们将“签名”也放入 Task 中
                 // 这样即使签名需要 1ms，也不会阻碍我们接收下一个行情信号
+                let exchange = exchange_label(signal.target_exchange);
                 tokio::spawn(async move {
                     // 1. 生成 EIP-712 签名 (CPU 计算)
-                    // create_signed_order 需要在 opinion_maker.rs 中实现 (参考 Part 2)
-                    match gw_signer.create_signed_order(signal).await {
-                        Ok(signed) => {
+                    let started = Instant::now();
+                    let result = gw_signer.create_signed_order(&signal).await;
+                    SIGN_LATENCY.with_label_values(&[exchange]).observe(started.elapsed().as_secs_f64());
+                    match result {
+                        Ok(mut signed) => {
+                            signed.stamps.sign_done = mono_ns();
                             // 2. 将签名好的包扔进通道，交给 Broadcaster 发送
                             // 如果通道满了 (Backpressure)，选择丢弃该订单，而不是阻塞
-                            if let Err(_) = tx_inner.send(signed).await {
-                                eprintln!("⚠️ [EXEC] Pipeline full! Dropping order to preserve latency.");
+                            if tx_inner.try_send((signed, ticket)).is_err() {
+                                ORDERS_DROPPED.with_label_values(&[exchange]).inc();
+                                warn!("⚠️ Pipeline full! Dropping order to preserve latency.");
+                                if let Some(t) = ticket {
+                                    ladder_sign.lock().unwrap().failed(t);
+                                }
                             }
                         },
                         Err(e) => {
-                            eprintln!("⚠️ [EXEC] Signing Failed: {:?}", e);
+                            warn!(error = ?e, "⚠️ Signing failed");
+                            if let Some(t) = ticket {
+                                ladder_sign.lock().unwrap().failed(t);
+                            }
                         }
                     }
-                });
+                }.instrument(span));
+            } else {
+                DECODE_FAILURES.with_label_values(&["SG"]).inc();
+            }
+        }
+    }
+    info!("🔫 Execution loop stopped");
+}
+
+/// 连接所有场馆 (执行循环与运维命令共用)，private_key 见 config::private_key
+pub async fn connect_venues(cfg: &AppConfig, private_key: &str) -> HashMap<Exchange, Arc<dyn ExchangeAdapter>> {
+    let mut venue_map: HashMap<Exchange, Arc<dyn ExchangeAdapter>> = HashMap::new();
+    venue_map.insert(Exchange::OpinionLabs, Arc::new(OpinionMakerGateway::new(private_key, &cfg.venues.opinion_api_url)));
+    // Polymarket 认证失败时只禁用对冲路由，不影响 Opinion 报价
+    match PolymarketClobGateway::connect(private_key, &cfg.venues.poly_clob_url, &cfg.poly_asset_ids()).await {
+        Ok(poly) => { venue_map.insert(Exchange::Polymarket, Arc::new(poly)); }
+        Err(e) => warn!(error = %e, "⚠️ Polymarket auth failed, hedging disabled"),
+    }
+    venue_map
+}
+
+// 定期用场馆挂单列表对账报价阶梯：成交回报不带订单号，档位的成交只能从这里发现
+async fn reconcile_ladder(venues: Arc<HashMap<Exchange, Arc<dyn ExchangeAdapter>>>, ladder: Arc<Mutex<LadderBook>>, interval: Duration, running: Arc<AtomicBool>) {
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    while running.load(Ordering::SeqCst) {
+        ticker.tick().await;
+        for venue in venues.values() {
+            let snapshot = ladder.lock().unwrap().resting(venue.exchange());
+            if snapshot.is_empty() {
+                continue;
+            }
+            match venue.open_orders().await {
+                Ok(open) => {
+                    let released = ladder.lock().unwrap().reconcile(&snapshot, &open);
+                    if released > 0 {
+                        info!(exchange = ?venue.exchange(), released, "🧾 Filled quote slots released");
+                    }
+                }
+                Err(e) => warn!(exchange = ?venue.exchange(), error = %e, "⚠️ Ladder reconcile failed"),
+            }
+        }
+    }
+}
+
+// 撤掉被新报价取代的挂单 (失败只记日志：订单可能已经成交)
+fn spawn_cancel(venue: Arc<dyn ExchangeAdapter>, order_id: String) {
+    tokio::spawn(async move {
+        if let Err(e) = venue.cancel_order(&order_id).await {
+            warn!(exchange = ?venue.exchange(), order_id = %order_id, error = %e, "⚠️ Cancel replaced order failed");
+        }
+    });
+}
+
+/// 撤掉某个场馆的全部挂单
+/// ♻️ 重试机制：尝试 3 次，防止网络抖动导致撤单失败
+pub async fn cancel_all_with_retry(venue: Arc<dyn ExchangeAdapter>) -> bool {
+    for i in 1..=3 {
+        match venue.cancel_all().await {
+            Ok(_) => {
+                info!(exchange = ?venue.exchange(), attempt = i, "✅ Emergency cancel success");
+                return true; // 成功即退出
+            },
+            Err(e) => {
+                error!(exchange = ?venue.exchange(), attempt = i, error = ?e, "❌ Cancel failed");
+                // 失败稍微等一下再试
+                tokio::time::sleep(Duration::from_millis(200)).await;
             }
         }
     }
-}
\ No newline at end of file
+    false
+}
+
+/// 打印场馆账户快照 (余额 + 挂单)
+pub async fn log_venue_snapshot(venue: &dyn ExchangeAdapter) {
+    match venue.balances().await {
+        Ok(balances) => {
+            for b in balances {
+                info!(exchange = ?venue.exchange(), asset = %b.asset, available = %b.available, locked = %b.locked, "💰 Balance");
+            }
+        }
+        Err(e) => warn!(exchange = ?venue.exchange(), error = %e, "⚠️ Fetch balances failed"),
+    }
+
+    match venue.open_orders().await {
+        Ok(orders) => {
+            for o in orders {
+                warn!(exchange = ?venue.exchange(), order_id = %o.order_id, side = ?o.side,
+                    size = %o.size, price = %o.price, filled = %o.filled, "⚠️ Stale order");
+            }
+        }
+        Err(e) => warn!(exchange = ?venue.exchange(), error = %e, "⚠️ Fetch open orders failed"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{Side, StageStamps};
+    use crate::infrastructure::messaging::ZmqPublisher;
+    use crate::testing::mock_opinion::MockOpinion;
+    use crate::testing::{free_port, test_wallet, TEST_PRIVATE_KEY};
+    use ethers::signers::Signer;
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+    use std::thread::JoinHandle;
+
+    fn signal(logic_tag: u8, trace_id: u64) -> TradeSignal {
+        TradeSignal {
+            strategy_id: 1,
+            target_exchange: Exchange::OpinionLabs,
+            symbol_id: 3,
+            side: Side::Buy,
+            price: dec!(0.41),
+            size_usd: dec!(25),
+            logic_tag,
+            post_only: false,
+            level: 0,
+            created_at_ns: 0,
+            trace_id,
+            stamps: StageStamps::default(),
+        }
+    }
+
+    // 反复发布直到条件成立 (ZMQ 订阅建立之前发出的消息会丢失)
+    async fn publish_until(publisher: &ZmqPublisher, signal: &TradeSignal, done: impl Fn() -> bool) -> bool {
+        for _ in 0..50 {
+            publisher.send_signal(signal);
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            if done() {
+                return true;
+            }
+        }
+        false
+    }
+
+    // 独立线程上运行的执行循环 (阻塞的 ZMQ 接收不能占用测试的 runtime)；drop 时停止并等待线程退出
+    struct LoopHandle {
+        running: Arc<AtomicBool>,
+        thread: Option<JoinHandle<()>>,
+    }
+
+    impl Drop for LoopHandle {
+        fn drop(&mut self) {
+            self.running.store(false, Ordering::SeqCst);
+            if let Some(thread) = self.thread.take() {
+                let _ = thread.join();
+            }
+        }
+    }
+
+    // 连接 mock Opinion；Polymarket 不可达，只禁用对冲路由
+    fn start_loop(mock: &MockOpinion) -> (ZmqPublisher, LoopHandle) {
+        let port = free_port();
+        let mut cfg = AppConfig::default();
+        cfg.venues.opinion_api_url = mock.url.clone();
+        cfg.venues.poly_clob_url = "http://127.0.0.1:1".to_string();
+        cfg.venues.open_orders_poll_ms = 100;
+        cfg.bus.signal_bind = format!("tcp://127.0.0.1:{}", port);
+        cfg.bus.signal_connect = format!("tcp://127.0.0.1:{}", port);
+        let publisher = ZmqPublisher::new(&cfg.bus.signal_bind);
+
+        let running = Arc::new(AtomicBool::new(true));
+        let flag = running.clone();
+        let thread = std::thread::spawn(move || {
+            tokio::runtime::Runtime::new().unwrap().block_on(run_execution_loop(cfg, TEST_PRIVATE_KEY.to_string(), flag));
+        });
+        (publisher, LoopHandle { running, thread: Some(thread) })
+    }
+
+    #[tokio::test]
+    async fn execution_loop_submits_signed_orders_and_cancels_on_kill() {
+        let mock = MockOpinion::start(test_wallet().address()).await;
+        let (publisher, _exec) = start_loop(&mock);
+
+        let submitted = publish_until(&publisher, &signal(1, 0x5eed), || !mock.state().orders.is_empty()).await;
+        assert!(submitted, "no order reached the mock venue");
+        {
+            let state = mock.state();
+            assert_eq!(state.bad_signatures, 0);
+            let order = &state.orders[0];
+            assert_eq!((order.market_id, order.side, order.price, order.size), (3, Side::Buy, dec!(0.41), dec!(25)));
+            assert!(order.client_order_id.starts_with("0000000000005eed-"));
+        }
+
+        // 熔断信号：撤掉全部挂单
+        let cancelled = publish_until(&publisher, &signal(99, 0), || mock.state().cancel_all_calls > 0).await;
+        assert!(cancelled, "kill switch did not cancel");
+        assert!(mock.state().orders.is_empty());
+    }
+
+    #[tokio::test]
+    async fn execution_loop_manages_quote_ladder() {
+        let mock = MockOpinion::start(test_wallet().address()).await;
+        let (publisher, _exec) = start_loop(&mock);
+
+        let prices = |m: &MockOpinion| -> Vec<Decimal> { m.state().orders.iter().map(|o| o.price).collect() };
+        let posts = |m: &MockOpinion| m.state().requests.iter().filter(|r| *r == "POST /order").count();
+
+        // 1. 第 0 档：重复发布同样的报价只下一张单
+        let level0 = signal(1, 1);
+        assert!(publish_until(&publisher, &level0, || posts(&mock) == 1).await);
+        for _ in 0..3 {
+            publisher.send_signal(&level0);
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(posts(&mock), 1);
+
+        // 2. 改价：撤旧挂新
+        let repriced = TradeSignal { price: dec!(0.42), ..signal(1, 2) };
+        assert!(publish_until(&publisher, &repriced, || prices(&mock) == vec![dec!(0.42)]).await);
+        assert!(mock.state().requests.iter().any(|r| r == "DELETE /order/mock-1"));
+
+        // 3. 第 1 档：同一方向可以同时挂多张
+        let level1 = TradeSignal { price: dec!(0.40), level: 1, ..signal(1, 3) };
+        assert!(publish_until(&publisher, &level1, || mock.state().orders.len() == 2).await);
+
+        // 4. size_usd = 0：撤掉第 1 档，第 0 档不受影响
+        let withdraw = TradeSignal { price: dec!(0), size_usd: dec!(0), level: 1, ..signal(1, 4) };
+        assert!(publish_until(&publisher, &withdraw, || prices(&mock) == vec![dec!(0.42)]).await);
+        assert_eq!(posts(&mock), 3);
+
+        // 5. 按 target_exchange 路由：Polymarket 没有连上，发往它的报价整条丢弃，
+        //    即使市场 / 方向 / 档位与 Opinion 的挂单相同，也不会落到 Opinion 或改动 Opinion 的档位
+        let poly = TradeSignal { target_exchange: Exchange::Polymarket, price: dec!(0.45), ..signal(1, 5) };
+        for _ in 0..5 {
+            publisher.send_signal(&poly);
+        }
+        // 同一发布端的消息按顺序到达：后面这条 Opinion 报价成交之前，上面的都已处理完
+        let level2 = TradeSignal { price: dec!(0.39), level: 2, ..signal(1, 6) };
+        assert!(publish_until(&publisher, &level2, || mock.state().orders.len() == 2).await);
+        assert_eq!(posts(&mock), 4);
+        assert_eq!(prices(&mock), vec![dec!(0.42), dec!(0.39)]);
+
+        // 6. 第 0 档全部成交：对账释放档位，同样的报价重新下单
+        let (order_id, size) = mock.state().orders.iter().find(|o| o.price == dec!(0.42)).map(|o| (o.order_id.clone(), o.size)).unwrap();
+        mock.fill(&order_id, size);
+        assert!(publish_until(&publisher, &repriced, || posts(&mock) == 5).await, "filled slot was not quoted again");
+        assert_eq!(prices(&mock), vec![dec!(0.39), dec!(0.42)]);
+    }
+}
@@ -0,0 +1,185 @@
+// File: src/execution/ladder.rs
+// 报价阶梯的执行层账本：每个 (场馆, 市场, 方向, 档位) 最多一张挂单
+//   - 同一档位的价格和金额都没变：不重复下单
+//   - 价格或金额变了：撤掉旧单再挂新单
+//   - size_usd = 0：撤掉该档
+// 下单是异步的，新报价到达时旧单可能还在路上；每次报价带一个递增的版本号，
+// 旧版本的订单回报到达时档位已被新报价占用，立即撤掉它
+// 成交回报里没有订单号，档位的成交靠定期对账 (场馆挂单列表) 发现：
+//   - 订单已不在挂单列表 (全部成交 / 被场馆撤销)：释放档位，下一次报价重新下单
+//   - 部分成交：档位金额按剩余比例缩小，下一次报价 (金额不同) 撤掉残单、按目标金额重挂
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+
+use crate::core::{Exchange, Side, TradeSignal};
+use crate::venues::OpenOrder;
+
+pub type SlotKey = (Exchange, u64, Side, u8);
+
+/// 档位上的一次报价 (档位 + 版本号)，随订单一起穿过签名 / 发送流水线
+#[derive(Clone, Copy, Debug)]
+pub struct SlotTicket {
+    pub key: SlotKey,
+    generation: u64,
+}
+
+pub fn slot_key(signal: &TradeSignal) -> SlotKey {
+    (signal.target_exchange, signal.symbol_id, signal.side, signal.level)
+}
+
+struct Slot {
+    price: Decimal,
+    size_usd: Decimal,
+    generation: u64,
+    order_id: Option<String>, // 场馆订单号 (发送成功后才知道)
+}
+
+#[derive(Default)]
+pub struct LadderBook {
+    slots: HashMap<SlotKey, Slot>,
+    next_generation: u64,
+}
+
+impl LadderBook {
+    /// 新报价：None = 与当前挂单相同，跳过；否则返回 (本次报价凭证, 需要先撤掉的旧单)
+    pub fn quote(&mut self, key: SlotKey, price: Decimal, size_usd: Decimal) -> Option<(SlotTicket, Option<String>)> {
+        if let Some(slot) = self.slots.get(&key) {
+            if slot.price == price && slot.size_usd == size_usd {
+                return None;
+            }
+        }
+        self.next_generation += 1;
+        let generation = self.next_generation;
+        let old = self.slots.insert(key, Slot { price, size_usd, generation, order_id: None });
+        Some((SlotTicket { key, generation }, old.and_then(|s| s.order_id)))
+    }
+
+    /// 发送成功：返回 Some(order_id) 表示这张单已被更新的报价取代，需要撤掉
+    pub fn submitted(&mut self, ticket: SlotTicket, order_id: &str) -> Option<String> {
+        match self.slots.get_mut(&ticket.key) {
+            Some(slot) if slot.generation == ticket.generation => {
+                slot.order_id = Some(order_id.to_string());
+                None
+            }
+            _ => Some(order_id.to_string()),
+        }
+    }
+
+    /// 签名或发送失败：释放档位，下一次报价会重新下单
+    pub fn failed(&mut self, ticket: SlotTicket) {
+        if self.slots.get(&ticket.key).is_some_and(|slot| slot.generation == ticket.generation) {
+            self.slots.remove(&ticket.key);
+        }
+    }
+
+    /// 撤档：返回该档位上需要撤掉的订单
+    pub fn withdraw(&mut self, key: SlotKey) -> Option<String> {
+        self.slots.remove(&key).and_then(|s| s.order_id)
+    }
+
+    /// 全部撤单之后清空账本
+    pub fn clear(&mut self) {
+        self.slots.clear();
+    }
+
+    /// 对账快照：某个场馆上已拿到订单号的档位；必须在请求挂单列表之前取
+    /// (之后才发送成功的订单不在列表里是正常的，不能参与对账)
+    pub fn resting(&self, exchange: Exchange) -> Vec<(SlotTicket, String)> {
+        self.slots.iter()
+            .filter(|(key, _)| key.0 == exchange)
+            .filter_map(|(&key, slot)| Some((SlotTicket { key, generation: slot.generation }, slot.order_id.clone()?)))
+            .collect()
+    }
+
+    /// 用场馆挂单列表对账 snapshot (见 resting)；期间已被新报价占用的档位不动
+    /// 返回被释放的档位数
+    pub fn reconcile(&mut self, snapshot: &[(SlotTicket, String)], open: &[OpenOrder]) -> usize {
+        let mut released = 0;
+        for (ticket, order_id) in snapshot {
+            let Some(slot) = self.slots.get_mut(&ticket.key).filter(|s| s.generation == ticket.generation) else { continue };
+            match open.iter().find(|o| &o.order_id == order_id) {
+                None => {
+                    self.slots.remove(&ticket.key);
+                    released += 1;
+                }
+                Some(o) if o.filled > Decimal::ZERO && o.size > Decimal::ZERO => {
+                    let remaining = ((o.size - o.filled) / o.size).max(Decimal::ZERO);
+                    slot.size_usd = (slot.size_usd * remaining).round_dp(6);
+                }
+                Some(_) => {}
+            }
+        }
+        released
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    const KEY: SlotKey = (Exchange::OpinionLabs, 1, Side::Buy, 0);
+
+    #[test]
+    fn unchanged_quote_is_skipped_and_changed_quote_replaces() {
+        let mut book = LadderBook::default();
+        let (t1, old) = book.quote(KEY, dec!(0.40), dec!(50)).unwrap();
+        assert!(old.is_none());
+        assert!(book.submitted(t1, "a").is_none());
+
+        assert!(book.quote(KEY, dec!(0.40), dec!(50)).is_none());
+        let (_, old) = book.quote(KEY, dec!(0.41), dec!(50)).unwrap();
+        assert_eq!(old.as_deref(), Some("a"));
+    }
+
+    #[test]
+    fn in_flight_order_superseded_by_newer_quote_is_cancelled() {
+        let mut book = LadderBook::default();
+        let (t1, _) = book.quote(KEY, dec!(0.40), dec!(50)).unwrap();
+        let (t2, old) = book.quote(KEY, dec!(0.41), dec!(50)).unwrap();
+        assert!(old.is_none()); // 第一张单还没有订单号
+
+        assert_eq!(book.submitted(t1, "a").as_deref(), Some("a"));
+        assert!(book.submitted(t2, "b").is_none());
+        assert_eq!(book.withdraw(KEY).as_deref(), Some("b"));
+    }
+
+    #[test]
+    fn failure_releases_only_its_own_generation() {
+        let mut book = LadderBook::default();
+        let (t1, _) = book.quote(KEY, dec!(0.40), dec!(50)).unwrap();
+        book.failed(t1);
+        // 档位已释放：同样的报价会重新下单
+        let (t2, _) = book.quote(KEY, dec!(0.40), dec!(50)).unwrap();
+        let (t3, _) = book.quote(KEY, dec!(0.41), dec!(50)).unwrap();
+        book.failed(t2);
+        assert!(book.submitted(t3, "c").is_none());
+    }
+
+    #[test]
+    fn reconcile_releases_filled_slots_and_shrinks_partial_fills() {
+        let open = |id: &str, filled| OpenOrder { order_id: id.to_string(), symbol_id: 1, side: Side::Buy, price: dec!(0.40), size: dec!(100), filled };
+        let level1 = (Exchange::OpinionLabs, 1, Side::Buy, 1);
+        let level2 = (Exchange::OpinionLabs, 1, Side::Buy, 2);
+        let poly = (Exchange::Polymarket, 1, Side::Buy, 0);
+        let mut book = LadderBook::default();
+        for (key, id) in [(KEY, "a"), (level1, "b"), (level2, "c"), (poly, "p")] {
+            let (t, _) = book.quote(key, dec!(0.40), dec!(50)).unwrap();
+            book.submitted(t, id);
+        }
+        let snapshot = book.resting(Exchange::OpinionLabs);
+        assert_eq!(snapshot.len(), 3);
+
+        // 对账期间 level2 改价：新报价不受这次对账影响
+        let (t, old) = book.quote(level2, dec!(0.39), dec!(50)).unwrap();
+        assert_eq!(old.as_deref(), Some("c"));
+
+        // "a" 已成交完、"b" 成交了 40%、"c" 已被撤掉 (不在列表里)
+        assert_eq!(book.reconcile(&snapshot, &[open("b", dec!(40))]), 1);
+        assert!(book.quote(KEY, dec!(0.40), dec!(50)).is_some(), "filled slot is quoted again");
+        let (_, old) = book.quote(level1, dec!(0.40), dec!(50)).unwrap();
+        assert_eq!(old.as_deref(), Some("b"), "partially filled order is replaced at the full size");
+        assert!(book.submitted(t, "d").is_none());
+        assert!(book.quote(poly, dec!(0.40), dec!(50)).is_none(), "other venues are not reconciled");
+    }
+}
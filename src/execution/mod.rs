@@ -1,2 +1,2 @@
-pub mod opinion_maker;
-pub mod event_loop;  // 因为 loop 是关键字，必须加 r#
\ No newline at end of file
+pub mod event_loop;  // 因为 loop 是关键字，文件名改为 event_loop
+pub mod ladder;
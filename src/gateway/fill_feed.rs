@@ -0,0 +1,140 @@
+use crate::infrastructure::messaging::ZmqPublisher;
+use crate::infrastructure::logging::TraceId;
+use crate::core::{Exchange, InventoryUpdate, Side};
+use crate::venues::{ExchangeAdapter, Fill};
+use rust_decimal::prelude::ToPrimitive;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, info};
+
+/// 通用成交监听器 (任意场馆)
+/// 轮询场馆的成交回报，转换为 InventoryUpdate 推入总线 (策略引擎 / 对冲器据此更新账本)
+pub async fn run_fill_listener(venue: Arc<dyn ExchangeAdapter>, zmq_pub: ZmqPublisher) {
+    // 只关心启动之后的成交，历史仓位由策略引擎的状态文件负责恢复
+    let mut cursor = FillCursor::new(chrono::Utc::now().timestamp_millis());
+
+    loop {
+        match venue.fills(cursor.since_ms()).await {
+            Ok(fills) => {
+                // 同一笔成交会被重复返回 (游标包含边界)，只发布第一次
+                for fill in cursor.accept_batch(fills) {
+                    info!(trace_id = %TraceId(fill.trace_id), exchange = ?venue.exchange(), order_id = %fill.order_id,
+                        symbol_id = fill.symbol_id, side = ?fill.side, price = %fill.price, size = %fill.size, "💵 Fill");
+                    zmq_pub.send_inventory_update(&inventory_update(venue.exchange(), &fill));
+                }
+            }
+            Err(e) => {
+                error!(exchange = ?venue.exchange(), error = %e, "❌ Fetch fills failed");
+            }
+        }
+
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+}
+
+/// 成交游标：按时间戳包含边界查询，边界上的成交按成交 ID 去重
+/// 游标若取 "最新成交时间 + 1"，稍后才出现的同一毫秒成交会被跳过
+pub struct FillCursor {
+    since_ms: i64,
+    seen: HashSet<String>, // 时间戳 == since_ms 的已发布成交
+}
+
+impl FillCursor {
+    pub fn new(since_ms: i64) -> Self {
+        Self { since_ms, seen: HashSet::new() }
+    }
+
+    pub fn since_ms(&self) -> i64 {
+        self.since_ms
+    }
+
+    /// 新成交返回 true 并推进游标；重复的或早于游标的成交返回 false
+    pub fn accept(&mut self, fill: &Fill) -> bool {
+        if fill.timestamp_ms < self.since_ms || self.seen.contains(&fill.fill_id) {
+            return false;
+        }
+        if fill.timestamp_ms > self.since_ms {
+            self.since_ms = fill.timestamp_ms;
+            self.seen.clear();
+        }
+        self.seen.insert(fill.fill_id.clone());
+        true
+    }
+
+    /// 一次轮询返回的整批成交：场馆不保证顺序 (可能最新的在前)，先按 (时间戳, 成交 ID) 升序排好再逐笔 accept，
+    /// 否则第一笔就把游标推到最新，同一批里更早的成交全部被当成过期丢掉
+    pub fn accept_batch(&mut self, mut fills: Vec<Fill>) -> Vec<Fill> {
+        fills.sort_by(|a, b| (a.timestamp_ms, &a.fill_id).cmp(&(b.timestamp_ms, &b.fill_id)));
+        fills.retain(|fill| self.accept(fill));
+        fills
+    }
+}
+
+/// 成交回报 -> 库存变动
+/// 买入：仓位增加，现金流出；卖出：仓位减少，现金流入 (均扣除手续费)
+pub fn inventory_update(exchange: Exchange, fill: &Fill) -> InventoryUpdate {
+    let size = fill.size.to_f64().unwrap_or(0.0);
+    let gross = fill.price * fill.size;
+    let (change, cash_flow) = match fill.side {
+        Side::Buy => (size, -(gross + fill.fee)),
+        Side::Sell => (-size, gross - fill.fee),
+    };
+    InventoryUpdate {
+        exchange,
+        symbol_id: fill.symbol_id,
+        change,
+        cost_usd: cash_flow.to_f64().unwrap_or(0.0),
+        trace_id: fill.trace_id,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn fill(fill_id: &str, timestamp_ms: i64) -> Fill {
+        Fill {
+            fill_id: fill_id.to_string(),
+            order_id: "o".to_string(),
+            symbol_id: 1,
+            side: Side::Buy,
+            price: dec!(0.5),
+            size: dec!(10),
+            fee: dec!(0),
+            timestamp_ms,
+            trace_id: 0,
+        }
+    }
+
+    #[test]
+    fn cursor_dedupes_on_the_boundary_and_keeps_late_fills_of_the_same_millisecond() {
+        let mut cursor = FillCursor::new(1_000);
+        assert!(!cursor.accept(&fill("old", 999)));
+        assert!(cursor.accept(&fill("a", 1_500)));
+        assert_eq!(cursor.since_ms(), 1_500);
+
+        // 下一次轮询从 1500 (含) 开始：a 再次返回，同一毫秒新出现的 b 不能丢
+        assert!(!cursor.accept(&fill("a", 1_500)));
+        assert!(cursor.accept(&fill("b", 1_500)));
+        assert!(cursor.accept(&fill("c", 1_600)));
+        assert!(!cursor.accept(&fill("b", 1_500)));
+        assert!(!cursor.accept(&fill("c", 1_600)));
+    }
+
+    #[test]
+    fn newest_first_batch_is_accepted_in_full_exactly_once() {
+        let mut cursor = FillCursor::new(1_000);
+        let batch = || vec![fill("c", 1_600), fill("b", 1_500), fill("a2", 1_200), fill("a1", 1_200), fill("old", 999)];
+        let ids: Vec<_> = cursor.accept_batch(batch()).into_iter().map(|f| f.fill_id).collect();
+        assert_eq!(ids, vec!["a1", "a2", "b", "c"]);
+        assert_eq!(cursor.since_ms(), 1_600);
+
+        // 下一次轮询返回同一批 (加一笔新的)：只发布新成交
+        let mut next = batch();
+        next.insert(0, fill("d", 1_700));
+        let ids: Vec<_> = cursor.accept_batch(next).into_iter().map(|f| f.fill_id).collect();
+        assert_eq!(ids, vec!["d"]);
+    }
+}
@@ -1,2 +1,5 @@
 pub mod poly_feed;
-pub mod opinion_feed;
\ No newline at end of file
+pub mod opinion_feed;
+pub mod fill_feed;
+pub mod replay;
+pub mod recorder;
@@ -1,97 +1,226 @@
-use ethers::prelude::*;
-use ethers::types::transaction::eip712::Eip712;
-use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use crate::infrastructure::messaging::ZmqPublisher;
+use crate::core::{BookSide, OrderBookUpdate, Exchange, StageStamps};
+use crate::infrastructure::latency::mono_ns;
+use crate::infrastructure::logging::new_trace_id;
+use crate::gateway::fill_feed::run_fill_listener;
+use crate::venues::opinion::OpinionMakerGateway;
+use crate::venues::{json_decimal, send_recorded, VenueResult};
+use futures_util::future::join_all;
 use rust_decimal::Decimal;
-use crate::core::{TradeSignal, Side}; 
-
-// --- A. 定义 Opinion Labs 的订单结构 (EIP-712) ---
-#[derive(Debug, Clone, Eip712, EthAbiType, Serialize, Deserialize)]
-#[eip712(
-    name = "OpinionExchange",
-    version = "1",
-    chainId = 137,
-    verifyingContract = "0x..." // ⚠️ 务必替换为真实合约地址
-)]
-pub struct LimitOrder {
-    pub salt: u128,
-    pub maker: Address,
-    pub market_id: U256,
-    pub side: u8,
-    pub price: U256,
-    pub size: U256,
-    pub expiration: u64,
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::MissedTickBehavior;
+use tracing::{info, warn};
+
+/// 启动 Opinion 成交监听器
+/// private_key 与执行层使用同一个账户 (见 config::private_key)
+pub async fn run_opinion_chain_listener(zmq_pub: ZmqPublisher, api_url: String, private_key: String) {
+    let venue = Arc::new(OpinionMakerGateway::new(&private_key, &api_url));
+
+    run_fill_listener(venue, zmq_pub).await;
+}
+
+/// 没有变化的快照每隔这么多轮重新发布一次：引擎按本地接收时间判断盘口是否过期，
+/// 安静但在线的盘口不能因为不发布而被当成过期
+pub const KEEPALIVE_POLLS: u32 = 2;
+
+/// 启动 Opinion 盘口轮询：每 poll_ms 拉一次各市场的盘口快照，以 Exchange::OpinionLabs 发布到 "MD" 总线
+/// 每次轮询都是完整快照；请求失败只跳过这一轮，下一轮自动重试 (不需要重连逻辑)
+/// 同一轮里各市场并发请求：一个慢市场 (最多 2 秒超时) 不会拖住其他市场的盘口
+pub async fn run_opinion_book_feed(zmq_pub: ZmqPublisher, api_url: String, market_ids: Vec<u64>, poll_ms: u64) {
+    let client = reqwest::Client::builder()
+        .tcp_nodelay(true)
+        .timeout(Duration::from_secs(2))
+        .build()
+        .expect("Failed to create HTTP client");
+
+    let mut interval = tokio::time::interval(Duration::from_millis(poll_ms));
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    // 每个市场最近一次发布的快照 (+ 之后没有变化的轮数)：比它旧的是乱序到达的过期数据，
+    // 内容相同的只每 KEEPALIVE_POLLS 轮重新发布一次
+    let mut last: HashMap<u64, (i64, BookSide, BookSide, u32)> = HashMap::new();
+    // 拉取失败中的市场 (只在失败 / 恢复时各打一次日志，避免每轮刷屏)
+    let mut failing: HashSet<u64> = HashSet::new();
+
+    info!(url = %api_url, markets = market_ids.len(), poll_ms, "👂 Polling Opinion order books...");
+    loop {
+        interval.tick().await;
+        let polls = join_all(market_ids.iter().map(|&market_id| {
+            let (client, api_url) = (&client, &api_url);
+            async move { (market_id, fetch_book(client, api_url, market_id).await, mono_ns()) }
+        })).await;
+
+        for (market_id, result, recv_ns) in polls {
+            let update = match result {
+                Ok(v) => parse_opinion_book(market_id, &v),
+                Err(e) => {
+                    if failing.insert(market_id) {
+                        warn!(market_id, error = %e, "⚠️ Opinion book poll failed");
+                    }
+                    continue;
+                }
+            };
+            if failing.remove(&market_id) {
+                info!(market_id, "✅ Opinion book poll recovered");
+            }
+            let Some(mut update) = update else {
+                warn!(market_id, "⚠️ Malformed or crossed Opinion book dropped");
+                continue;
+            };
+
+            if let Some((ts, bids, asks, unchanged)) = last.get_mut(&market_id) {
+                if update.timestamp_ns < *ts {
+                    warn!(market_id, ts = update.timestamp_ns, last = *ts, "⚠️ Stale book snapshot dropped");
+                    continue;
+                }
+                if update.timestamp_ns == *ts && update.bids == *bids && update.asks == *asks {
+                    *unchanged += 1;
+                    if *unchanged < KEEPALIVE_POLLS {
+                        continue;
+                    }
+                }
+            }
+            last.insert(market_id, (update.timestamp_ns, update.bids.clone(), update.asks.clone(), 0));
+
+            update.stamps.ws_recv = recv_ns;
+            update.stamps.md_publish = mono_ns();
+            zmq_pub.send_book_update(&update);
+        }
+    }
+}
+
+// 公开接口，不需要签名
+async fn fetch_book(client: &reqwest::Client, api_url: &str, market_id: u64) -> VenueResult<serde_json::Value> {
+    let req = client.get(format!("{}/orderbook/{}", api_url, market_id));
+    let resp = send_recorded(Exchange::OpinionLabs, req).await?;
+    if !resp.status().is_success() {
+        return Err(format!("HTTP {}", resp.status()).into());
+    }
+    Ok(resp.json().await?)
 }
 
-// --- B. 执行网关 ---
-pub struct OpinionMakerGateway {
-    wallet: LocalWallet,
-    http_client: reqwest::Client,
-    api_url: String,
+/// 解析器：{ marketId, timestamp (ms), bids: [{price, size}], asks: [...] }
+/// 档位按优先级重新排序、去掉数量为 0 的档位；缺少时间戳、任何一档无法解析、或者买一 >= 卖一，整个快照丢弃
+/// (没有时间戳就无法判断乱序，也无法判断盘口是否过期)
+fn parse_opinion_book(market_id: u64, v: &serde_json::Value) -> Option<OrderBookUpdate> {
+    if v["marketId"].as_u64().is_some_and(|id| id != market_id) {
+        return None;
+    }
+    let timestamp = v["timestamp"].as_i64()?;
+
+    let mut bids = parse_levels(&v["bids"])?;
+    let mut asks = parse_levels(&v["asks"])?;
+    bids.sort_by_key(|l| std::cmp::Reverse(l.0));
+    asks.sort_by_key(|l| l.0);
+    if let (Some(bid), Some(ask)) = (bids.first(), asks.first()) {
+        if bid.0 >= ask.0 {
+            return None;
+        }
+    }
+
+    Some(OrderBookUpdate {
+        exchange: Exchange::OpinionLabs,
+        symbol_id: market_id, // Opinion Market ID
+        timestamp_ns: timestamp * 1_000_000, // ms -> ns
+        trace_id: new_trace_id(),
+        bids,
+        asks,
+        stamps: StageStamps::default(),
+    })
 }
 
-impl OpinionMakerGateway {
-    pub fn new(private_key: &str, api_url: &str) -> Self {
-        let wallet = private_key.parse::<LocalWallet>().unwrap()
-            .with_chain_id(137u64);
-            
-        Self {
-            wallet,
-            http_client: reqwest::Client::new(),
-            api_url: api_url.to_string(),
+fn parse_levels(v: &serde_json::Value) -> Option<BookSide> {
+    let mut levels = BookSide::new();
+    for level in v.as_array().map(|a| a.as_slice()).unwrap_or_default() {
+        let price = json_decimal(&level["price"])?;
+        let size = json_decimal(&level["size"])?;
+        if price <= Decimal::ZERO || price >= Decimal::ONE {
+            return None;
         }
+        if size > Decimal::ZERO {
+            levels.push((price, size));
+        }
+    }
+    Some(levels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::messaging::ZmqSubscriber;
+    use crate::testing::mock_opinion::MockOpinion;
+    use crate::testing::{free_port, test_wallet};
+    use ethers::signers::Signer;
+    use rust_decimal_macros::dec;
+
+    fn book(ts_ms: i64, bid: &str) -> serde_json::Value {
+        serde_json::json!({
+            "marketId": 7,
+            "timestamp": ts_ms,
+            "bids": [{ "price": "0.40", "size": "50" }, { "price": bid, "size": "100" }],
+            "asks": [{ "price": "0.60", "size": "100" }, { "price": "0.61", "size": "0" }],
+        })
+    }
+
+    #[test]
+    fn parses_and_normalises_snapshots() {
+        let update = parse_opinion_book(7, &book(1_000, "0.45")).unwrap();
+        assert_eq!((update.exchange, update.symbol_id, update.timestamp_ns), (Exchange::OpinionLabs, 7, 1_000_000_000));
+        assert_eq!(update.bids.to_vec(), vec![(dec!(0.45), dec!(100)), (dec!(0.40), dec!(50))]);
+        assert_eq!(update.asks.to_vec(), vec![(dec!(0.60), dec!(100))]);
+
+        assert!(parse_opinion_book(8, &book(1_000, "0.45")).is_none()); // 市场不符
+        assert!(parse_opinion_book(7, &book(1_000, "0.60")).is_none()); // 买一 >= 卖一
+        assert!(parse_opinion_book(7, &book(1_000, "abc")).is_none());
+
+        let mut no_timestamp = book(1_000, "0.45");
+        no_timestamp.as_object_mut().unwrap().remove("timestamp");
+        assert!(parse_opinion_book(7, &no_timestamp).is_none());
     }
 
-    /// 核心方法：将策略信号转化为 EIP-712 签名并发送
-    pub async fn place_order(&self, signal: TradeSignal) -> Result<String, Box<dyn std::error::Error>> {
-        let order_struct = LimitOrder {
-            salt: rand::random::<u128>(),
-            maker: self.wallet.address(),
-            market_id: U256::from(signal.symbol_id),
-            side: if signal.side == Side::Buy { 0 } else { 1 },
-            // [关键修复] 使用 6 位精度 (USDC)
-            price: ethers::utils::parse_units(signal.price, 6)?.into(), 
-            size: ethers::utils::parse_units(signal.size_usd, 6)?.into(),
-            expiration: 0, 
-        };
-
-        let signature = self.wallet.sign_typed_data(&order_struct).await?;
-
-        let payload = serde_json::json!({
-            "order": order_struct,
-            "signature": signature.to_string(),
-            "strategy_tag": "RUST_MM_BOT"
-        });
-
-        let resp = self.http_client
-            .post(format!("{}/order", self.api_url))
-            .json(&payload)
-            .send()
-            .await?;
-
-        if resp.status().is_success() {
-            let resp_json: serde_json::Value = resp.json().await?;
-            Ok(resp_json["orderId"].as_str().unwrap_or("").to_string())
-        } else {
-            Err(format!("API Error: {:?}", resp.text().await?).into())
+    // 等到 mock 又收到 n 次盘口请求：第 n 次请求到达时，之前各轮的快照都已经处理 (发布或丢弃) 完
+    async fn wait_for_polls(mock: &MockOpinion, market_id: u64, n: usize) {
+        let path = format!("GET /orderbook/{}", market_id);
+        let count = || mock.state().requests.iter().filter(|r| **r == path).count();
+        let target = count() + n;
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+        while count() < target {
+            assert!(tokio::time::Instant::now() < deadline, "feed stopped polling {}", path);
+            tokio::time::sleep(Duration::from_millis(5)).await;
         }
     }
 
-    /// 极速撤单 (Batch Cancel)
-    /// 做市商保命键：一键撤回所有报价
-    pub async fn cancel_all(&self) -> Result<(), Box<dyn std::error::Error>> {
-        let timestamp = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)?.as_millis();
-        
-        // 签名消息格式需参考官方文档，这里假设为 "CANCEL_ALL_{ts}"
-        let signature = self.wallet.sign_message(format!("CANCEL_ALL_{}", timestamp)).await?;
-
-        self.http_client
-            .delete(format!("{}/orders", self.api_url))
-            .header("X-Signature", signature.to_string())
-            .header("X-Timestamp", timestamp.to_string())
-            .send()
-            .await?;
-            
-        Ok(())
+    #[tokio::test]
+    async fn publishes_changed_snapshots_and_survives_errors() {
+        let mock = MockOpinion::start(test_wallet().address()).await;
+        let endpoint = format!("tcp://127.0.0.1:{}", free_port());
+        let publisher = ZmqPublisher::new(&endpoint);
+        let sub = ZmqSubscriber::new(&endpoint, "MD");
+        sub.set_recv_timeout(200);
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        // 还没有盘口 (404)：跳过，之后的轮询照常进行
+        let feed = tokio::spawn(run_opinion_book_feed(publisher, mock.url.clone(), vec![7], 20));
+        wait_for_polls(&mock, 7, 2).await;
+        mock.set_book(7, book(2_000, "0.45"));
+        wait_for_polls(&mock, 7, 4).await; // 同一快照被轮询多次：每 KEEPALIVE_POLLS 轮才重新发布一次
+        mock.set_book(7, book(1_000, "0.44")); // 乱序
+        wait_for_polls(&mock, 7, 2).await;
+        mock.set_book(7, book(3_000, "0.46"));
+        wait_for_polls(&mock, 7, 2).await;
+        feed.abort();
+
+        let mut bids = Vec::new();
+        while let Some(msg) = sub.recv_raw_bytes() {
+            let update = bincode::deserialize::<OrderBookUpdate>(&msg).unwrap();
+            assert!(update.trace_id != 0 && update.stamps.md_publish != 0);
+            bids.push(update.bids[0].0);
+        }
+        // 乱序的 0.44 从未发布；没有变化的 0.45 至少重新发布过一次 (保活)
+        assert!(bids.iter().filter(|b| **b == dec!(0.45)).count() >= 2, "{:?}", bids);
+        bids.dedup();
+        assert_eq!(bids, vec![dec!(0.45), dec!(0.46)]);
     }
-}
\ No newline at end of file
+}
@@ -1,21 +1,28 @@
 use crate::infrastructure::messaging::ZmqPublisher;
-use market_maker_core::{OrderBookUpdate, Exchange, Side};
+use crate::core::{OrderBookUpdate, Exchange, StageStamps};
+use crate::infrastructure::latency::mono_ns;
+use crate::infrastructure::logging::new_trace_id;
+use crate::gateway::fill_feed::run_fill_listener;
+use crate::venues::polymarket::{parse_asset_id, PolymarketClobGateway};
+use std::collections::HashMap;
+use std::sync::Arc;
 use futures_util::{StreamExt, SinkExt};
 use tokio_tungstenite::{connect_async, tungstenite::protocol::Message};
 use url::Url;
 use rust_decimal::Decimal;
 use std::str::FromStr;
 use smallvec::smallvec;
+use tracing::{error, info, warn};
 
 /// 启动监听器
-pub async fn run_poly_feed_handler(zmq_pub: ZmqPublisher, market_ids: Vec<String>) {
-    let url = Url::parse("wss://ws-poly.polymarket.com").expect("Invalid URL");
+pub async fn run_poly_feed_handler(zmq_pub: ZmqPublisher, market_ids: Vec<String>, ws_url: String) {
+    let url = Url::parse(&ws_url).expect("Invalid URL");
 
-    println!("👂 [Gateway] Connecting to Polymarket WS...");
+    info!(url = %ws_url, "👂 Connecting to Polymarket WS...");
     
     // 1. 建立长连接 (Handshake)
     let (ws_stream, _) = connect_async(url).await.expect("Failed to connect");
-    println!("✅ [Gateway] Connected!");
+    info!("✅ Connected!");
 
     let (mut write, mut read) = ws_stream.split();
 
@@ -29,13 +36,25 @@ pub async fn run_poly_feed_handler(zmq_pub: ZmqPublisher, market_ids: Vec<String
     
     write.send(Message::Text(sub_msg.to_string())).await.expect("Subscribe failed");
 
+    // 每个资产最近一次快照的时间戳：比它旧的快照是乱序到达的过期数据，直接丢弃
+    let mut last_ts: HashMap<u64, i64> = HashMap::new();
+
     // 3. 死循环监听 (Event Loop)
     // 这里不是 Polling，是 Reactor 模式，有数据才会动
     while let Some(msg) = read.next().await {
         match msg {
             Ok(Message::Text(text)) => {
+                let recv_ns = mono_ns();
                 // 收到 JSON 文本 -> 解析 -> 转换 -> 广播
-                if let Some(update) = parse_poly_json(&text) {
+                if let Some(mut update) = parse_poly_json(&text) {
+                    let last = last_ts.entry(update.symbol_id).or_default();
+                    if update.timestamp_ns < *last {
+                        warn!(symbol_id = update.symbol_id, ts = update.timestamp_ns, last = *last, "⚠️ Stale book snapshot dropped");
+                        continue;
+                    }
+                    *last = update.timestamp_ns;
+                    update.stamps.ws_recv = recv_ns;
+                    update.stamps.md_publish = mono_ns();
                     // 🚀 这里的 send 就是把数据推入 ZMQ 管道
                     // 策略引擎那边就会收到数据
                     zmq_pub.send_book_update(&update);
@@ -46,7 +65,7 @@ pub async fn run_poly_feed_handler(zmq_pub: ZmqPublisher, market_ids: Vec<String
                 write.send(Message::Pong(payload)).await.unwrap_or(());
             }
             Err(e) => {
-                println!("❌ WS Error: {:?}", e);
+                error!(error = ?e, "❌ WS error");
                 break; // 真实环境这里需要写重连逻辑 (Reconnection)
             }
             _ => {}
@@ -54,6 +73,14 @@ pub async fn run_poly_feed_handler(zmq_pub: ZmqPublisher, market_ids: Vec<String
     }
 }
 
+/// 启动 Polymarket 成交监听器 (对冲腿的成交回报)
+pub async fn run_poly_fill_listener(zmq_pub: ZmqPublisher, market_ids: Vec<String>, clob_url: String, private_key: String) {
+    match PolymarketClobGateway::connect(&private_key, &clob_url, &market_ids).await {
+        Ok(venue) => run_fill_listener(Arc::new(venue), zmq_pub).await,
+        Err(e) => warn!(error = %e, "⚠️ Polymarket auth failed, hedge fills unavailable"),
+    }
+}
+
 /// 解析器：将 Polymarket 的脏 JSON 清洗为我们的干净结构体
 fn parse_poly_json(raw: &str) -> Option<OrderBookUpdate> {
     let v: serde_json::Value = serde_json::from_str(raw).ok()?;
@@ -66,8 +93,8 @@ fn parse_poly_json(raw: &str) -> Option<OrderBookUpdate> {
     // 提取字段 (这里简化了错误处理)
     let timestamp = v["timestamp"].as_i64().unwrap_or(0);
     let asset_id_str = v["asset_id"].as_str()?;
-    let asset_id_u256 = U256::from_str_radix(asset_id_str.trim_start_matches("0x"), 16).ok()?;
-    let symbol_id = asset_id_u256.low_u64();
+    // 与执行层 (PolymarketClobGateway) 使用同一套 ID 映射：取 Token ID 低 64 位
+    let symbol_id = parse_asset_id(asset_id_str)?.low_u64();
     // 解析 Bids
     let mut bids = smallvec![];
     if let Some(arr) = v["bids"].as_array() {
@@ -91,9 +118,125 @@ fn parse_poly_json(raw: &str) -> Option<OrderBookUpdate> {
     // 返回我们在 Module 1 定义的标准结构体
     Some(OrderBookUpdate {
         exchange: Exchange::Polymarket,
-        symbol_id: u64::from_str_radix(&asset_id_str[2..], 16).unwrap_or(0), // 简单的 hash 模拟
+        symbol_id, // 取 Asset ID 的低 64 位作为内部 ID
         timestamp_ns: timestamp * 1_000_000, // ms -> ns
+        trace_id: new_trace_id(), // 每个快照一个追踪 ID，由它触发的报价沿用
         bids,
         asks,
+        stamps: StageStamps::default(),
     })
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::messaging::ZmqSubscriber;
+    use crate::testing::free_port;
+    use crate::testing::mock_poly_ws::{MockPolyWs, WsStep};
+    use rust_decimal_macros::dec;
+    use std::time::Duration;
+
+    const ASSET_A: &str = "21742633143463906290569050155826241533067272736897614950488156847949938836455";
+    const ASSET_B: &str = "48331043336612883890938759509493159234755048973500640148014422747788308965732";
+
+    fn book(asset: &str, ts_ms: i64, bid: &str) -> WsStep {
+        WsStep::Text(serde_json::json!({
+            "event_type": "order_book_update",
+            "asset_id": asset,
+            "timestamp": ts_ms,
+            "bids": [{ "price": bid, "size": "100" }],
+            "asks": [{ "price": "0.60", "size": "100" }],
+        }).to_string())
+    }
+
+    fn symbol(asset: &str) -> u64 {
+        parse_asset_id(asset).unwrap().low_u64()
+    }
+
+    // 跑完整个脚本 (feed 在连接关闭后返回)，收集总线上发出的全部盘口
+    async fn run_feed(script: Vec<WsStep>) -> (MockPolyWs, Vec<OrderBookUpdate>) {
+        let mock = MockPolyWs::start(script).await;
+        let endpoint = format!("tcp://127.0.0.1:{}", free_port());
+        let publisher = ZmqPublisher::new(&endpoint);
+        let sub = ZmqSubscriber::new(&endpoint, "MD");
+        sub.set_recv_timeout(200);
+        // 等订阅关系建立，否则最早的几条消息会被 PUB 丢弃
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let feed = run_poly_feed_handler(publisher, vec![ASSET_A.to_string(), ASSET_B.to_string()], mock.url.clone());
+        tokio::time::timeout(Duration::from_secs(5), feed).await.expect("feed did not stop after disconnect");
+
+        let mut updates = Vec::new();
+        while let Some(msg) = sub.recv_raw_bytes() {
+            updates.push(bincode::deserialize::<OrderBookUpdate>(&msg).unwrap());
+        }
+        (mock, updates)
+    }
+
+    #[tokio::test]
+    async fn replays_captured_session() {
+        let fixture = include_str!("../testing/fixtures/poly_ws_session.jsonl");
+        let (mock, updates) = run_feed(WsStep::fixture(fixture)).await;
+
+        // 订阅指令带上全部资产
+        let subscribe = mock.received()[0].to_text().unwrap().to_string();
+        let subscribe: serde_json::Value = serde_json::from_str(&subscribe).unwrap();
+        assert_eq!(subscribe["assets_ids"], serde_json::json!([ASSET_A, ASSET_B]));
+
+        // price_change 不是盘口快照，不发布
+        assert_eq!(updates.len(), 3);
+        let first = &updates[0];
+        assert_eq!((first.exchange, first.symbol_id, first.timestamp_ns), (Exchange::Polymarket, symbol(ASSET_A), 1_760_000_000_000_000_000));
+        assert_eq!(first.bids.to_vec(), vec![(dec!(0.48), dec!(1200)), (dec!(0.47), dec!(350.5))]);
+        assert_eq!(first.asks.to_vec(), vec![(dec!(0.52), dec!(800)), (dec!(0.53), dec!(95))]);
+        assert!(first.trace_id != 0 && first.stamps.ws_recv != 0 && first.stamps.md_publish != 0);
+
+        assert_eq!(updates[1].symbol_id, symbol(ASSET_B));
+        assert_eq!(updates[1].asks.len(), 2);
+        assert_eq!(updates[2].bids[0], (dec!(0.49), dec!(40)));
+        assert!(updates[2].asks.is_empty());
+    }
+
+    #[tokio::test]
+    async fn answers_pings_and_skips_malformed_messages() {
+        let (mock, updates) = run_feed(vec![
+            WsStep::Ping(b"hb-1".to_vec()),
+            WsStep::Text("{\"event_type\": \"order_book_update\", \"bids\": [".to_string()),
+            WsStep::Text(r#"{"event_type":"order_book_update","timestamp":1,"bids":[],"asks":[]}"#.to_string()),
+            WsStep::Text(format!(r#"{{"event_type":"order_book_update","asset_id":"{}","timestamp":2,"bids":[{{"price":"abc","size":"1"}}],"asks":[]}}"#, ASSET_A)),
+            book(ASSET_A, 3, "0.50"),
+        ]).await;
+
+        assert!(mock.received().iter().any(|m| *m == tokio_tungstenite::tungstenite::Message::Pong(b"hb-1".to_vec())));
+        assert_eq!(updates.len(), 1);
+        assert_eq!(updates[0].timestamp_ns, 3_000_000);
+    }
+
+    #[tokio::test]
+    async fn drops_out_of_order_snapshots_per_asset() {
+        let (_, updates) = run_feed(vec![
+            book(ASSET_A, 1_000, "0.40"),
+            book(ASSET_A, 3_000, "0.41"),
+            book(ASSET_B, 2_000, "0.30"), // 其他资产的时间线互不影响
+            book(ASSET_A, 2_000, "0.42"), // 乱序
+            book(ASSET_A, 3_000, "0.43"), // 同一毫秒的新快照保留
+            book(ASSET_A, 4_000, "0.44"),
+        ]).await;
+
+        let bids: Vec<Decimal> = updates.iter().map(|u| u.bids[0].0).collect();
+        assert_eq!(bids, vec![dec!(0.40), dec!(0.41), dec!(0.30), dec!(0.43), dec!(0.44)]);
+    }
+
+    #[tokio::test]
+    async fn stops_on_disconnect() {
+        let (_, updates) = run_feed(vec![
+            book(ASSET_A, 1, "0.40"),
+            WsStep::Sleep(Duration::from_millis(20)),
+            WsStep::Drop,
+            book(ASSET_A, 2, "0.41"),
+        ]).await;
+        assert_eq!(updates.len(), 1);
+
+        let (_, updates) = run_feed(vec![book(ASSET_B, 1, "0.40"), WsStep::Close, book(ASSET_B, 2, "0.41")]).await;
+        assert_eq!(updates.len(), 1);
+    }
+}
@@ -0,0 +1,70 @@
+// File: src/gateway/recorder.rs
+// 录制组件：同时订阅行情总线 (MD / IV) 与信号总线 (SG)，把消息原样写入压缩日志 (journal)
+// 日志用于事后排查和参数研究，读取见 infrastructure::journal::JournalReader
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, info};
+
+use crate::config::BusConfig;
+use crate::infrastructure::journal::{JournalWriter, RecordKind};
+use crate::infrastructure::messaging::ZmqSubscriber;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RecorderConfig {
+    pub enabled: bool,          // `all` 模式下是否同时启动录制
+    pub dir: String,            // 日志目录 (每天一个 .mmj 数据文件 + .idx 索引)
+    pub flush_interval_ms: u64, // 数据块最长缓存时间 (进程被杀时最多丢失这么久的数据)
+}
+
+impl Default for RecorderConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            dir: "./data/journal".to_string(),
+            flush_interval_ms: 1000,
+        }
+    }
+}
+
+/// 阻塞运行，直到 running 被置为 false (退出前把缓存的数据块落盘)
+pub fn run_recorder(bus: &BusConfig, cfg: &RecorderConfig, running: Arc<AtomicBool>) {
+    let mut writer = match JournalWriter::new(&cfg.dir) {
+        Ok(w) => w,
+        Err(e) => {
+            error!(error = %e, "❌ Recorder disabled");
+            return;
+        }
+    };
+
+    // 订阅所有主题 (CT 等控制消息不录制，收到后直接丢弃)
+    let sub = ZmqSubscriber::new(&bus.market_data_connect, "");
+    sub.connect(&bus.signal_connect);
+    sub.set_recv_timeout(100);
+    let flush_interval = Duration::from_millis(cfg.flush_interval_ms);
+    info!(dir = %cfg.dir, "📼 Recorder started");
+
+    let mut recorded: u64 = 0;
+    while running.load(Ordering::SeqCst) {
+        if let Some((topic, payload)) = sub.recv_with_topic() {
+            if let Some(kind) = RecordKind::from_topic(&topic) {
+                let now_ns = chrono::Utc::now().timestamp_nanos_opt().unwrap_or(0);
+                if let Err(e) = writer.append(kind, now_ns, &payload) {
+                    error!(error = %e, "❌ Journal append failed");
+                } else {
+                    recorded += 1;
+                }
+            }
+        }
+        if let Err(e) = writer.flush_if_due(flush_interval) {
+            error!(error = %e, "❌ Journal flush failed");
+        }
+    }
+
+    if let Err(e) = writer.flush() {
+        error!(error = %e, "❌ Journal flush failed");
+    }
+    info!(records = recorded, "📼 Recorder stopped");
+}
@@ -0,0 +1,51 @@
+// File: src/gateway/replay.rs
+// 行情回放：把录制的盘口快照按原始时间间隔重新发布到 "MD" 总线
+// 输入格式: 每行一个 JSON 编码的 OrderBookUpdate
+use crate::core::OrderBookUpdate;
+use crate::infrastructure::latency::mono_ns;
+use crate::infrastructure::logging::new_trace_id;
+use crate::infrastructure::messaging::ZmqPublisher;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::time::Duration;
+
+/// 回放文件中的所有快照；speed = 2.0 表示两倍速，0 表示不等待 (尽快发送)
+/// 返回已发布的条数
+pub async fn run_replay(zmq_pub: ZmqPublisher, path: &str, speed: f64) -> Result<usize, String> {
+    let file = File::open(path).map_err(|e| format!("Cannot open {}: {}", path, e))?;
+    let reader = BufReader::new(file);
+
+    // ZMQ PUB 刚 bind 时订阅者还没连上，先等一下避免丢掉开头的数据 (slow joiner)
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let mut last_ts: Option<i64> = None;
+    let mut count = 0;
+    for (line_no, line) in reader.lines().enumerate() {
+        let line = line.map_err(|e| format!("{}:{}: {}", path, line_no + 1, e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut update: OrderBookUpdate = serde_json::from_str(&line)
+            .map_err(|e| format!("{}:{}: {}", path, line_no + 1, e))?;
+        if update.trace_id == 0 {
+            update.trace_id = new_trace_id();
+        }
+
+        // 按原始时间间隔等待 (时间戳倒退时不等待)
+        if let Some(prev) = last_ts {
+            let gap_ns = update.timestamp_ns - prev;
+            if speed > 0.0 && gap_ns > 0 {
+                tokio::time::sleep(Duration::from_nanos((gap_ns as f64 / speed) as u64)).await;
+            }
+        }
+        last_ts = Some(update.timestamp_ns);
+
+        // 回放没有 WS 接收时刻，从发布开始计时
+        update.stamps = Default::default();
+        update.stamps.md_publish = mono_ns();
+        zmq_pub.send_book_update(&update);
+        count += 1;
+    }
+
+    Ok(count)
+}
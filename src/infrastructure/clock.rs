@@ -0,0 +1,97 @@
+// File: src/infrastructure/clock.rs
+// 可注入的时钟：策略 (到期 / 清仓窗口)、风控 (熔断时间) 和引擎 (信号时间戳) 都从这里取时间
+//   SystemClock : 实盘，墙上时钟
+//   SimClock    : 模拟，由调用方直接设置 (回测按录制时间推进)
+//   ReplayClock : 回放，跟随收到的行情时间戳 (只前进不后退)
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+pub trait Clock: Send + Sync {
+    /// Unix 纳秒
+    fn now_ns(&self) -> i64;
+
+    fn now_ms(&self) -> i64 {
+        self.now_ns() / 1_000_000
+    }
+}
+
+pub type SharedClock = Arc<dyn Clock>;
+
+/// 引擎使用的时钟来源 ([engine] clock)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClockMode {
+    #[default]
+    System,
+    Replay, // 消费 `replay` 回放的历史行情时，到期逻辑按行情时间计算
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_ns(&self) -> i64 {
+        chrono::Utc::now().timestamp_nanos_opt().unwrap_or(0)
+    }
+}
+
+#[derive(Default)]
+pub struct SimClock {
+    ns: AtomicI64,
+}
+
+impl SimClock {
+    pub fn set(&self, ns: i64) {
+        self.ns.store(ns, Ordering::SeqCst);
+    }
+}
+
+impl Clock for SimClock {
+    fn now_ns(&self) -> i64 {
+        self.ns.load(Ordering::SeqCst)
+    }
+}
+
+#[derive(Default)]
+pub struct ReplayClock {
+    ns: AtomicI64,
+}
+
+impl ReplayClock {
+    /// 收到一条带时间戳的事件；乱序到达的旧时间戳被忽略
+    pub fn observe(&self, ns: i64) {
+        self.ns.fetch_max(ns, Ordering::SeqCst);
+    }
+}
+
+impl Clock for ReplayClock {
+    fn now_ns(&self) -> i64 {
+        self.ns.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sim_clock_goes_wherever_it_is_set() {
+        let clock = SimClock::default();
+        clock.set(5_000_000_000);
+        assert_eq!((clock.now_ns(), clock.now_ms()), (5_000_000_000, 5_000));
+        // 回测可以把时钟拨回去 (重跑同一段数据)
+        clock.set(1_000_000);
+        assert_eq!(clock.now_ms(), 1);
+    }
+
+    #[test]
+    fn replay_clock_never_goes_backwards() {
+        let clock = ReplayClock::default();
+        assert_eq!(clock.now_ns(), 0);
+        for (observed, now) in [(100, 100), (300, 300), (200, 300), (300, 300), (301, 301), (0, 301)] {
+            clock.observe(observed);
+            assert_eq!(clock.now_ns(), now, "after observing {}", observed);
+        }
+    }
+}
@@ -0,0 +1,537 @@
+// File: src/infrastructure/journal.rs
+// 行情 / 成交 / 信号日志 (Journal)：只追加、压缩、长度前缀的二进制文件，按天 (UTC) 滚动
+// 文件布局 (所有整数均为小端序):
+//   YYYYMMDD.mmj : 魔数 "MMJ1" + 若干数据块
+//     数据块 = [u32 压缩后长度][u32 记录数][i64 最早记录时间][i64 最晚记录时间] + Deflate(记录...)
+//     记录   = [u8 类型][i64 记录时间 ns][u32 负载长度] + bincode 负载 (与总线上的字节完全一致)
+//   YYYYMMDD.idx : 每个数据块一条 [i64 最早记录时间][i64 最晚记录时间][u64 块偏移]，按时间定位数据块
+// 崩溃后末尾残缺的数据块在下次打开当天文件时截掉，索引随之重建
+use chrono::{DateTime, NaiveDate, Utc};
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::core::{InventoryUpdate, OrderBookUpdate, TradeSignal};
+
+const MAGIC: &[u8; 4] = b"MMJ1";
+const BLOCK_HEADER_LEN: u64 = 24;
+const INDEX_ENTRY_LEN: usize = 24;
+const RECORD_HEADER_LEN: usize = 13;
+// 当前数据块 (未压缩) 攒到这么大就落盘
+const MAX_BLOCK_BYTES: usize = 256 * 1024;
+
+/// 记录类型 (与总线主题一一对应)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum RecordKind {
+    Book = 1,      // "MD"
+    Inventory = 2, // "IV"
+    Signal = 3,    // "SG"
+}
+
+impl RecordKind {
+    pub fn from_topic(topic: &[u8]) -> Option<Self> {
+        match topic {
+            b"MD" => Some(Self::Book),
+            b"IV" => Some(Self::Inventory),
+            b"SG" => Some(Self::Signal),
+            _ => None,
+        }
+    }
+
+    fn from_byte(b: u8) -> Option<Self> {
+        match b {
+            1 => Some(Self::Book),
+            2 => Some(Self::Inventory),
+            3 => Some(Self::Signal),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", content = "data", rename_all = "lowercase")]
+pub enum JournalEntry {
+    Book(Box<OrderBookUpdate>), // 盘口快照比另外两种大得多
+    Inventory(InventoryUpdate),
+    Signal(TradeSignal),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JournalRecord {
+    pub recorded_ns: i64, // 录制端收到消息的时间 (Unix 纳秒，索引按它排序)
+    #[serde(flatten)]
+    pub entry: JournalEntry,
+}
+
+// 数据块的位置与时间范围 (索引文件里的一条)
+#[derive(Clone, Copy, Debug)]
+struct BlockMeta {
+    first_ns: i64,
+    last_ns: i64,
+    offset: u64,
+}
+
+impl BlockMeta {
+    fn encode(&self) -> [u8; INDEX_ENTRY_LEN] {
+        let mut buf = [0u8; INDEX_ENTRY_LEN];
+        buf[0..8].copy_from_slice(&self.first_ns.to_le_bytes());
+        buf[8..16].copy_from_slice(&self.last_ns.to_le_bytes());
+        buf[16..24].copy_from_slice(&self.offset.to_le_bytes());
+        buf
+    }
+
+    fn decode(buf: &[u8]) -> Self {
+        Self {
+            first_ns: i64::from_le_bytes(buf[0..8].try_into().unwrap()),
+            last_ns: i64::from_le_bytes(buf[8..16].try_into().unwrap()),
+            offset: u64::from_le_bytes(buf[16..24].try_into().unwrap()),
+        }
+    }
+}
+
+fn day_of(ns: i64) -> NaiveDate {
+    DateTime::<Utc>::from_timestamp_nanos(ns).date_naive()
+}
+
+fn data_path(dir: &Path, day: NaiveDate) -> PathBuf {
+    dir.join(format!("{}.mmj", day.format("%Y%m%d")))
+}
+
+fn index_path(data_path: &Path) -> PathBuf {
+    data_path.with_extension("idx")
+}
+
+// 读取数据块头: (压缩后长度, 最早时间, 最晚时间)；文件在块头中间结束时返回 None
+fn read_block_header(file: &mut File, offset: u64) -> std::io::Result<Option<(u32, i64, i64)>> {
+    let mut head = [0u8; BLOCK_HEADER_LEN as usize];
+    file.seek(SeekFrom::Start(offset))?;
+    match file.read_exact(&mut head) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    let len = u32::from_le_bytes(head[0..4].try_into().unwrap());
+    let first_ns = i64::from_le_bytes(head[8..16].try_into().unwrap());
+    let last_ns = i64::from_le_bytes(head[16..24].try_into().unwrap());
+    Ok(Some((len, first_ns, last_ns)))
+}
+
+// 从头扫描数据文件，返回所有完整的数据块和有效数据的末尾位置 (之后的是残缺块)
+fn scan_blocks(file: &mut File) -> Result<(Vec<BlockMeta>, u64), String> {
+    let file_len = file.metadata().map_err(|e| e.to_string())?.len();
+    let mut magic = [0u8; 4];
+    file.seek(SeekFrom::Start(0)).map_err(|e| e.to_string())?;
+    if file.read_exact(&mut magic).is_err() || &magic != MAGIC {
+        return Err("not a journal file (bad magic)".to_string());
+    }
+
+    let mut blocks = Vec::new();
+    let mut offset = MAGIC.len() as u64;
+    while let Some((len, first_ns, last_ns)) = read_block_header(file, offset).map_err(|e| e.to_string())? {
+        let end = offset + BLOCK_HEADER_LEN + len as u64;
+        if end > file_len {
+            break;
+        }
+        blocks.push(BlockMeta { first_ns, last_ns, offset });
+        offset = end;
+    }
+    Ok((blocks, offset))
+}
+
+/// 日志写入端：记录先攒在内存里的当前数据块，块满或超时后压缩落盘
+pub struct JournalWriter {
+    dir: PathBuf,
+    day: Option<NaiveDate>,
+    data: Option<File>,
+    index: Option<File>,
+    offset: u64, // 下一个数据块的写入位置
+    block: Vec<u8>,
+    count: u32,
+    first_ns: i64,
+    last_ns: i64,
+    started: Instant, // 当前数据块第一条记录的写入时刻
+}
+
+impl JournalWriter {
+    pub fn new(dir: &str) -> Result<Self, String> {
+        fs::create_dir_all(dir).map_err(|e| format!("Cannot create journal dir {}: {}", dir, e))?;
+        Ok(Self {
+            dir: PathBuf::from(dir),
+            day: None,
+            data: None,
+            index: None,
+            offset: 0,
+            block: Vec::with_capacity(MAX_BLOCK_BYTES),
+            count: 0,
+            first_ns: 0,
+            last_ns: 0,
+            started: Instant::now(),
+        })
+    }
+
+    /// 追加一条记录 (payload 为总线上的 bincode 字节)；跨天时先滚动到新文件
+    pub fn append(&mut self, kind: RecordKind, recorded_ns: i64, payload: &[u8]) -> Result<(), String> {
+        let day = day_of(recorded_ns);
+        if self.day != Some(day) {
+            self.flush()?;
+            self.open_day(day)?;
+        }
+
+        if self.count == 0 {
+            self.first_ns = recorded_ns;
+            self.last_ns = recorded_ns;
+            self.started = Instant::now();
+        }
+        // 墙上时钟可能回拨，块的时间范围取最小 / 最大值
+        self.first_ns = self.first_ns.min(recorded_ns);
+        self.last_ns = self.last_ns.max(recorded_ns);
+        self.count += 1;
+
+        self.block.push(kind as u8);
+        self.block.extend_from_slice(&recorded_ns.to_le_bytes());
+        self.block.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        self.block.extend_from_slice(payload);
+
+        if self.block.len() >= MAX_BLOCK_BYTES {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// 当前数据块缓存超过 max_age 时落盘 (限制崩溃时丢失的数据量)
+    pub fn flush_if_due(&mut self, max_age: Duration) -> Result<(), String> {
+        if self.count > 0 && self.started.elapsed() >= max_age {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// 压缩并写出当前数据块；先写数据再写索引，索引永远不会指向不存在的数据
+    pub fn flush(&mut self) -> Result<(), String> {
+        if self.count == 0 {
+            return Ok(());
+        }
+        let (Some(data), Some(index)) = (self.data.as_mut(), self.index.as_mut()) else {
+            return Err("journal file is not open".to_string());
+        };
+
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::fast());
+        encoder.write_all(&self.block).map_err(|e| e.to_string())?;
+        let compressed = encoder.finish().map_err(|e| e.to_string())?;
+
+        let mut buf = Vec::with_capacity(BLOCK_HEADER_LEN as usize + compressed.len());
+        buf.extend_from_slice(&(compressed.len() as u32).to_le_bytes());
+        buf.extend_from_slice(&self.count.to_le_bytes());
+        buf.extend_from_slice(&self.first_ns.to_le_bytes());
+        buf.extend_from_slice(&self.last_ns.to_le_bytes());
+        buf.extend_from_slice(&compressed);
+        data.write_all(&buf).map_err(|e| format!("Journal write failed: {}", e))?;
+
+        let meta = BlockMeta { first_ns: self.first_ns, last_ns: self.last_ns, offset: self.offset };
+        index.write_all(&meta.encode()).map_err(|e| format!("Journal index write failed: {}", e))?;
+
+        self.offset += buf.len() as u64;
+        self.block.clear();
+        self.count = 0;
+        Ok(())
+    }
+
+    // 打开 (或续写) 某一天的文件：截掉末尾残缺的数据块，按数据文件重建索引
+    fn open_day(&mut self, day: NaiveDate) -> Result<(), String> {
+        let path = data_path(&self.dir, day);
+        let mut data = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(&path)
+            .map_err(|e| format!("Cannot open journal {}: {}", path.display(), e))?;
+
+        let is_new = data.metadata().map_err(|e| e.to_string())?.len() == 0;
+        let (blocks, end) = if is_new {
+            data.write_all(MAGIC).map_err(|e| e.to_string())?;
+            (Vec::new(), MAGIC.len() as u64)
+        } else {
+            scan_blocks(&mut data).map_err(|e| format!("{}: {}", path.display(), e))?
+        };
+        data.set_len(end).map_err(|e| e.to_string())?;
+        data.seek(SeekFrom::Start(end)).map_err(|e| e.to_string())?;
+
+        let idx_path = index_path(&path);
+        let mut index = File::create(&idx_path)
+            .map_err(|e| format!("Cannot open journal index {}: {}", idx_path.display(), e))?;
+        for meta in &blocks {
+            index.write_all(&meta.encode()).map_err(|e| e.to_string())?;
+        }
+
+        self.day = Some(day);
+        self.data = Some(data);
+        self.index = Some(index);
+        self.offset = end;
+        Ok(())
+    }
+}
+
+impl Drop for JournalWriter {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+/// 日志读取端：按时间范围顺序读取，跨天文件自动衔接
+pub struct JournalReader {
+    dir: PathBuf,
+}
+
+impl JournalReader {
+    pub fn open(dir: &str) -> Self {
+        Self { dir: PathBuf::from(dir) }
+    }
+
+    /// 读取 recorded_ns 落在 [from_ns, to_ns] 内的记录 (按写入顺序)
+    pub fn range(&self, from_ns: i64, to_ns: i64) -> Result<JournalIter, String> {
+        let (first_day, last_day) = (day_of(from_ns), day_of(to_ns));
+        let entries = fs::read_dir(&self.dir)
+            .map_err(|e| format!("Cannot read journal dir {}: {}", self.dir.display(), e))?;
+
+        let mut files: Vec<(NaiveDate, PathBuf)> = entries
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.extension().is_some_and(|ext| ext == "mmj"))
+            .filter_map(|p| {
+                let day = NaiveDate::parse_from_str(p.file_stem()?.to_str()?, "%Y%m%d").ok()?;
+                (first_day..=last_day).contains(&day).then_some((day, p))
+            })
+            .collect();
+        files.sort();
+
+        Ok(JournalIter {
+            files: files.into_iter().map(|(_, p)| p).collect(),
+            from_ns,
+            to_ns,
+            file: None,
+            blocks: VecDeque::new(),
+            records: VecDeque::new(),
+        })
+    }
+}
+
+pub struct JournalIter {
+    files: VecDeque<PathBuf>,
+    from_ns: i64,
+    to_ns: i64,
+    file: Option<File>,
+    blocks: VecDeque<BlockMeta>,
+    records: VecDeque<JournalRecord>,
+}
+
+impl JournalIter {
+    // 打开下一个文件：优先用索引定位数据块，索引缺失时扫描数据文件
+    fn open_file(&mut self, path: &Path) -> Result<(), String> {
+        let mut file = File::open(path).map_err(|e| format!("Cannot open journal {}: {}", path.display(), e))?;
+        let blocks = match fs::read(index_path(path)) {
+            Ok(idx) => idx.chunks_exact(INDEX_ENTRY_LEN).map(BlockMeta::decode).collect(),
+            Err(_) => scan_blocks(&mut file).map_err(|e| format!("{}: {}", path.display(), e))?.0,
+        };
+
+        self.blocks = blocks.into_iter()
+            .filter(|b| b.last_ns >= self.from_ns && b.first_ns <= self.to_ns)
+            .collect();
+        self.file = Some(file);
+        Ok(())
+    }
+
+    // 解压一个数据块，把时间范围内的记录放进缓冲区
+    fn load_block(&mut self, meta: BlockMeta) -> Result<(), String> {
+        let file = self.file.as_mut().ok_or("journal file is not open")?;
+        let (len, _, _) = read_block_header(file, meta.offset)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("truncated block at offset {}", meta.offset))?;
+
+        let mut compressed = vec![0u8; len as usize];
+        file.read_exact(&mut compressed)
+            .map_err(|e| format!("truncated block at offset {}: {}", meta.offset, e))?;
+        let mut raw = Vec::new();
+        DeflateDecoder::new(compressed.as_slice()).read_to_end(&mut raw)
+            .map_err(|e| format!("corrupt block at offset {}: {}", meta.offset, e))?;
+
+        let mut pos = 0;
+        while pos + RECORD_HEADER_LEN <= raw.len() {
+            let kind = raw[pos];
+            let recorded_ns = i64::from_le_bytes(raw[pos + 1..pos + 9].try_into().unwrap());
+            let len = u32::from_le_bytes(raw[pos + 9..pos + 13].try_into().unwrap()) as usize;
+            let start = pos + RECORD_HEADER_LEN;
+            let payload = raw.get(start..start + len)
+                .ok_or_else(|| format!("corrupt record in block at offset {}", meta.offset))?;
+            pos = start + len;
+
+            if recorded_ns < self.from_ns || recorded_ns > self.to_ns {
+                continue;
+            }
+            let entry = decode_entry(kind, payload)
+                .map_err(|e| format!("corrupt record in block at offset {}: {}", meta.offset, e))?;
+            self.records.push_back(JournalRecord { recorded_ns, entry });
+        }
+        Ok(())
+    }
+}
+
+fn decode_entry(kind: u8, payload: &[u8]) -> Result<JournalEntry, String> {
+    let entry = match RecordKind::from_byte(kind) {
+        Some(RecordKind::Book) => JournalEntry::Book(Box::new(bincode::deserialize(payload).map_err(|e| e.to_string())?)),
+        Some(RecordKind::Inventory) => JournalEntry::Inventory(bincode::deserialize(payload).map_err(|e| e.to_string())?),
+        Some(RecordKind::Signal) => JournalEntry::Signal(bincode::deserialize(payload).map_err(|e| e.to_string())?),
+        None => return Err(format!("unknown record type {}", kind)),
+    };
+    Ok(entry)
+}
+
+impl Iterator for JournalIter {
+    type Item = Result<JournalRecord, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(record) = self.records.pop_front() {
+                return Some(Ok(record));
+            }
+            if let Some(meta) = self.blocks.pop_front() {
+                if let Err(e) = self.load_block(meta) {
+                    return Some(Err(e));
+                }
+                continue;
+            }
+            let path = self.files.pop_front()?;
+            if let Err(e) = self.open_file(&path) {
+                return Some(Err(e));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::temp_dir;
+    use chrono::TimeZone;
+
+    fn ns(day: u32, h: u32, m: u32, s: u32) -> i64 {
+        Utc.with_ymd_and_hms(2026, 1, day, h, m, s).unwrap().timestamp_nanos_opt().unwrap()
+    }
+
+    // 用 symbol_id 标记每条记录
+    fn inventory(symbol_id: u64) -> Vec<u8> {
+        bincode::serialize(&InventoryUpdate { exchange: crate::core::Exchange::OpinionLabs, symbol_id, change: 1.0, cost_usd: -0.5, trace_id: 0 }).unwrap()
+    }
+
+    fn symbols(dir: &Path, from_ns: i64, to_ns: i64) -> Result<Vec<u64>, String> {
+        JournalReader::open(dir.to_str().unwrap()).range(from_ns, to_ns)?
+            .map(|r| match r?.entry {
+                JournalEntry::Inventory(u) => Ok(u.symbol_id),
+                other => Err(format!("unexpected {:?}", other)),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn records_round_trip_with_their_kind() {
+        let dir = temp_dir("journal-roundtrip");
+        let signal = TradeSignal {
+            strategy_id: 1,
+            target_exchange: crate::core::Exchange::OpinionLabs,
+            symbol_id: 7,
+            side: crate::core::Side::Buy,
+            price: rust_decimal_macros::dec!(0.47),
+            size_usd: rust_decimal_macros::dec!(50),
+            logic_tag: 1,
+            post_only: true,
+            level: 2,
+            created_at_ns: 1,
+            trace_id: 0xabc,
+            stamps: Default::default(),
+        };
+        {
+            let mut writer = JournalWriter::new(dir.to_str().unwrap()).unwrap();
+            writer.append(RecordKind::Inventory, ns(1, 12, 0, 0), &inventory(1)).unwrap();
+            writer.append(RecordKind::Signal, ns(1, 12, 0, 1), &bincode::serialize(&signal).unwrap()).unwrap();
+        } // drop 时落盘
+
+        let records: Vec<_> = JournalReader::open(dir.to_str().unwrap()).range(0, i64::MAX).unwrap().collect::<Result<_, _>>().unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].recorded_ns, ns(1, 12, 0, 0));
+        assert!(matches!(&records[0].entry, JournalEntry::Inventory(u) if u.symbol_id == 1));
+        let JournalEntry::Signal(s) = &records[1].entry else { panic!("expected a signal") };
+        assert_eq!((s.price, s.post_only, s.level, s.trace_id), (signal.price, true, 2, 0xabc));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn rotates_at_the_utc_day_boundary_and_reads_across_days() {
+        let dir = temp_dir("journal-rotate");
+        {
+            let mut writer = JournalWriter::new(dir.to_str().unwrap()).unwrap();
+            writer.append(RecordKind::Inventory, ns(1, 23, 59, 59), &inventory(1)).unwrap();
+            writer.append(RecordKind::Inventory, ns(2, 0, 0, 1), &inventory(2)).unwrap();
+        }
+        assert!(dir.join("20260101.mmj").exists() && dir.join("20260102.mmj").exists());
+        assert!(dir.join("20260101.idx").exists() && dir.join("20260102.idx").exists());
+
+        assert_eq!(symbols(&dir, ns(1, 0, 0, 0), ns(2, 23, 0, 0)).unwrap(), vec![1, 2]);
+        assert_eq!(symbols(&dir, ns(2, 0, 0, 0), ns(2, 23, 0, 0)).unwrap(), vec![2]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn torn_tail_is_truncated_when_the_day_is_reopened() {
+        let dir = temp_dir("journal-torn");
+        {
+            let mut writer = JournalWriter::new(dir.to_str().unwrap()).unwrap();
+            writer.append(RecordKind::Inventory, ns(1, 12, 0, 0), &inventory(1)).unwrap();
+        }
+        // 模拟写到一半崩溃：块头声明 1000 字节，实际只写了 10 字节
+        let path = dir.join("20260101.mmj");
+        let intact_len = fs::metadata(&path).unwrap().len();
+        let mut torn = 1000u32.to_le_bytes().to_vec();
+        torn.extend_from_slice(&[0u8; 30]);
+        OpenOptions::new().append(true).open(&path).unwrap().write_all(&torn).unwrap();
+
+        {
+            let mut writer = JournalWriter::new(dir.to_str().unwrap()).unwrap();
+            writer.append(RecordKind::Inventory, ns(1, 12, 0, 5), &inventory(2)).unwrap();
+            writer.flush().unwrap();
+            // 残缺块被截掉，新数据块紧接在完整数据之后
+            assert!(fs::metadata(&path).unwrap().len() > intact_len);
+        }
+        assert_eq!(symbols(&dir, 0, i64::MAX).unwrap(), vec![1, 2]);
+        assert_eq!(fs::read(dir.join("20260101.idx")).unwrap().len(), 2 * INDEX_ENTRY_LEN);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn range_reads_skip_blocks_and_days_outside_the_range() {
+        let dir = temp_dir("journal-index");
+        let mut offsets = Vec::new();
+        {
+            let mut writer = JournalWriter::new(dir.to_str().unwrap()).unwrap();
+            for (i, t) in [ns(2, 9, 0, 0), ns(2, 10, 0, 0), ns(2, 11, 0, 0)].into_iter().enumerate() {
+                offsets.push(writer.offset);
+                writer.append(RecordKind::Inventory, t, &inventory(i as u64 + 1)).unwrap();
+                writer.flush().unwrap();
+            }
+        }
+
+        // 破坏中间那个数据块的压缩数据，以及前一天的整个文件：只要按索引跳过，读取就不会碰到它们
+        let path = dir.join("20260102.mmj");
+        let mut data = fs::read(&path).unwrap();
+        let body = offsets[1] as usize + BLOCK_HEADER_LEN as usize;
+        data[body..body + 4].copy_from_slice(&[0xff; 4]);
+        fs::write(&path, data).unwrap();
+        fs::write(dir.join("20260101.mmj"), b"garbage").unwrap();
+
+        assert_eq!(symbols(&dir, ns(2, 10, 30, 0), ns(2, 12, 0, 0)).unwrap(), vec![3]);
+        assert_eq!(symbols(&dir, ns(2, 8, 0, 0), ns(2, 9, 30, 0)).unwrap(), vec![1]);
+        assert!(symbols(&dir, ns(2, 9, 30, 0), ns(2, 10, 30, 0)).is_err());
+        assert!(symbols(&dir, ns(1, 0, 0, 0), ns(2, 9, 30, 0)).is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
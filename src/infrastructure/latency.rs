@@ -0,0 +1,106 @@
+// File: src/infrastructure/latency.rs
+// Tick-to-Trade 延迟测量：各阶段用操作系统单调时钟 (CLOCK_MONOTONIC) 打点，不依赖 TSC 等硬件计数器
+// 时间戳随消息传递 (StageStamps)，订单收到 HTTP 响应后一次性汇总到按阶段划分的直方图
+use prometheus::{exponential_buckets, register_histogram_vec, HistogramVec};
+use std::sync::LazyLock;
+
+use crate::core::StageStamps;
+
+// 1us ~ 2s，按 2 倍递增 (histogram_quantile 计算 p50 / p99)
+pub static STAGE_LATENCY: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec!(
+        "mm_stage_latency_seconds",
+        "Latency between consecutive pipeline stages (monotonic clock)",
+        &["stage"],
+        exponential_buckets(0.000_001, 2.0, 22).unwrap()
+    ).unwrap()
+});
+
+// 超过该值的差值视为跨主机的时钟 (不可比)，直接丢弃
+const MAX_PLAUSIBLE_NS: u64 = 60_000_000_000;
+
+/// 当前单调时钟 (纳秒)；同一主机上的所有进程共享同一个时钟源
+pub fn mono_ns() -> u64 {
+    let mut ts = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+    // SAFETY: ts 是有效的可写指针，CLOCK_MONOTONIC 在 Linux / macOS 上总是可用
+    unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut ts) };
+    ts.tv_sec as u64 * 1_000_000_000 + ts.tv_nsec as u64
+}
+
+/// 汇总一条完整链路：相邻阶段各记一次，外加端到端的 tick_to_trade
+pub fn observe(s: &StageStamps) {
+    for (stage, ns) in stage_latencies(s) {
+        STAGE_LATENCY.with_label_values(&[stage]).observe(ns as f64 / 1e9);
+    }
+}
+
+/// 各阶段耗时 (纳秒)
+/// 任一端未记录 (对冲单没有 WS 阶段) 或跨主机不可比时跳过该阶段
+pub fn stage_latencies(s: &StageStamps) -> Vec<(&'static str, u64)> {
+    let stages = [
+        ("ws_to_publish", s.ws_recv, s.md_publish),
+        ("publish_to_engine", s.md_publish, s.engine_recv),
+        ("engine_to_quote", s.engine_recv, s.quote_done),
+        ("quote_to_risk", s.quote_done, s.risk_done),
+        ("risk_to_exec", s.risk_done, s.exec_recv),
+        ("exec_to_signed", s.exec_recv, s.sign_done),
+        ("signed_to_response", s.sign_done, s.http_response),
+        ("tick_to_trade", s.ws_recv, s.http_response),
+    ];
+    stages.into_iter()
+        .filter(|&(_, from, to)| from != 0 && to >= from && to - from <= MAX_PLAUSIBLE_NS)
+        .map(|(stage, from, to)| (stage, to - from))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stages_are_differences_of_consecutive_stamps() {
+        let s = StageStamps {
+            ws_recv: 1_000,
+            md_publish: 1_500,
+            engine_recv: 3_000,
+            quote_done: 3_200,
+            risk_done: 3_250,
+            exec_recv: 4_000,
+            sign_done: 9_000,
+            http_response: 20_000,
+        };
+        assert_eq!(stage_latencies(&s), vec![
+            ("ws_to_publish", 500),
+            ("publish_to_engine", 1_500),
+            ("engine_to_quote", 200),
+            ("quote_to_risk", 50),
+            ("risk_to_exec", 750),
+            ("exec_to_signed", 5_000),
+            ("signed_to_response", 11_000),
+            ("tick_to_trade", 19_000),
+        ]);
+    }
+
+    #[test]
+    fn missing_backwards_and_cross_host_stages_are_skipped() {
+        // 对冲单：没有 WS / 发布阶段
+        let hedge = StageStamps { risk_done: 10, exec_recv: 20, sign_done: 30, http_response: 45, ..Default::default() };
+        assert_eq!(stage_latencies(&hedge), vec![("risk_to_exec", 10), ("exec_to_signed", 10), ("signed_to_response", 15)]);
+
+        // 执行层在另一台主机：单调时钟不可比 (倒退或相差过大)
+        let remote = StageStamps { quote_done: 5_000, risk_done: 6_000, exec_recv: 10, sign_done: 20, ..Default::default() };
+        assert_eq!(stage_latencies(&remote), vec![("quote_to_risk", 1_000), ("exec_to_signed", 10)]);
+        let far = StageStamps { risk_done: 1, exec_recv: 1 + MAX_PLAUSIBLE_NS + 1, ..Default::default() };
+        assert!(stage_latencies(&far).is_empty());
+    }
+
+    #[test]
+    fn observe_feeds_the_stage_histogram() {
+        let start = mono_ns();
+        assert!(mono_ns() >= start);
+        let histogram = STAGE_LATENCY.with_label_values(&["signed_to_response"]);
+        let before = histogram.get_sample_count();
+        observe(&StageStamps { sign_done: start, http_response: start + 2_000_000, ..Default::default() });
+        assert!(histogram.get_sample_count() > before);
+    }
+}
@@ -0,0 +1,63 @@
+// File: src/infrastructure/logging.rs
+// 结构化日志：级别过滤 (按模块路径区分组件)、时间戳、文本 / JSON 两种输出
+// 追踪 ID (trace_id) 从触发报价的 OrderBookUpdate 开始，沿 TradeSignal -> 签名 -> 发送 -> 成交 一路传递
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use tracing_subscriber::EnvFilter;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogConfig {
+    /// EnvFilter 语法，例如 "info,enterprise_market_maker::execution=debug"；RUST_LOG 环境变量优先
+    pub level: String,
+    pub format: LogFormat,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            level: "info".to_string(),
+            format: LogFormat::Text,
+        }
+    }
+}
+
+/// 安装全局 subscriber (进程启动时调用一次)
+pub fn init(cfg: &LogConfig) {
+    let filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new(&cfg.level));
+
+    // 日志写 stderr，stdout 留给 journal dump / backtest 等命令的数据输出
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
+        .with_target(true);
+
+    match cfg.format {
+        // JSON：每行一个事件，带当前 span 及其所有父 span 的字段 (trace_id 等)
+        LogFormat::Json => builder.json().with_current_span(true).with_span_list(true).init(),
+        LogFormat::Text => builder.init(),
+    }
+}
+
+/// 新的追踪 ID (0 保留为“未知”)
+pub fn new_trace_id() -> u64 {
+    rand::random::<u64>().max(1)
+}
+
+/// 日志中的追踪 ID 统一显示为 16 位十六进制
+pub struct TraceId(pub u64);
+
+impl fmt::Display for TraceId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
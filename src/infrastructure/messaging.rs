@@ -1,5 +1,6 @@
 use zmq::{Context, Socket, PUB, SUB};
 use crate::core::{OrderBookUpdate, TradeSignal, InventoryUpdate};
+use crate::infrastructure::metrics::{topic_label, BUS_MESSAGES};
 use std::sync::{Arc, Mutex};
 
 #[derive(Clone)]
@@ -21,18 +22,20 @@ impl ZmqPublisher {
     }
 
     pub fn send_book_update(&self, update: &OrderBookUpdate) {
-        let encoded = bincode::serialize(update).unwrap();
-        self.socket.lock().unwrap().send_multipart(&["MD".as_bytes(), &encoded], 0).unwrap();
+        self.send("MD", &bincode::serialize(update).unwrap());
     }
 
     pub fn send_signal(&self, signal: &TradeSignal) {
-        let encoded = bincode::serialize(signal).unwrap();
-        self.socket.lock().unwrap().send_multipart(&["SG".as_bytes(), &encoded], 0).unwrap();
+        self.send("SG", &bincode::serialize(signal).unwrap());
     }
 
     pub fn send_inventory_update(&self, update: &InventoryUpdate) {
-        let encoded = bincode::serialize(update).unwrap();
-        self.socket.lock().unwrap().send_multipart(&["IV".as_bytes(), &encoded], 0).unwrap();
+        self.send("IV", &bincode::serialize(update).unwrap());
+    }
+
+    fn send(&self, topic: &str, encoded: &[u8]) {
+        self.socket.lock().unwrap().send_multipart([topic.as_bytes(), encoded], 0).unwrap();
+        BUS_MESSAGES.with_label_values(&["out", topic]).inc();
     }
 }
 
@@ -49,10 +52,28 @@ impl ZmqSubscriber {
         Self { socket }
     }
 
+    // 额外连接一个发布端 (同一个 SUB socket 可以同时订阅多条总线)
+    pub fn connect(&self, endpoint: &str) {
+        self.socket.connect(endpoint).unwrap();
+    }
+
+    // 接收超时 (毫秒)：超时后 recv 返回 None，调用方可以借机处理退出信号等事务
+    pub fn set_recv_timeout(&self, timeout_ms: i32) {
+        self.socket.set_rcvtimeo(timeout_ms).unwrap();
+    }
+
     // 通用接收：返回原始字节供反序列化
     pub fn recv_raw_bytes(&self) -> Option<Vec<u8>> {
-        let msg = self.socket.recv_multipart(0).ok()?;
+        self.recv_with_topic().map(|(_, payload)| payload)
+    }
+
+    // 带主题接收：返回 (主题, 原始字节)，用于同一个订阅里区分 MD / IV / CT 等消息
+    pub fn recv_with_topic(&self) -> Option<(Vec<u8>, Vec<u8>)> {
+        let mut msg = self.socket.recv_multipart(0).ok()?;
         if msg.len() < 2 { return None; }
-        Some(msg[1].to_vec())
+        let payload = msg.swap_remove(1);
+        let topic = msg.swap_remove(0);
+        BUS_MESSAGES.with_label_values(&["in", topic_label(&topic)]).inc();
+        Some((topic, payload))
     }
 }
\ No newline at end of file
@@ -0,0 +1,153 @@
+// File: src/infrastructure/metrics.rs
+// Prometheus 指标：计数器 / 直方图 / 仪表盘，通过本地 HTTP 端点 (GET /metrics) 导出
+// 所有指标都挂在默认 Registry 上，进程内任何线程都可以直接更新 (内部是原子操作)
+use prometheus::{
+    register_gauge, register_gauge_vec, register_histogram_vec, register_int_counter_vec, register_int_gauge,
+    Encoder, Gauge, GaugeVec, HistogramVec, IntCounterVec, IntGauge, TextEncoder,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::LazyLock;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tracing::{info, warn};
+
+use crate::core::Exchange;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MetricsConfig {
+    pub enabled: bool,
+    pub bind: String, // 同一台主机上分开部署的组件需要使用不同端口
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            bind: "127.0.0.1:9100".to_string(),
+        }
+    }
+}
+
+// 延迟直方图的桶 (秒)：100us ~ 2.5s
+const LATENCY_BUCKETS: &[f64] = &[0.0001, 0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5];
+
+// --- 总线 ---
+pub static BUS_MESSAGES: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!("mm_bus_messages_total", "ZMQ messages by direction (in/out) and topic", &["direction", "topic"]).unwrap()
+});
+pub static DECODE_FAILURES: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!("mm_bus_decode_failures_total", "Bus messages that could not be decoded", &["topic"]).unwrap()
+});
+
+// --- 风控 ---
+pub static SIGNALS_ACCEPTED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!("mm_signals_accepted_total", "Signals accepted by RiskManager::check_signal", &["exchange"]).unwrap()
+});
+pub static SIGNALS_REJECTED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!("mm_signals_rejected_total", "Signals rejected by RiskManager::check_signal", &["exchange", "reason"]).unwrap()
+});
+pub static TOTAL_PNL: LazyLock<Gauge> = LazyLock::new(|| {
+    register_gauge!("mm_total_pnl_usd", "Cumulative mark-to-market PnL").unwrap()
+});
+pub static DRAWDOWN: LazyLock<Gauge> = LazyLock::new(|| {
+    register_gauge!("mm_drawdown_usd", "Current drawdown from the PnL high-water mark").unwrap()
+});
+pub static KILL_SWITCH: LazyLock<IntGauge> = LazyLock::new(|| {
+    register_int_gauge!("mm_kill_switch_active", "1 if the kill switch has fired").unwrap()
+});
+
+// --- 账本 ---
+pub static INVENTORY: LazyLock<GaugeVec> = LazyLock::new(|| {
+    register_gauge_vec!("mm_inventory_shares", "Inventory per Opinion market", &["market"]).unwrap()
+});
+pub static CASH: LazyLock<GaugeVec> = LazyLock::new(|| {
+    register_gauge_vec!("mm_cash_usd", "Cash ledger per Opinion market", &["market"]).unwrap()
+});
+
+// --- 执行 ---
+pub static SIGN_LATENCY: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec!("mm_sign_latency_seconds", "Order signing latency", &["exchange"], LATENCY_BUCKETS.to_vec()).unwrap()
+});
+pub static SUBMIT_LATENCY: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec!("mm_submit_latency_seconds", "HTTP order submit latency", &["exchange"], LATENCY_BUCKETS.to_vec()).unwrap()
+});
+pub static HTTP_RESPONSES: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!("mm_http_responses_total", "Venue HTTP responses by method and status code", &["exchange", "method", "status"]).unwrap()
+});
+pub static ORDERS_DROPPED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!("mm_orders_dropped_total", "Signed orders dropped because the submit pipeline was full", &["exchange"]).unwrap()
+});
+
+/// 指标里的场馆标签
+pub fn exchange_label(exchange: Exchange) -> &'static str {
+    match exchange {
+        Exchange::Polymarket => "polymarket",
+        Exchange::OpinionLabs => "opinion",
+        Exchange::Unknown => "unknown",
+    }
+}
+
+/// 总线主题标签 (非 UTF-8 的主题统一记为 "?")
+pub fn topic_label(topic: &[u8]) -> &str {
+    std::str::from_utf8(topic).unwrap_or("?")
+}
+
+/// 启动 HTTP 导出端点 (后台任务)；端口被占用时只打警告，不影响交易
+pub async fn spawn_exporter(cfg: &MetricsConfig) {
+    if !cfg.enabled {
+        return;
+    }
+
+    // 预先注册所有指标，让 Dashboard 从启动开始就能看到 0 值序列
+    LazyLock::force(&BUS_MESSAGES);
+    LazyLock::force(&DECODE_FAILURES);
+    LazyLock::force(&SIGNALS_ACCEPTED);
+    LazyLock::force(&SIGNALS_REJECTED);
+    LazyLock::force(&TOTAL_PNL);
+    LazyLock::force(&DRAWDOWN);
+    LazyLock::force(&KILL_SWITCH);
+    LazyLock::force(&INVENTORY);
+    LazyLock::force(&CASH);
+    LazyLock::force(&SIGN_LATENCY);
+    LazyLock::force(&SUBMIT_LATENCY);
+    LazyLock::force(&HTTP_RESPONSES);
+    LazyLock::force(&ORDERS_DROPPED);
+    LazyLock::force(&crate::infrastructure::latency::STAGE_LATENCY);
+
+    let listener = match TcpListener::bind(&cfg.bind).await {
+        Ok(l) => l,
+        Err(e) => {
+            warn!(bind = %cfg.bind, error = %e, "⚠️ Metrics endpoint disabled");
+            return;
+        }
+    };
+    info!(bind = %cfg.bind, "📊 Metrics endpoint listening on /metrics");
+
+    tokio::spawn(async move {
+        loop {
+            let Ok((mut stream, _)) = listener.accept().await else { continue };
+            tokio::spawn(async move {
+                // 只需要请求行；Prometheus 的抓取请求很小，一次读取足够
+                let mut buf = [0u8; 1024];
+                let n = stream.read(&mut buf).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..n]);
+
+                let response = if request.starts_with("GET /metrics") {
+                    let mut body = Vec::new();
+                    let encoder = TextEncoder::new();
+                    let _ = encoder.encode(&prometheus::gather(), &mut body);
+                    let mut head = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                        encoder.format_type(), body.len()
+                    ).into_bytes();
+                    head.extend_from_slice(&body);
+                    head
+                } else {
+                    b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_vec()
+                };
+                let _ = stream.write_all(&response).await;
+            });
+        }
+    });
+}
@@ -1 +1,6 @@
-pub mod messaging;
\ No newline at end of file
+pub mod messaging;
+pub mod logging;
+pub mod metrics;
+pub mod latency;
+pub mod journal;
+pub mod clock;
@@ -8,59 +8,360 @@ mod engine;
 mod execution;
 // ✅ 必须启用 core 模块，因为 OrderBookUpdate 等结构体定义在这里
 mod core; 
+mod venues;
+mod config;
+mod reload;
+mod cli;
+mod backtest;
+#[cfg(test)]
+mod testing;
 
+use clap::Parser;
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tracing::{error, info};
 use infrastructure::messaging::ZmqPublisher;
-use gateway::poly_feed::run_poly_feed_handler;
-use gateway::opinion_feed::run_opinion_chain_listener;
-use engine::run_strategy_engine;
+use gateway::poly_feed::{run_poly_feed_handler, run_poly_fill_listener};
+use gateway::opinion_feed::{run_opinion_book_feed, run_opinion_chain_listener};
+use gateway::recorder::run_recorder;
+use gateway::replay::run_replay;
+use engine::{load_initial_state, run_strategy_engine};
+use config::{private_key, AppConfig};
+use crate::core::Exchange;
+use infrastructure::journal::{JournalEntry, JournalReader};
+use backtest::sweep::{format_params, run_sweep, RunMetrics, SweepOutcome, SweepSpec};
+use cli::{Cli, Command, ConfigAction, FeedSource, JournalAction, RecordFilter};
 // ✅ 修复：使用 r#loop 导入 loop 模块
-use execution::event_loop::run_execution_loop;
+use execution::event_loop::{cancel_all_with_retry, connect_venues, log_venue_snapshot, run_execution_loop};
 
 #[tokio::main]
 async fn main() {
-    println!("🚀 Starting Enterprise Market Maker System...");
+    let cli = Cli::parse();
 
-    // [关键修复] 创建共享的 ZMQ 发布者
-    // 不能调用两次 new("tcp://*:5555")，否则第二个会因为端口占用而崩溃
-    // ZmqPublisher 实现了 Clone (基于 Arc)，可以在多个任务间共享同一个 socket
-    let market_data_pub = ZmqPublisher::new("tcp://*:5555");
+    // 0. 加载配置 (--config 或 MM_CONFIG 环境变量，默认 ./config.toml)
+    // 配置非法时直接拒绝启动，不带着错误参数上线
+    let cfg = match AppConfig::load(&cli.config) {
+        Ok(c) => c,
+        Err(e) => {
+            // 日志系统还没有初始化 (日志配置也在配置文件里)
+            eprintln!("❌ [Config] {}", e);
+            std::process::exit(1);
+        }
+    };
+    infrastructure::logging::init(&cfg.log);
 
-    // 1. 启动 Polymarket 数据源 (生产者 -> 5555)
-    let poly_pub = market_data_pub.clone();
-    tokio::spawn(async move {
-        // 这里填入你要监听的 Polymarket Asset IDs
-        let markets = vec!["217426331...".to_string()]; 
-        println!("👂 [PolyFeed] Starting listener for {} markets...", markets.len());
-        run_poly_feed_handler(poly_pub, markets).await;
-    });
+    let command = cli.command.unwrap_or(Command::All);
 
-    // 2. 启动 Opinion 链上监听 (生产者 -> 5555)
-    // 复用同一个端口发布 Opinion 的数据
-    let opinion_pub = market_data_pub.clone();
-    tokio::spawn(async move {
-        println!("👂 [OpinionFeed] Starting chain listener...");
-        run_opinion_chain_listener(opinion_pub).await;
-    });
+    // 常驻组件导出 Prometheus 指标 (一次性的运维命令不需要)
+    if matches!(command, Command::All | Command::Feed { .. } | Command::Engine | Command::Exec | Command::Record) {
+        infrastructure::metrics::spawn_exporter(&cfg.metrics).await;
+    }
 
-    // 3. 启动执行引擎 (消费者 <- 5556)
+    match command {
+        Command::All => run_all(cfg, cli.config).await,
+        Command::Feed { source: FeedSource::Poly } => run_feeds(cfg, true, false).await,
+        Command::Feed { source: FeedSource::Opinion } => run_feeds(cfg, false, true).await,
+        Command::Engine => run_engine(cfg, cli.config).await,
+        Command::Exec => {
+            info!("🔫 Starting execution loop...");
+            run_execution_loop(cfg, private_key(), Arc::new(AtomicBool::new(true))).await;
+        }
+        Command::Record => record(cfg).await,
+        Command::Journal { action: JournalAction::Dump { from, to, kind } } => {
+            if let Err(e) = journal_dump(&cfg, from, to, kind) {
+                error!(error = %e, "❌ Journal dump failed");
+                std::process::exit(1);
+            }
+        }
+        Command::Backtest { from, to, path_csv } => backtest(&cfg, from, to, path_csv),
+        Command::Sweep { spec, from, to, threads, top } => sweep(&cfg, &spec, from, to, threads, top),
+        Command::CancelAll => cancel_all(cfg).await,
+        Command::Status => status(cfg).await,
+        Command::Replay { file, speed } => replay(cfg, &file, speed).await,
+        Command::Config { action: ConfigAction::Check } => config_check(&cfg, &cli.config),
+    }
+}
+
+// 单进程模式：所有组件共享一个 tokio runtime
+async fn run_all(cfg: AppConfig, config_path: String) {
+    info!("🚀 Starting Enterprise Market Maker System...");
+    info!(path = %config_path, markets = cfg.markets.len(), "⚙️ Config loaded");
+
+    // 1. 启动数据源 (生产者 -> 5555)
+    let feed_cfg = cfg.clone();
+    tokio::spawn(async move { run_feeds(feed_cfg, true, true).await });
+
+    // 2. 启动执行引擎 (消费者 <- 5556)
     // 它负责接收策略引擎发出的 "SG" 信号并下单
-    tokio::spawn(async {
-        println!("🔫 [Execution] Starting execution loop...");
-        run_execution_loop().await;
+    let exec_cfg = cfg.clone();
+    tokio::spawn(async move {
+        info!("🔫 Starting execution loop...");
+        run_execution_loop(exec_cfg, private_key(), Arc::new(AtomicBool::new(true))).await;
     });
 
+    // 3. 录制 (独立线程；进程退出时最多丢失 recorder.flush_interval_ms 内的数据)
+    if cfg.recorder.enabled {
+        let rec_cfg = cfg.clone();
+        std::thread::spawn(move || {
+            run_recorder(&rec_cfg.bus, &rec_cfg.recorder, Arc::new(AtomicBool::new(true)));
+        });
+    }
+
     // 4. 启动策略引擎 (大脑: Sub 5555 -> Pub 5556)
-    // 它是 CPU 密集型死循环，使用 spawn_blocking 防止阻塞 tokio runtime
-    println!("🧠 [Strategy] Engine booting up...");
-    let strategy_handle = tokio::task::spawn_blocking(|| {
-        run_strategy_engine();
+    run_engine(cfg, config_path).await;
+
+    info!("👋 System shutdown complete");
+}
+
+// 启动数据源；同一进程内的所有数据源共享一个 PUB socket
+async fn run_feeds(cfg: AppConfig, poly: bool, opinion: bool) {
+    // [关键修复] 创建共享的 ZMQ 发布者
+    // 不能调用两次 new("tcp://*:5555")，否则第二个会因为端口占用而崩溃
+    // ZmqPublisher 实现了 Clone (基于 Arc)，可以在多个任务间共享同一个 socket
+    let market_data_pub = ZmqPublisher::new(&cfg.bus.market_data_bind);
+    let mut handles = Vec::new();
+
+    if poly {
+        // 行情与对冲执行共用的 Polymarket Asset IDs
+        let poly_markets = cfg.poly_asset_ids();
+
+        // 1a. Polymarket 盘口
+        let poly_pub = market_data_pub.clone();
+        let markets = poly_markets.clone();
+        let ws_url = cfg.venues.poly_ws_url.clone();
+        handles.push(tokio::spawn(async move {
+            info!(markets = markets.len(), "👂 Starting Polymarket book listener...");
+            run_poly_feed_handler(poly_pub, markets, ws_url).await;
+        }));
+
+        // 1b. Polymarket 成交监听 (对冲腿回报)；模拟盘的成交由模拟场馆发布
+        if !cfg.paper.enabled {
+            let poly_fill_pub = market_data_pub.clone();
+            let clob_url = cfg.venues.poly_clob_url.clone();
+            handles.push(tokio::spawn(async move {
+                info!("👂 Starting Polymarket hedge fill listener...");
+                run_poly_fill_listener(poly_fill_pub, poly_markets, clob_url, private_key()).await;
+            }));
+        }
+    }
+
+    if opinion && cfg.venues.opinion_book_poll_ms > 0 {
+        // 2a. Opinion 盘口 (报价场馆自己的盘口，模拟盘也需要)
+        let book_pub = market_data_pub.clone();
+        let opinion_api_url = cfg.venues.opinion_api_url.clone();
+        let market_ids: Vec<u64> = cfg.markets.iter().map(|m| m.opinion_market_id).collect();
+        let poll_ms = cfg.venues.opinion_book_poll_ms;
+        handles.push(tokio::spawn(async move {
+            info!("👂 Starting Opinion book listener...");
+            run_opinion_book_feed(book_pub, opinion_api_url, market_ids, poll_ms).await;
+        }));
+    }
+
+    if opinion && cfg.paper.enabled {
+        info!("📝 Paper trading: Opinion chain listener disabled");
+    } else if opinion {
+        // 2b. Opinion 链上监听 (复用同一个端口发布 Opinion 的数据)
+        let opinion_pub = market_data_pub.clone();
+        let opinion_api_url = cfg.venues.opinion_api_url.clone();
+        handles.push(tokio::spawn(async move {
+            info!("👂 Starting Opinion chain listener...");
+            run_opinion_chain_listener(opinion_pub, opinion_api_url, private_key()).await;
+        }));
+    }
+
+    for handle in handles {
+        if let Err(e) = handle.await {
+            error!(error = ?e, "❌ Feed task crashed");
+        }
+    }
+}
+
+// 策略引擎是 CPU 密集型死循环，使用 spawn_blocking 防止阻塞 tokio runtime
+async fn run_engine(cfg: AppConfig, config_path: String) {
+    info!("🧠 Strategy engine booting up...");
+    let strategy_handle = tokio::task::spawn_blocking(move || {
+        run_strategy_engine(cfg, Some(config_path));
     });
 
     // 等待策略引擎 (它内部有 Ctrl+C 处理，退出时会返回)
     match strategy_handle.await {
-        Ok(_) => println!("✅ [Main] Strategy Engine exited gracefully."),
-        Err(e) => eprintln!("❌ [Main] Strategy Engine crashed: {:?}", e),
+        Ok(_) => info!("✅ Strategy engine exited gracefully"),
+        Err(e) => error!(error = ?e, "❌ Strategy engine crashed"),
+    }
+}
+
+// 录制组件单独运行：Ctrl+C 时把缓存的数据块落盘后退出
+async fn record(cfg: AppConfig) {
+    let running = Arc::new(AtomicBool::new(true));
+    let r = running.clone();
+    if let Err(e) = ctrlc::set_handler(move || r.store(false, Ordering::SeqCst)) {
+        error!(error = %e, "❌ Error setting Ctrl-C handler");
+    }
+
+    let handle = tokio::task::spawn_blocking(move || run_recorder(&cfg.bus, &cfg.recorder, running));
+    if let Err(e) = handle.await {
+        error!(error = ?e, "❌ Recorder crashed");
     }
+}
+
+// --from / --to (RFC 3339) 转成纳秒时间范围；默认今天 0 点 (UTC) 到现在
+fn parse_time_range(from: Option<String>, to: Option<String>) -> Result<(i64, i64), String> {
+    let parse = |s: &str| {
+        chrono::DateTime::parse_from_rfc3339(s)
+            .ok()
+            .and_then(|t| t.timestamp_nanos_opt())
+            .ok_or_else(|| format!("'{}' is not an RFC 3339 timestamp", s))
+    };
+    let now = chrono::Utc::now();
+    let from_ns = match from.as_deref() {
+        Some(s) => parse(s)?,
+        None => now.date_naive().and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp_nanos_opt().unwrap_or(0),
+    };
+    let to_ns = match to.as_deref() {
+        Some(s) => parse(s)?,
+        None => now.timestamp_nanos_opt().unwrap_or(i64::MAX),
+    };
+    Ok((from_ns, to_ns))
+}
 
-    println!("👋 [Main] System Shutdown Complete.");
-}
\ No newline at end of file
+// 按时间范围导出日志 (stdout，JSON Lines)
+fn journal_dump(cfg: &AppConfig, from: Option<String>, to: Option<String>, kind: Option<RecordFilter>) -> Result<(), String> {
+    let (from_ns, to_ns) = parse_time_range(from, to)?;
+    let mut out = std::io::BufWriter::new(std::io::stdout().lock());
+
+    for record in JournalReader::open(&cfg.recorder.dir).range(from_ns, to_ns)? {
+        let r = record?;
+        let keep = matches!(
+            (kind, &r.entry),
+            (None, _)
+                | (Some(RecordFilter::Book), JournalEntry::Book(_))
+                | (Some(RecordFilter::Inventory), JournalEntry::Inventory(_))
+                | (Some(RecordFilter::Signal), JournalEntry::Signal(_))
+        );
+        if keep {
+            serde_json::to_writer(&mut out, &r).map_err(|e| e.to_string())?;
+            out.write_all(b"\n").map_err(|e| e.to_string())?;
+        }
+    }
+    out.flush().map_err(|e| e.to_string())
+}
+
+// 读取日志里时间范围内的盘口 (回测 / 参数扫描的输入)
+fn load_tape_or_exit(cfg: &AppConfig, from: Option<String>, to: Option<String>) -> backtest::BookTape {
+    parse_time_range(from, to).and_then(|(from_ns, to_ns)| backtest::load_tape(&cfg.recorder.dir, from_ns, to_ns)).unwrap_or_else(|e| {
+        eprintln!("❌ [Backtest] {}", e);
+        std::process::exit(1);
+    })
+}
+
+// 离线回测：读取日志里的盘口，用当前配置的市场、策略与风控参数跑一遍
+fn backtest(cfg: &AppConfig, from: Option<String>, to: Option<String>, path_csv: Option<String>) {
+    let tape = load_tape_or_exit(cfg, from, to);
+    let specs = cfg.market_specs().unwrap_or_default();
+    let report = backtest::run_backtest(&specs, &cfg.risk, &tape);
+
+    if let Some(path) = path_csv {
+        if let Err(e) = report.write_path_csv(&path) {
+            eprintln!("❌ [Backtest] {}", e);
+            std::process::exit(1);
+        }
+    }
+    println!("{}", serde_json::to_string_pretty(&report).unwrap());
+    if report.ignored_ladder_quotes > 0 {
+        eprintln!("⚠️ [Backtest] Only ladder level 0 is simulated: {} deeper quote levels were ignored (not in fills or PnL)", report.ignored_ladder_quotes);
+    }
+}
+
+// 参数扫描：打印排名表 (或 Walk-Forward 每一折的选择与样本外表现)
+fn sweep(cfg: &AppConfig, spec_path: &str, from: Option<String>, to: Option<String>, threads: Option<usize>, top: usize) {
+    let spec = SweepSpec::load(spec_path).unwrap_or_else(|e| {
+        eprintln!("❌ [Sweep] {}", e);
+        std::process::exit(1);
+    });
+    let tape = load_tape_or_exit(cfg, from, to);
+    let threads = threads.unwrap_or_else(|| std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1));
+    let specs = cfg.market_specs().unwrap_or_default();
+
+    let fmt_metrics = |m: &RunMetrics| format!(
+        "pnl {:>10.2} | sharpe {:>7.2} | max_dd {:>8.2} | inv_var {:>10.1} | fills {:>5} | fill_rate {:>5.2}{}",
+        m.pnl, m.sharpe, m.max_drawdown, m.inventory_variance, m.fills, m.fill_rate, if m.killed { " | KILLED" } else { "" }
+    );
+    match run_sweep(&spec, &specs, &cfg.risk, &tape, threads) {
+        Ok(SweepOutcome::Ranked(rows)) => {
+            println!("📊 [Sweep] {} candidates on {} books (ranked by {:?})", rows.len(), tape.len(), spec.rank_by);
+            for (i, row) in rows.iter().take(top).enumerate() {
+                println!("{:>3}. {} | {}", i + 1, fmt_metrics(&row.metrics), format_params(&row.params));
+            }
+        }
+        Ok(SweepOutcome::WalkForward(folds)) => {
+            println!("📊 [Sweep] walk-forward with {} folds on {} books (ranked by {:?})", folds.len(), tape.len(), spec.rank_by);
+            let mut oos_pnl = 0.0;
+            for f in &folds {
+                println!("fold {} | train {} books | test {} books | {}", f.fold, f.train_books, f.test_books, format_params(&f.chosen.params));
+                println!("   in-sample     {}", fmt_metrics(&f.chosen.metrics));
+                println!("   out-of-sample {}", fmt_metrics(&f.out_of_sample));
+                oos_pnl += f.out_of_sample.pnl;
+            }
+            println!("📊 [Sweep] total out-of-sample pnl {:.2}", oos_pnl);
+        }
+        Err(e) => {
+            eprintln!("❌ [Sweep] {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+// 运维命令：一次性撤掉所有场馆的挂单，任何一个场馆失败都以非 0 退出
+async fn cancel_all(cfg: AppConfig) {
+    let venues = connect_venues(&cfg, &private_key()).await;
+    let mut all_ok = true;
+    // 认证失败的场馆根本没有撤单，同样算失败
+    for exchange in [Exchange::OpinionLabs, Exchange::Polymarket] {
+        if !venues.contains_key(&exchange) {
+            error!(exchange = ?exchange, "❌ Venue unavailable, orders were NOT cancelled");
+            all_ok = false;
+        }
+    }
+    for venue in venues.into_values() {
+        all_ok &= cancel_all_with_retry(venue).await;
+    }
+    if !all_ok {
+        std::process::exit(1);
+    }
+}
+
+// 运维命令：场馆账户快照 + 本地持仓状态
+async fn status(cfg: AppConfig) {
+    for venue in connect_venues(&cfg, &private_key()).await.values() {
+        log_venue_snapshot(venue.as_ref()).await;
+    }
+
+    let states = load_initial_state(&cfg.engine.state_file, None);
+    for m in &cfg.markets {
+        let (inv, cash) = states.get(&m.opinion_market_id).copied().unwrap_or((0.0, 0.0));
+        info!(market_id = m.opinion_market_id, inventory = inv, cash, "📦 Position");
+    }
+}
+
+async fn replay(cfg: AppConfig, file: &str, speed: f64) {
+    let market_data_pub = ZmqPublisher::new(&cfg.bus.market_data_bind);
+    info!(file, endpoint = %cfg.bus.market_data_bind, speed, "⏪ Replaying");
+    match run_replay(market_data_pub, file, speed).await {
+        Ok(n) => info!(count = n, "✅ Replay finished"),
+        Err(e) => {
+            error!(error = %e, "❌ Replay failed");
+            std::process::exit(1);
+        }
+    }
+}
+
+// 配置校验已在加载时完成，这里打印合并之后的最终参数供人工核对
+fn config_check(cfg: &AppConfig, config_path: &str) {
+    let specs = cfg.market_specs().unwrap_or_default();
+    println!("✅ [Config] {} is valid ({} markets)", config_path, specs.len());
+    for spec in specs {
+        println!("   Market {} <- Poly {} | order ${} | {:?}",
+            spec.opinion_market_id, spec.poly_asset_id, spec.order_size_usd, spec.config);
+    }
+}
@@ -0,0 +1,17 @@
+// File: src/math/logit.rs
+// 概率 <-> 对数几率 (log-odds)：二元结果的价格在 (0, 1) 内，logit 把它映射到整条实数轴
+// 在 logit 空间里做加减，映射回来的价格永远落在 (0, 1) 内，且越靠近边界价格变化越小
+
+// 避免 p = 0 / 1 时得到无穷大
+const EPS: f64 = 1e-6;
+
+/// x = ln(p / (1 - p))
+pub fn logit(p: f64) -> f64 {
+    let p = p.clamp(EPS, 1.0 - EPS);
+    (p / (1.0 - p)).ln()
+}
+
+/// p = 1 / (1 + e^(-x))
+pub fn sigmoid(x: f64) -> f64 {
+    1.0 / (1.0 + (-x).exp())
+}
@@ -1 +1,2 @@
-pub mod volatility;
\ No newline at end of file
+pub mod volatility;
+pub mod logit;
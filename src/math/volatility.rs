@@ -2,9 +2,19 @@ use rust_decimal::Decimal;
 use rust_decimal::prelude::ToPrimitive;
 use std::collections::VecDeque;
 
+use crate::math::logit::logit;
+
+// 收益率口径
+#[derive(Clone, Copy)]
+enum Returns {
+    Log,   // ln(Pt / Pt-1)
+    Logit, // logit(Pt) - logit(Pt-1)
+}
+
 pub struct RollingVolatility {
+    kind: Returns,
     window_size: usize,
-    returns: VecDeque<f64>, // 存 ln(Pt / Pt-1)
+    returns: VecDeque<f64>, // 存收益率 (口径见 kind)
     last_price: Option<f64>,
     sum: f64,
     sum_sq: f64,
@@ -12,7 +22,17 @@ pub struct RollingVolatility {
 
 impl RollingVolatility {
     pub fn new(window_size: usize) -> Self {
+        Self::with_returns(Returns::Log, window_size)
+    }
+
+    /// 对数几率收益率的波动率 (二元结果价格在 logit 空间里的波动)
+    pub fn logit(window_size: usize) -> Self {
+        Self::with_returns(Returns::Logit, window_size)
+    }
+
+    fn with_returns(kind: Returns, window_size: usize) -> Self {
         Self {
+            kind,
             window_size,
             returns: VecDeque::with_capacity(window_size),
             last_price: None,
@@ -24,9 +44,10 @@ impl RollingVolatility {
     pub fn update(&mut self, new_price_dec: Decimal) -> f64 {
         let new_price = new_price_dec.to_f64().unwrap_or(0.0);
         
-        // 1. 计算对数收益率
-        let ret = match self.last_price {
-            Some(last) if last > 0.0 && new_price > 0.0 => (new_price / last).ln(),
+        // 1. 计算收益率
+        let ret = match (self.last_price, self.kind) {
+            (Some(last), Returns::Log) if last > 0.0 && new_price > 0.0 => (new_price / last).ln(),
+            (Some(last), Returns::Logit) if last > 0.0 && new_price > 0.0 => logit(new_price) - logit(last),
             _ => 0.0,
         };
         self.last_price = Some(new_price);
@@ -55,4 +76,38 @@ impl RollingVolatility {
         
         variance.max(0.0).sqrt()
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn logit_volatility_is_larger_near_the_bounds() {
+        // 同样 +/-0.01 的来回波动 (20 个收益率，均值为 0)：0.5 附近与 0.03 附近
+        let vol = |mut v: RollingVolatility, lo, hi| (0..21).map(|i| v.update(if i % 2 == 0 { lo } else { hi })).last().unwrap();
+        let (log_mid, logit_mid) = (vol(RollingVolatility::new(100), dec!(0.50), dec!(0.51)), vol(RollingVolatility::logit(100), dec!(0.50), dec!(0.51)));
+        let logit_edge = vol(RollingVolatility::logit(100), dec!(0.03), dec!(0.04));
+        // 每步收益率 +/-r，标准差 = r
+        let r = |a: f64, b: f64| ((b / (1.0 - b)).ln() - (a / (1.0 - a)).ln()).abs();
+        assert!((logit_mid - r(0.50, 0.51)).abs() < 1e-9);
+        assert!((logit_edge - r(0.03, 0.04)).abs() < 1e-9);
+        // 0.5 附近 logit 收益率 ≈ 对数收益率 / (1 - p)；靠近 0 时 logit 波动远大于 0.5 附近
+        assert!((logit_mid - log_mid / 0.5).abs() < 1e-3);
+        assert!(logit_edge > 5.0 * logit_mid);
+    }
+
+    #[test]
+    fn logit_volatility_needs_two_returns_and_rolls_its_window() {
+        let mut v = RollingVolatility::logit(3);
+        assert_eq!(v.update(dec!(0.2)), 0.0);
+        assert_eq!(v.update(dec!(0.3)), 0.0); // 只有一个收益率
+        assert!(v.update(dec!(0.2)) > 0.0);
+        // 窗口只保留最近 3 个收益率：价格平稳之后波动率归零
+        for _ in 0..3 {
+            v.update(dec!(0.25));
+        }
+        assert!(v.update(dec!(0.25)) < 1e-9);
+    }
 }
\ No newline at end of file
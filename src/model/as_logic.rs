@@ -1,119 +1,42 @@
 // File: src/model/as_logic.rs
+// Avellaneda-Stoikov 报价模型：保留价随库存偏移，价差随波动率与剩余时间变化
+// price_space = "logit" 时在对数几率空间里计算保留价和价差，再映射回概率 (见 math/logit.rs)
+// 映射回来的价格仍按市场规则取整并截到可报价格范围 (默认 0.01 - 0.99)，越过边界的报价会贴在边界上
 use rust_decimal::Decimal;
 use rust_decimal::prelude::ToPrimitive;
-use rust_decimal_macros::dec;
+use crate::math::logit::{logit, sigmoid};
 use crate::math::volatility::RollingVolatility;
-use serde::{Serialize, Deserialize};
-use std::sync::mpsc::Sender;
-
-// --- 配置部分 ---
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct StrategyConfig {
-    pub risk_aversion_gamma: f64,
-    pub liquidity_k: f64,
-    pub min_spread_bps: u32,
-    pub tick_size: f64,
-    pub max_inventory_usd: f64,
-    // 时间相关
-    pub maturity_timestamp_ms: i64,
-    pub terminal_dumping_factor: f64,
-    pub closing_window_seconds: i64,
-}
-
-// --- 持久化状态结构 (写入磁盘的内容) ---
-#[derive(Debug, Serialize, Deserialize)]
-pub struct PersistState {
-    pub inventory_shares: f64,
-    pub cash_balance: f64, // 账户里的现金余额 (Realized PnL 累积)
-    pub timestamp: i64,
-}
+use crate::venues::rules::MarketRules;
+use crate::model::strategy::{quote_ladders, Ledger, PriceSpace, Quote, Strategy, StrategyConfig};
 
 pub struct OpinionGridStrategy {
     cfg: StrategyConfig,
-    vol_calc: RollingVolatility,
-    
-    // 核心状态
-    pub current_inventory_shares: f64,
-    pub current_cash_balance: f64, // 内存中的现金余额
-    
-    // 辅助状态：用于计算权益变动
-    last_equity_mark: f64, 
-    
-    // IO 通道
-    persist_sender: Option<Sender<PersistState>>, 
+    rules: MarketRules, // 报价市场的 tick 表 / 最小下单量
+    vol_calc: RollingVolatility,       // 对数收益率波动率 (price_space = linear)
+    logit_vol_calc: RollingVolatility, // 对数几率收益率波动率 (price_space = logit)
+    ledger: Ledger,
 }
 
 impl OpinionGridStrategy {
-    pub fn new(cfg: StrategyConfig, sender: Option<Sender<PersistState>>) -> Self {
+    pub fn new(cfg: StrategyConfig, rules: MarketRules, ledger: Ledger) -> Self {
         Self {
             cfg,
+            rules,
             vol_calc: RollingVolatility::new(100),
-            current_inventory_shares: 0.0,
-            current_cash_balance: 0.0, // 初始为 0，等待 restore
-            last_equity_mark: 0.0,
-            persist_sender: sender,
-        }
-    }
-
-    /// [系统启动时调用] 恢复之前的账本
-    pub fn restore_state(&mut self, saved_inv: f64, saved_cash: f64) {
-        self.current_inventory_shares = saved_inv;
-        self.current_cash_balance = saved_cash;
-        println!("♻️ [State Restored] Inv: {}, Cash: ${:.4}", saved_inv, saved_cash);
-    }
-
-    /// [成交回调] 更新库存和现金，并触发异步写入
-    pub fn on_fill(&mut self, change_shares: f64, net_cash_flow: f64) {
-        self.current_inventory_shares += change_shares;
-        self.current_cash_balance += net_cash_flow;
-
-        // ⚡️ 异步 IO：状态存盘
-        if let Some(tx) = &self.persist_sender {
-            // 这里我们忽略 send 错误，因为在极高频下如果 channel 满了，我们选择丢弃旧状态
-            // 但对于资金状态，最好保证 buffer 足够大
-            let _ = tx.send(PersistState {
-                inventory_shares: self.current_inventory_shares,
-                cash_balance: self.current_cash_balance,
-                timestamp: chrono::Utc::now().timestamp(),
-            });
+            logit_vol_calc: RollingVolatility::logit(100),
+            ledger,
         }
     }
 
-    /// [核心风控计算] 计算权益变动 (Mark-to-Market PnL)
-    /// 公式：Total Equity = Cash + (Inventory * MidPrice)
-    /// 返回值：PnL Change (相对于上一次计算的变动值)
-    pub fn calculate_equity_change(&mut self, current_mid_price: f64) -> f64 {
-        let position_value = self.current_inventory_shares * current_mid_price;
-        let current_equity = self.current_cash_balance + position_value;
-
-        // 如果是启动后的第一次计算，我们初始化基准值，不产生 PnL 跳变
-        if self.last_equity_mark == 0.0 && self.current_inventory_shares == 0.0 && self.current_cash_balance == 0.0 {
-             self.last_equity_mark = current_equity;
-             return 0.0;
-        }
-        
-        // 第一次恢复状态后的校准
-        if self.last_equity_mark == 0.0 {
-             self.last_equity_mark = current_equity;
-             return 0.0;
-        }
-
-        let pnl_change = current_equity - self.last_equity_mark;
-        
-        // 更新水位线
-        self.last_equity_mark = current_equity;
-        
-        pnl_change
-    }
-    
-    pub fn calculate_quotes(&mut self, poly_mid_price: Decimal) -> (Decimal, Decimal) {
+    /// base_size_usd: 该市场配置的单笔下单金额 (库存为 0、波动正常时两侧都用它)
+    pub fn calculate_quotes(&mut self, poly_mid_price: Decimal, base_size_usd: Decimal) -> Quote {
         // 1. 获取当前时间与剩余时间
-        let now = chrono::Utc::now().timestamp_millis();
+        let now = self.ledger.now_ms();
         let time_left_ms = self.cfg.maturity_timestamp_ms - now;
         
         // 如果市场已经结束，停止报价（或者报出一个极宽的价格）
         if time_left_ms <= 0 {
-            return (dec!(0), dec!(0));
+            return Quote::default();
         }
 
         // 2. 将剩余时间标准化为“年” (AS 模型通常基于年化波动率)
@@ -125,48 +48,245 @@ impl OpinionGridStrategy {
         // 逻辑：如果是“垃圾时间”(Closing Window)，我们极度厌恶持仓，Gamma 暴增
         let effective_gamma = if time_left_ms < (self.cfg.closing_window_seconds * 1000) {
             // 线性插值：时间越少，Gamma 越大，最大达到 terminal_dumping_factor 倍
-            let progress = 1.0 - (time_left_ms as f64 / (self.cfg.closing_window_seconds * 1000.0 as f64));
+            let progress = 1.0 - (time_left_ms as f64 / (self.cfg.closing_window_seconds as f64 * 1000.0));
             self.cfg.risk_aversion_gamma * (1.0 + progress * self.cfg.terminal_dumping_factor)
         } else {
             self.cfg.risk_aversion_gamma
         };
 
-        // 4. 计算数据
-        let sigma = self.vol_calc.update(poly_mid_price);
+        // 4. 计算数据 (两种口径的波动率都持续更新，热切换 price_space 时不用重新积累窗口)
+        let sigma_log = self.vol_calc.update(poly_mid_price);
+        let sigma_logit = self.logit_vol_calc.update(poly_mid_price);
         let mid_f64 = poly_mid_price.to_f64().unwrap_or(0.5);
-        
-        // 5. AS 模型完全体公式
-        // r = s - q * gamma * sigma^2 * T
-        // 注意：这里的 T 实际上是一个“风险窗口”。
-        // 在标准 AS 中，T 变小 Skew 变小。但在预测市场，如果你想平仓，必须配合上面的 effective_gamma 暴增。
-        // 简单的工程实践：保留 T 项用于衰减长期风险，但在末端通过 Gamma 反向拉升。
-        let risk_term = self.current_inventory_shares * effective_gamma * (sigma * sigma) * t_days.max(0.01); 
-        let reservation_price = mid_f64 - risk_term;
-
-        // 6. 动态价差 (Spread)
-        // delta = gamma * sigma^2 * T + (2/gamma) * ln(1 + gamma/k)
-        let spread_term_1 = effective_gamma * (sigma * sigma) * t_days.max(0.01);
-        let spread_term_2 = (2.0 / effective_gamma) * (1.0 + effective_gamma / self.cfg.liquidity_k).ln();
-        
-        let half_spread = spread_term_1 + spread_term_2;
-
-        // 7. 最小价差兜底 (防止 Gas 费亏损)
+        let inventory = self.ledger.current_inventory_shares;
         let min_half = (self.cfg.min_spread_bps as f64 / 10000.0) / 2.0;
-        let final_half_spread = half_spread.max(min_half);
 
-        let raw_bid = reservation_price - final_half_spread;
-        let raw_ask = reservation_price + final_half_spread;
+        let (raw_bid, raw_ask, sigma) = match self.cfg.price_space {
+            PriceSpace::Linear => {
+                // 5. AS 模型完全体公式
+                // r = s - q * gamma * sigma^2 * T
+                // 注意：这里的 T 实际上是一个“风险窗口”。
+                // 在标准 AS 中，T 变小 Skew 变小。但在预测市场，如果你想平仓，必须配合上面的 effective_gamma 暴增。
+                // 简单的工程实践：保留 T 项用于衰减长期风险，但在末端通过 Gamma 反向拉升。
+                let risk_term = inventory * effective_gamma * (sigma_log * sigma_log) * t_days.max(0.01);
+                let reservation_price = mid_f64 - risk_term;
+
+                // 6. 动态价差 (Spread)
+                // delta = gamma * sigma^2 * T + (2/gamma) * ln(1 + gamma/k)
+                let spread_term_1 = effective_gamma * (sigma_log * sigma_log) * t_days.max(0.01);
+                let spread_term_2 = (2.0 / effective_gamma) * (1.0 + effective_gamma / self.cfg.liquidity_k).ln();
+
+                // 7. 最小价差兜底 (防止 Gas 费亏损)
+                let final_half_spread = (spread_term_1 + spread_term_2).max(min_half);
+                (reservation_price - final_half_spread, reservation_price + final_half_spread, sigma_log)
+            }
+            PriceSpace::Logit => {
+                // 5-7. 同一组公式放到 x = logit(p) 空间：保留价和价差都以对数几率计，sigmoid 映射回概率
+                // 价格对 x 的敏感度 dp/dx = p(1-p)：流动性 k 和最小价差按它换算到 x 空间
+                // 越靠近 0 / 1，同样的 x 偏移对应的价格偏移越小，且永远不会越过边界
+                let x = logit(mid_f64);
+                let slope = (mid_f64 * (1.0 - mid_f64)).max(1e-4);
+                let reservation_x = x - inventory * effective_gamma * (sigma_logit * sigma_logit) * t_days.max(0.01);
+
+                let spread_term_1 = effective_gamma * (sigma_logit * sigma_logit) * t_days.max(0.01);
+                let spread_term_2 = (2.0 / effective_gamma) * (1.0 + effective_gamma / (self.cfg.liquidity_k * slope)).ln();
+                let half_spread_x = (spread_term_1 + spread_term_2).max(min_half / slope);
+
+                // 金额缩放和波动率档距按价格收益率计：logit 波动 x (1 - p) ≈ 对数收益率波动
+                (sigmoid(reservation_x - half_spread_x), sigmoid(reservation_x + half_spread_x), sigma_logit * (1.0 - mid_f64))
+            }
+        };
+
+        // 8. 报价阶梯 (金额按库存 / 波动率 / 剩余时间调整)
+        quote_ladders(&self.cfg, &self.rules, inventory, (raw_bid, raw_ask), base_size_usd, (mid_f64, sigma), time_left_ms)
+    }
+}
+
+impl Strategy for OpinionGridStrategy {
+    fn ledger(&self) -> &Ledger {
+        &self.ledger
+    }
+
+    fn ledger_mut(&mut self) -> &mut Ledger {
+        &mut self.ledger
+    }
+
+    fn config(&self) -> &StrategyConfig {
+        &self.cfg
+    }
+
+    fn set_config(&mut self, cfg: StrategyConfig) {
+        self.cfg = cfg;
+    }
+
+    fn on_book(&mut self, mid: Decimal, base_size_usd: Decimal) -> Quote {
+        self.calculate_quotes(mid, base_size_usd)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::clock::SimClock;
+    use crate::model::strategy::QuoteLevel;
+    use rust_decimal_macros::dec;
+    use std::sync::Arc;
+
+    const NOW_MS: i64 = 1_800_000_000_000;
+
+    // 第 0 档的金额 (该边不报时为 0)
+    fn top_size(levels: &[QuoteLevel]) -> Decimal {
+        levels.first().map(|l| l.size_usd).unwrap_or_default()
+    }
+
+    fn strategy(cfg: StrategyConfig) -> (OpinionGridStrategy, Arc<SimClock>) {
+        let clock = Arc::new(SimClock::default());
+        clock.set(NOW_MS * 1_000_000);
+        let cfg = StrategyConfig { maturity_timestamp_ms: NOW_MS + 30 * 24 * 3600 * 1000, ..cfg };
+        (OpinionGridStrategy::new(cfg, MarketRules::default(), Ledger::new(1, None, clock.clone())), clock)
+    }
+
+    #[test]
+    fn flat_inventory_quotes_base_size_on_both_sides() {
+        let (mut s, _) = strategy(StrategyConfig::default());
+        let q = s.calculate_quotes(dec!(0.5), dec!(50));
+        assert_eq!((top_size(&q.bids), top_size(&q.asks)), (dec!(50), dec!(50)));
+    }
+
+    #[test]
+    fn long_inventory_shrinks_bid_and_grows_ask() {
+        let (mut s, _) = strategy(StrategyConfig::default());
+        s.ledger_mut().restore_state(2000.0, 0.0); // 2000 份 x 0.5 = 1000 USD = 50% 软上限
+        let q = s.calculate_quotes(dec!(0.5), dec!(50));
+        assert_eq!((top_size(&q.bids), top_size(&q.asks)), (dec!(25), dec!(75)));
+
+        // 到达软上限：加仓一侧停止报价，减仓一侧翻倍
+        s.ledger_mut().restore_state(-4000.0, 0.0);
+        let q = s.calculate_quotes(dec!(0.5), dec!(50));
+        assert_eq!((top_size(&q.bids), top_size(&q.asks)), (dec!(100), dec!(0)));
+    }
+
+    #[test]
+    fn high_volatility_scales_both_sides_down() {
+        let (mut s, _) = strategy(StrategyConfig { size_vol_target: 0.01, ..Default::default() });
+        let mut q = s.calculate_quotes(dec!(0.5), dec!(50));
+        for i in 0..20 {
+            let mid = if i % 2 == 0 { dec!(0.6) } else { dec!(0.4) };
+            q = s.calculate_quotes(mid, dec!(50));
+        }
+        assert!(top_size(&q.bids) < dec!(5) && top_size(&q.bids) > dec!(0), "{:?}", q);
+        assert_eq!(top_size(&q.bids), top_size(&q.asks));
+    }
+
+    #[test]
+    fn closing_window_shrinks_only_the_adding_side() {
+        let (mut s, clock) = strategy(StrategyConfig { closing_window_seconds: 3600, ..Default::default() });
+        s.ledger_mut().restore_state(400.0, 0.0);
+        // 距离到期还剩 15 分钟：加仓 (买) 一侧只剩 1/4
+        let maturity = s.config().maturity_timestamp_ms;
+        clock.set((maturity - 900_000) * 1_000_000);
+        let q = s.calculate_quotes(dec!(0.5), dec!(50));
+        let skew = 400.0 * 0.5 / 2000.0;
+        assert_eq!(top_size(&q.bids), Decimal::from_f64_retain(50.0 * (1.0 - skew) * 0.25).unwrap().round_dp(2));
+        assert_eq!(top_size(&q.asks), Decimal::from_f64_retain(50.0 * (1.0 + skew)).unwrap().round_dp(2));
 
-        // 8. 边界检查 (0.01 - 0.99)
-        (
-            Self::round_to_tick(raw_bid, self.cfg.tick_size),
-            Self::round_to_tick(raw_ask, self.cfg.tick_size)
-        )
+        clock.set(maturity * 1_000_000);
+        assert!(s.calculate_quotes(dec!(0.5), dec!(50)).bids.is_empty());
     }
 
-    fn round_to_tick(price: f64, tick: f64) -> Decimal {
-        let p = (price / tick).round() * tick;
-        let p = p.max(0.01).min(0.99); // 预测市场价格边界
-        Decimal::from_f64_retain(p).unwrap_or(dec!(0.5))
+    #[test]
+    fn closing_window_leans_harder_against_inventory() {
+        // 同样的波动率历史与库存，只差 terminal_dumping_factor：15 分钟时 gamma 放大 1 + 0.75 x 10 倍
+        let quote = |terminal_dumping_factor: f64| {
+            let (mut s, clock) = strategy(StrategyConfig { tick_size: 0.0001, terminal_dumping_factor, ..Default::default() });
+            s.ledger_mut().restore_state(400.0, 0.0);
+            for i in 0..20 {
+                s.calculate_quotes(if i % 2 == 0 { dec!(0.52) } else { dec!(0.48) }, dec!(50));
+            }
+            clock.set((s.config().maturity_timestamp_ms - 900_000) * 1_000_000);
+            let q = s.calculate_quotes(dec!(0.5), dec!(50));
+            (q.bids[0].price, q.asks[0].price)
+        };
+        let (calm_bid, calm_ask) = quote(0.0);
+        let (dump_bid, dump_ask) = quote(10.0);
+        // 多头库存：保留价进一步下移，两边都更便宜；价差不会收窄 (这组参数下由最小价差兜底)
+        assert!(dump_ask - dump_bid >= calm_ask - calm_bid, "calm {}-{} dump {}-{}", calm_bid, calm_ask, dump_bid, dump_ask);
+        assert!(dump_bid < calm_bid && dump_ask < calm_ask, "calm {}-{} dump {}-{}", calm_bid, calm_ask, dump_bid, dump_ask);
+    }
+
+    #[test]
+    fn no_quotes_on_either_side_from_maturity_on() {
+        let (mut s, clock) = strategy(StrategyConfig::default());
+        let maturity = s.config().maturity_timestamp_ms;
+        // 清仓窗口之外还是两边报价
+        clock.set((maturity - 7_200_000) * 1_000_000);
+        let q = s.calculate_quotes(dec!(0.5), dec!(50));
+        assert!(!q.bids.is_empty() && !q.asks.is_empty());
+
+        for now_ms in [maturity, maturity + 3_600_000] {
+            clock.set(now_ms * 1_000_000);
+            let q = s.calculate_quotes(dec!(0.5), dec!(50));
+            assert!(q.bids.is_empty() && q.asks.is_empty(), "{:?}", q);
+        }
+    }
+
+    #[test]
+    fn ladder_steps_out_by_ticks_with_decaying_size() {
+        let (mut s, _) = strategy(StrategyConfig { ladder_levels: 3, ladder_step_ticks: 2, ..Default::default() });
+        let q = s.calculate_quotes(dec!(0.5), dec!(40));
+        let bids: Vec<_> = q.bids.iter().map(|l| (l.price, l.size_usd)).collect();
+        let asks: Vec<_> = q.asks.iter().map(|l| (l.price, l.size_usd)).collect();
+        let (b0, a0) = (bids[0].0, asks[0].0);
+        assert_eq!(bids, vec![(b0, dec!(40)), (b0 - dec!(0.02), dec!(20)), (b0 - dec!(0.04), dec!(10))]);
+        assert_eq!(asks, vec![(a0, dec!(40)), (a0 + dec!(0.02), dec!(20)), (a0 + dec!(0.04), dec!(10))]);
+    }
+
+    #[test]
+    fn ladder_stops_at_price_bounds() {
+        let (mut s, _) = strategy(StrategyConfig { ladder_levels: 5, ..Default::default() });
+        let q = s.calculate_quotes(dec!(0.025), dec!(40));
+        // 买单阶梯被 0.01 截断，不会在同一价格上堆叠多档
+        assert!(q.bids.len() < 5);
+        assert!(q.bids.windows(2).all(|w| w[0].price > w[1].price));
+        assert_eq!(q.asks.len(), 5);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn crossing_levels_are_clamped_inside_the_venue_book() {
+        let (mut s, _) = strategy(StrategyConfig { ladder_levels: 3, ..Default::default() });
+        let mut q = s.calculate_quotes(dec!(0.5), dec!(40));
+        let (b0, a0) = (q.bids[0].price, q.asks[0].price);
+        // Opinion 卖一落在我们第 0、1 档买价之间：第 0 档截到卖一下方一个 tick，与第 1 档合并 (金额相加)
+        let best_ask = b0 - dec!(0.005);
+        assert!(q.keep_passive(Some(dec!(0.1)), Some(best_ask), &MarketRules::uniform(dec!(0.01), dec!(0))));
+        let bids: Vec<_> = q.bids.iter().map(|l| (l.price, l.size_usd)).collect();
+        assert_eq!(bids, vec![(b0 - dec!(0.01), dec!(60)), (b0 - dec!(0.02), dec!(10))]);
+        // 不穿价的一边保持不变
+        assert_eq!(q.asks[0].price, a0);
+        assert_eq!(q.asks.len(), 3);
+    }
+
+    #[test]
+    fn logit_pricing_leans_away_from_the_nearest_bound() {
+        let cfg = StrategyConfig { price_space: PriceSpace::Logit, tick_size: 0.0001, ..Default::default() };
+        for (mid, lower_half) in [(dec!(0.03), true), (dec!(0.97), false)] {
+            let (mut s, _) = strategy(cfg.clone());
+            let q = s.calculate_quotes(mid, dec!(50));
+            let (bid, ask) = (q.bids[0].price, q.asks[0].price);
+            assert!(bid > dec!(0.01) && ask < dec!(0.99), "{} {}", bid, ask);
+            // 靠近 0 时向下的价格空间更小：买价离中间价更近，靠近 1 时反过来
+            assert_eq!(mid - bid < ask - mid, lower_half, "mid {} bid {} ask {}", mid, bid, ask);
+        }
+    }
+
+    #[test]
+    fn logit_quotes_are_still_clamped_to_the_tick_bounds() {
+        // 默认 tick 0.01：可报价格 0.01 - 0.99，logit 空间算出的更窄报价也要落在网格和边界上
+        let cfg = StrategyConfig { price_space: PriceSpace::Logit, ..Default::default() };
+        for (mid, expected) in [(dec!(0.012), (dec!(0.01), dec!(0.02))), (dec!(0.988), (dec!(0.98), dec!(0.99)))] {
+            let (mut s, _) = strategy(cfg.clone());
+            let q = s.calculate_quotes(mid, dec!(50));
+            assert_eq!((q.bids[0].price, q.asks[0].price), expected, "mid {}", mid);
+        }
+    }
+}
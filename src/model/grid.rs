@@ -0,0 +1,78 @@
+// File: src/model/grid.rs
+// 固定价差网格：中间价两侧各让出 grid_half_spread_ticks 个 tick，价格不随库存偏移 (对照基准)
+// 下单金额与报价阶梯仍按通用规则随库存 / 波动率 / 剩余时间调整
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+use crate::math::volatility::RollingVolatility;
+use crate::venues::rules::MarketRules;
+use crate::model::strategy::{quote_ladders, Ledger, Quote, Strategy, StrategyConfig};
+
+pub struct GridStrategy {
+    cfg: StrategyConfig,
+    rules: MarketRules, // 报价市场的 tick 表 / 最小下单量
+    vol_calc: RollingVolatility,
+    ledger: Ledger,
+}
+
+impl GridStrategy {
+    pub fn new(cfg: StrategyConfig, rules: MarketRules, ledger: Ledger) -> Self {
+        Self {
+            cfg,
+            rules,
+            vol_calc: RollingVolatility::new(100),
+            ledger,
+        }
+    }
+}
+
+impl Strategy for GridStrategy {
+    fn ledger(&self) -> &Ledger {
+        &self.ledger
+    }
+
+    fn ledger_mut(&mut self) -> &mut Ledger {
+        &mut self.ledger
+    }
+
+    fn config(&self) -> &StrategyConfig {
+        &self.cfg
+    }
+
+    fn set_config(&mut self, cfg: StrategyConfig) {
+        self.cfg = cfg;
+    }
+
+    fn on_book(&mut self, mid: Decimal, base_size_usd: Decimal) -> Quote {
+        let time_left_ms = self.cfg.maturity_timestamp_ms - self.ledger.now_ms();
+        if time_left_ms <= 0 {
+            return Quote::default();
+        }
+
+        let sigma = self.vol_calc.update(mid);
+        let mid_f64 = mid.to_f64().unwrap_or(0.5);
+        // 按中间价所在区间的 tick 计算 (边界附近 tick 更细)
+        let tick = self.rules.with_default_tick(self.cfg.tick_size).tick_at(mid).to_f64().unwrap_or(self.cfg.tick_size);
+        let half_spread = self.cfg.grid_half_spread_ticks as f64 * tick;
+        quote_ladders(&self.cfg, &self.rules, self.ledger.current_inventory_shares, (mid_f64 - half_spread, mid_f64 + half_spread),
+            base_size_usd, (mid_f64, sigma), time_left_ms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::clock::SimClock;
+    use rust_decimal_macros::dec;
+    use std::sync::Arc;
+
+    #[test]
+    fn quotes_fixed_ticks_around_mid_regardless_of_inventory() {
+        let cfg = StrategyConfig { maturity_timestamp_ms: i64::MAX, grid_half_spread_ticks: 3, ..Default::default() };
+        let mut s = GridStrategy::new(cfg, MarketRules::default(), Ledger::new(1, None, Arc::new(SimClock::default())));
+        s.ledger_mut().restore_state(1000.0, 0.0);
+        let q = s.on_book(dec!(0.5), dec!(50));
+        assert_eq!((q.bids[0].price, q.asks[0].price), (dec!(0.47), dec!(0.53)));
+        // 库存只影响金额：多头时买单变小
+        assert!(q.bids[0].size_usd < q.asks[0].size_usd);
+    }
+}
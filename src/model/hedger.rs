@@ -0,0 +1,287 @@
+// File: src/model/hedger.rs
+// 跨场馆 Delta 对冲器：Opinion 上被动成交积累的敞口，到更深的 Polymarket 订单簿上主动平掉
+use crate::core::{BookSide, Exchange, OrderBookUpdate, Side, StageStamps, TradeSignal, LOGIC_TAG_HEDGE};
+use rust_decimal::Decimal;
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HedgeConfig {
+    pub threshold_shares: f64,     // 净敞口 (份额) 超过该值才触发对冲
+    pub max_slippage: Decimal,     // 相对最优价最多让出的价格 (如 0.02 = 2 美分)
+    pub max_hedge_usd: Decimal,    // 单笔对冲金额上限
+    pub pending_timeout_ms: i64,   // IOC 单发出后等待回报的超时时间
+}
+
+impl Default for HedgeConfig {
+    fn default() -> Self {
+        Self {
+            threshold_shares: 20.0,   // 净敞口超过 20 份才对冲，避免碎单
+            max_slippage: dec!(0.02), // 最多让出 2 美分
+            max_hedge_usd: dec!(500), // 与单笔风控上限一致
+            pending_timeout_ms: 3000, // 3 秒内没有回报视为 IOC 已失效
+        }
+    }
+}
+
+// 单个事件 (Opinion 市场 <-> Polymarket Token) 的敞口账本
+#[derive(Default)]
+struct EventExposure {
+    opinion_shares: f64,
+    poly_shares: f64,
+    pending_hedge_shares: f64, // 已发出但尚未回报的对冲 (带方向)
+    last_hedge_ms: i64,
+
+    // 对冲腿的现金账本，用于把对冲盈亏计入风控
+    poly_cash_usd: f64,
+    last_equity_mark: Option<f64>,
+}
+
+pub struct DeltaHedger {
+    cfg: HedgeConfig,
+    // Opinion Market ID -> Polymarket symbol_id (同一个底层事件)
+    pairs: HashMap<u64, u64>,
+    // 以 Polymarket symbol_id 作为事件主键
+    exposures: HashMap<u64, EventExposure>,
+    // 最新的 Polymarket 盘口 (bids, asks)
+    books: HashMap<u64, (BookSide, BookSide)>,
+    // 对冲限价的范围 (卖出下限, 买入上限)，与 RiskManager 的价格保护一致，否则对冲单会被风控拒绝
+    price_bounds: (Decimal, Decimal),
+}
+
+impl DeltaHedger {
+    pub fn new(cfg: HedgeConfig, pairs: HashMap<u64, u64>, price_bounds: (Decimal, Decimal)) -> Self {
+        Self {
+            cfg,
+            pairs,
+            exposures: HashMap::new(),
+            books: HashMap::new(),
+            price_bounds,
+        }
+    }
+
+    // 未配置映射时，沿用引擎当前的简化约定：两边使用同一个 ID
+    fn event_key(&self, exchange: Exchange, symbol_id: u64) -> u64 {
+        match exchange {
+            Exchange::OpinionLabs => self.pairs.get(&symbol_id).copied().unwrap_or(symbol_id),
+            _ => symbol_id,
+        }
+    }
+
+    /// [行情回调] 记录 Polymarket 盘口 (对冲深度与滑点计算的依据)
+    pub fn on_book(&mut self, update: &OrderBookUpdate) {
+        if update.exchange != Exchange::Polymarket {
+            return;
+        }
+        self.books.insert(update.symbol_id, (update.bids.clone(), update.asks.clone()));
+    }
+
+    /// [成交回调] 更新对应场馆的仓位；返回受影响的事件主键
+    pub fn on_fill(&mut self, exchange: Exchange, symbol_id: u64, change_shares: f64, net_cash_flow: f64) -> u64 {
+        let key = self.event_key(exchange, symbol_id);
+        let exp = self.exposures.entry(key).or_default();
+
+        match exchange {
+            Exchange::Polymarket => {
+                exp.poly_shares += change_shares;
+                exp.poly_cash_usd += net_cash_flow;
+
+                // 对冲回报抵消在途数量；方向反转说明在途已全部成交
+                let remaining = exp.pending_hedge_shares - change_shares;
+                exp.pending_hedge_shares = if remaining * exp.pending_hedge_shares > 0.0 { remaining } else { 0.0 };
+            }
+            _ => exp.opinion_shares += change_shares,
+        }
+        key
+    }
+
+    /// 当前净敞口 (份额，含在途对冲)
+    pub fn net_exposure(&self, event_key: u64) -> f64 {
+        self.exposures.get(&event_key)
+            .map(|e| e.opinion_shares + e.poly_shares + e.pending_hedge_shares)
+            .unwrap_or(0.0)
+    }
+
+    /// [核心逻辑] 检查敞口，必要时生成 Polymarket 对冲信号 (IOC / 激进限价)
+    /// trace_id: 触发本次检查的行情 / 成交的追踪 ID，对冲单沿用
+    pub fn check_hedge(&mut self, event_key: u64, now_ms: i64, trace_id: u64) -> Option<TradeSignal> {
+        let cfg = &self.cfg;
+        let exp = self.exposures.get_mut(&event_key)?;
+
+        // 1. 还有在途对冲：等待回报，超时则视为 IOC 未成交部分已被撤销
+        if exp.pending_hedge_shares != 0.0 {
+            if now_ms - exp.last_hedge_ms < cfg.pending_timeout_ms {
+                return None;
+            }
+            exp.pending_hedge_shares = 0.0;
+        }
+
+        let net = exp.opinion_shares + exp.poly_shares;
+        if net.abs() < cfg.threshold_shares {
+            return None;
+        }
+
+        // 2. 多头敞口 -> 卖出吃 bids；空头敞口 -> 买入吃 asks
+        let side = if net > 0.0 { Side::Sell } else { Side::Buy };
+        let (bids, asks) = self.books.get(&event_key)?;
+        let levels = if side == Side::Sell { bids } else { asks };
+        let best = levels.first()?.0;
+
+        // 3. 滑点上限：最差可接受价格 (不超出风控的价格保护范围)
+        let (floor, ceiling) = self.price_bounds;
+        let limit_price = match side {
+            Side::Sell => (best - cfg.max_slippage).max(floor),
+            Side::Buy => (best + cfg.max_slippage).min(ceiling),
+        };
+
+        // 4. 只吃限价以内的深度
+        let depth: Decimal = levels.iter()
+            .filter(|(p, _)| match side {
+                Side::Sell => *p >= limit_price,
+                Side::Buy => *p <= limit_price,
+            })
+            .map(|(_, s)| *s)
+            .sum();
+
+        let wanted = Decimal::from_f64(net.abs())?.round_dp(2);
+        let max_by_usd = (cfg.max_hedge_usd / limit_price).round_dp(2);
+        let shares = wanted.min(depth).min(max_by_usd);
+        if shares <= Decimal::ZERO {
+            return None;
+        }
+
+        let signed_shares = shares.to_f64().unwrap_or(0.0);
+        exp.pending_hedge_shares = if side == Side::Sell { -signed_shares } else { signed_shares };
+        exp.last_hedge_ms = now_ms;
+
+        Some(TradeSignal {
+            strategy_id: 2,
+            target_exchange: Exchange::Polymarket,
+            symbol_id: event_key,
+            side,
+            price: limit_price,
+            size_usd: shares * limit_price,
+            logic_tag: LOGIC_TAG_HEDGE,
+            post_only: false, // 对冲单要吃流动性
+            level: 0,
+            created_at_ns: now_ms * 1_000_000,
+            trace_id,
+            stamps: StageStamps::default(), // 由引擎在发出前打点
+        })
+    }
+
+    /// [风控] 对冲腿的权益变动 (Mark-to-Market)，与 Ledger::calculate_equity_change 同口径
+    pub fn calculate_equity_change(&mut self, event_key: u64, current_mid_price: f64) -> f64 {
+        let Some(exp) = self.exposures.get_mut(&event_key) else { return 0.0 };
+        let current_equity = exp.poly_cash_usd + exp.poly_shares * current_mid_price;
+
+        let pnl_change = match exp.last_equity_mark {
+            Some(mark) => current_equity - mark,
+            None => 0.0, // 第一次计算只建立基准
+        };
+        exp.last_equity_mark = Some(current_equity);
+        pnl_change
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::RiskConfig;
+    use crate::infrastructure::clock::SimClock;
+    use crate::model::risk::RiskManager;
+    use smallvec::smallvec;
+    use std::sync::Arc;
+
+    const OPINION_ID: u64 = 7;
+    const POLY_ID: u64 = 123;
+
+    fn hedger() -> DeltaHedger {
+        let mut hedger = DeltaHedger::new(HedgeConfig::default(), HashMap::from([(OPINION_ID, POLY_ID)]), (dec!(0.02), dec!(0.98)));
+        hedger.on_book(&OrderBookUpdate {
+            exchange: Exchange::Polymarket,
+            symbol_id: POLY_ID,
+            timestamp_ns: 0,
+            trace_id: 0,
+            bids: smallvec![(dec!(0.50), dec!(10)), (dec!(0.49), dec!(15)), (dec!(0.47), dec!(100))],
+            asks: smallvec![(dec!(0.52), dec!(1000))],
+            stamps: StageStamps::default(),
+        });
+        hedger
+    }
+
+    #[test]
+    fn exposure_accumulates_across_venues_on_one_event() {
+        let mut hedger = hedger();
+        assert_eq!(hedger.on_fill(Exchange::OpinionLabs, OPINION_ID, 30.0, -15.0), POLY_ID);
+        assert_eq!(hedger.on_fill(Exchange::Polymarket, POLY_ID, -10.0, 5.0), POLY_ID);
+        assert_eq!(hedger.net_exposure(POLY_ID), 20.0);
+        // 低于阈值不对冲
+        hedger.on_fill(Exchange::Polymarket, POLY_ID, -1.0, 0.5);
+        assert!(hedger.check_hedge(POLY_ID, 0, 1).is_none());
+    }
+
+    #[test]
+    fn hedge_takes_depth_within_the_slippage_limit() {
+        let mut hedger = hedger();
+        hedger.on_fill(Exchange::OpinionLabs, OPINION_ID, 40.0, -20.0);
+
+        // 多头 40 份 -> 卖出；限价 = 买一 0.50 - 0.02，0.47 那档在限价之外，只能吃 10 + 15 份
+        let hedge = hedger.check_hedge(POLY_ID, 0, 9).unwrap();
+        assert_eq!((hedge.side, hedge.price, hedge.logic_tag, hedge.trace_id), (Side::Sell, dec!(0.48), LOGIC_TAG_HEDGE, 9));
+        assert_eq!(hedge.size_usd, dec!(25) * dec!(0.48));
+        assert_eq!(hedger.net_exposure(POLY_ID), 15.0);
+
+        // 空头敞口 -> 买入，且受 max_hedge_usd 限制
+        let mut hedger = self::hedger();
+        hedger.cfg.max_hedge_usd = dec!(54);
+        hedger.on_fill(Exchange::OpinionLabs, OPINION_ID, -500.0, 250.0);
+        let hedge = hedger.check_hedge(POLY_ID, 0, 0).unwrap();
+        assert_eq!((hedge.side, hedge.price), (Side::Buy, dec!(0.54)));
+        assert_eq!(hedge.size_usd, dec!(100) * dec!(0.54));
+    }
+
+    #[test]
+    fn buy_hedge_near_the_top_is_capped_at_the_risk_ceiling() {
+        let risk = RiskManager::new(&RiskConfig::default(), Arc::new(SimClock::default()));
+        let mut hedger = DeltaHedger::new(HedgeConfig::default(), HashMap::from([(OPINION_ID, POLY_ID)]),
+            (risk.stop_loss_price_floor, risk.stop_loss_price_ceiling));
+        hedger.on_book(&OrderBookUpdate {
+            exchange: Exchange::Polymarket,
+            symbol_id: POLY_ID,
+            timestamp_ns: 0,
+            trace_id: 0,
+            bids: smallvec![(dec!(0.96), dec!(100))],
+            asks: smallvec![(dec!(0.975), dec!(100))],
+            stamps: StageStamps::default(),
+        });
+
+        // 空头敞口 -> 买入：卖一 0.975 + 0.02 滑点会超过风控上限 0.98，限价截到 0.98，仍然能吃到卖一
+        hedger.on_fill(Exchange::OpinionLabs, OPINION_ID, -30.0, 29.0);
+        let hedge = hedger.check_hedge(POLY_ID, 0, 0).unwrap();
+        assert_eq!((hedge.side, hedge.price), (Side::Buy, dec!(0.98)));
+        assert_eq!(hedge.size_usd, dec!(30) * dec!(0.98));
+        assert!(risk.check_signal(&hedge));
+    }
+
+    #[test]
+    fn pending_hedge_is_retried_after_the_timeout() {
+        let mut hedger = hedger();
+        hedger.on_fill(Exchange::OpinionLabs, OPINION_ID, 20.0, -10.0);
+        assert!(hedger.check_hedge(POLY_ID, 1_000, 0).is_some());
+
+        // 在途期间不重复发单
+        assert!(hedger.check_hedge(POLY_ID, 1_000 + 2_999, 0).is_none());
+        // IOC 只成交了 5 份，超时后按剩余敞口重发
+        hedger.on_fill(Exchange::Polymarket, POLY_ID, -5.0, 2.5);
+        assert!(hedger.check_hedge(POLY_ID, 1_000 + 2_999, 0).is_none());
+        assert!(hedger.check_hedge(POLY_ID, 1_000 + 3_000, 0).is_none(), "15 shares left is below the threshold");
+
+        hedger.on_fill(Exchange::OpinionLabs, OPINION_ID, 10.0, -5.0);
+        let retry = hedger.check_hedge(POLY_ID, 1_000 + 3_000, 0).unwrap();
+        assert_eq!(retry.size_usd, dec!(25) * dec!(0.48));
+    }
+}
@@ -1,2 +1,7 @@
+pub mod strategy;
 pub mod as_logic;
-pub mod risk;
\ No newline at end of file
+pub mod glft;
+pub mod grid;
+pub mod risk;
+pub mod hedger;
+pub mod portfolio;
@@ -0,0 +1,144 @@
+// File: src/model/portfolio.rs
+// 多市场组合：每个市场一个独立的报价模型实例 (独立的波动率窗口、库存和账本)
+// 组合层负责 ID 路由和风险汇总
+use std::collections::HashMap;
+use crate::model::strategy::Strategy;
+
+/// 组合层汇总 (用于风控与日志)
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PortfolioSummary {
+    pub markets: usize,
+    pub net_inventory_shares: f64,   // 所有市场库存的代数和
+    pub gross_inventory_shares: f64, // Σ |库存|
+    pub gross_exposure_usd: f64,     // Σ |库存 × 最新中间价|
+    pub cash_balance: f64,
+    pub equity: f64,                 // Σ (现金 + 库存 × 最新中间价)
+}
+
+pub struct Portfolio {
+    // 参考行情 symbol_id (Polymarket) -> 策略实例
+    strategies: HashMap<u64, Box<dyn Strategy>>,
+    // Opinion Market ID -> 参考行情 symbol_id (成交回报路由)
+    opinion_to_ref: HashMap<u64, u64>,
+    // 参考行情 symbol_id -> 最新中间价 (Mark-to-Market)
+    last_mid: HashMap<u64, f64>,
+}
+
+impl Portfolio {
+    pub fn new() -> Self {
+        Self {
+            strategies: HashMap::new(),
+            opinion_to_ref: HashMap::new(),
+            last_mid: HashMap::new(),
+        }
+    }
+
+    pub fn add_market(&mut self, ref_symbol_id: u64, strategy: Box<dyn Strategy>) {
+        self.opinion_to_ref.insert(strategy.market_id(), ref_symbol_id);
+        self.strategies.insert(ref_symbol_id, strategy);
+    }
+
+    /// 按参考行情 ID 查找策略 (行情回调)
+    pub fn by_ref_mut(&mut self, ref_symbol_id: u64) -> Option<&mut dyn Strategy> {
+        Some(self.strategies.get_mut(&ref_symbol_id)?.as_mut())
+    }
+
+    /// 按 Opinion Market ID 查找策略 (成交回调)
+    pub fn by_opinion_mut(&mut self, opinion_market_id: u64) -> Option<&mut dyn Strategy> {
+        let ref_id = self.opinion_to_ref.get(&opinion_market_id)?;
+        Some(self.strategies.get_mut(ref_id)?.as_mut())
+    }
+
+    pub fn by_opinion(&self, opinion_market_id: u64) -> Option<&dyn Strategy> {
+        let ref_id = self.opinion_to_ref.get(&opinion_market_id)?;
+        Some(self.strategies.get(ref_id)?.as_ref())
+    }
+
+    /// 所有市场的 Opinion Market ID
+    pub fn market_ids(&self) -> Vec<u64> {
+        self.opinion_to_ref.keys().copied().collect()
+    }
+
+    /// Opinion Market ID -> 参考行情 ID 的映射 (对冲器需要同一套事件主键)
+    pub fn opinion_pairs(&self) -> HashMap<u64, u64> {
+        self.opinion_to_ref.clone()
+    }
+
+    /// 记录最新中间价
+    pub fn mark(&mut self, ref_symbol_id: u64, mid: f64) {
+        self.last_mid.insert(ref_symbol_id, mid);
+    }
+
+    /// 单个市场的 (持仓份额, 最新中间价)；还没有行情时中间价为 0
+    pub fn position(&self, opinion_market_id: u64) -> Option<(f64, f64)> {
+        let ref_id = self.opinion_to_ref.get(&opinion_market_id)?;
+        let shares = self.strategies.get(ref_id)?.ledger().current_inventory_shares;
+        Some((shares, self.last_mid.get(ref_id).copied().unwrap_or(0.0)))
+    }
+
+    pub fn summary(&self) -> PortfolioSummary {
+        let mut s = PortfolioSummary { markets: self.strategies.len(), ..Default::default() };
+        for (ref_id, strat) in &self.strategies {
+            let mid = self.last_mid.get(ref_id).copied().unwrap_or(0.0);
+            let ledger = strat.ledger();
+            let position_value = ledger.current_inventory_shares * mid;
+
+            s.net_inventory_shares += ledger.current_inventory_shares;
+            s.gross_inventory_shares += ledger.current_inventory_shares.abs();
+            s.gross_exposure_usd += position_value.abs();
+            s.cash_balance += ledger.current_cash_balance;
+            s.equity += ledger.current_cash_balance + position_value;
+        }
+        s
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::clock::SimClock;
+    use crate::model::strategy::{build_strategy, StrategyConfig};
+    use crate::venues::rules::MarketRules;
+    use std::sync::Arc;
+
+    // 两个市场：Opinion 7 <- 参考 100，Opinion 8 <- 参考 200
+    fn portfolio() -> Portfolio {
+        let mut p = Portfolio::new();
+        for (opinion_id, ref_id) in [(7, 100), (8, 200)] {
+            let strategy = build_strategy(opinion_id, StrategyConfig::default(), MarketRules::default(), None, Arc::new(SimClock::default()));
+            p.add_market(ref_id, strategy);
+        }
+        p
+    }
+
+    #[test]
+    fn markets_are_found_by_reference_id_and_by_opinion_id() {
+        let mut p = portfolio();
+        assert_eq!(p.by_ref_mut(100).map(|s| s.market_id()), Some(7));
+        assert_eq!(p.by_opinion_mut(8).map(|s| s.market_id()), Some(8));
+        assert_eq!(p.by_opinion(7).map(|s| s.market_id()), Some(7));
+        // 两套 ID 不能混用
+        assert!(p.by_ref_mut(7).is_none());
+        assert!(p.by_opinion_mut(200).is_none());
+        assert_eq!(p.opinion_pairs(), HashMap::from([(7, 100), (8, 200)]));
+    }
+
+    #[test]
+    fn fills_routed_by_opinion_id_are_marked_at_the_reference_mid() {
+        let mut p = portfolio();
+        // 内置模型成交后只记账，不立即改价
+        assert!(p.by_opinion_mut(7).unwrap().on_fill(10.0, -5.0).is_none());
+        assert!(p.by_opinion_mut(8).unwrap().on_fill(-4.0, 2.0).is_none());
+        p.mark(100, 0.6);
+        p.mark(200, 0.25);
+
+        assert_eq!(p.position(7), Some((10.0, 0.6)));
+        assert_eq!(p.position(100), None);
+
+        let s = p.summary();
+        assert_eq!(s.markets, 2);
+        assert_eq!((s.net_inventory_shares, s.gross_inventory_shares), (6.0, 14.0));
+        assert!((s.gross_exposure_usd - 7.0).abs() < 1e-9);
+        assert!((s.equity - 2.0).abs() < 1e-9);
+    }
+}
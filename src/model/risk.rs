@@ -1,7 +1,14 @@
 // File: src/model/risk.rs
-use crate::core::{Side, TradeSignal};
+use crate::core::{Exchange, Side, TradeSignal, LOGIC_TAG_QUOTE};
 use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
 use rust_decimal_macros::dec;
+use tracing::{error, warn};
+
+use crate::config::RiskConfig;
+use crate::infrastructure::clock::SharedClock;
+use crate::model::portfolio::Portfolio;
+use crate::infrastructure::metrics::{exchange_label, DRAWDOWN, KILL_SWITCH, SIGNALS_ACCEPTED, SIGNALS_REJECTED, TOTAL_PNL};
 
 pub struct RiskManager {
     // --- 硬参数 (Hard Limits) ---
@@ -9,56 +16,146 @@ pub struct RiskManager {
     pub max_order_size_usd: f64,  // 单笔最大金额 (肥手指保护)
     pub stop_loss_price_floor: Decimal, // 价格下限保护
     pub stop_loss_price_ceiling: Decimal, // 价格上限保护
+    pub max_position_shares: f64,  // 单市场持仓上限 (份额，0 = 不限)
+    pub max_position_usd: f64,     // 单市场持仓市值上限 (0 = 不限)
+    pub max_portfolio_shares: f64, // 组合持仓上限 Σ|份额| (0 = 不限)
+    pub max_portfolio_usd: f64,    // 组合持仓市值上限 Σ|市值| (0 = 不限)
+    pub soft_limit_ratio: f64,     // 软上限 = 硬上限 x 该比例
 
     // --- 运行时状态 ---
     pub total_pnl: f64,          // 累计盈亏
     pub peak_equity_pnl: f64,    // 历史最高盈亏水位 (用于计算回撤)
     pub current_drawdown: f64,   // 当前回撤值
     pub is_kill_switch_active: bool, // 是否熔断
+    pub kill_switch_at_ns: Option<i64>, // 熔断时刻
+
+    clock: SharedClock,
 }
 
 impl RiskManager {
-    pub fn new(max_drawdown: f64, max_order: f64) -> Self {
+    pub fn new(cfg: &RiskConfig, clock: SharedClock) -> Self {
         Self {
-            max_drawdown_usd: max_drawdown,
-            max_order_size_usd: max_order,
+            max_drawdown_usd: cfg.max_drawdown_usd,
+            max_order_size_usd: cfg.max_order_size_usd,
             stop_loss_price_floor: dec!(0.02),
             stop_loss_price_ceiling: dec!(0.98),
-            
+            max_position_shares: cfg.max_position_shares,
+            max_position_usd: cfg.max_position_usd,
+            max_portfolio_shares: cfg.max_portfolio_shares,
+            max_portfolio_usd: cfg.max_portfolio_usd,
+            soft_limit_ratio: cfg.soft_limit_ratio,
+
             total_pnl: 0.0,
             peak_equity_pnl: 0.0,
             current_drawdown: 0.0,
             is_kill_switch_active: false,
+            kill_switch_at_ns: None,
+            clock,
         }
     }
 
+    /// [热更新] 调整硬参数，运行时状态 (PnL 水位、熔断状态) 保持不变
+    pub fn set_limits(&mut self, cfg: &RiskConfig) {
+        self.max_drawdown_usd = cfg.max_drawdown_usd;
+        self.max_order_size_usd = cfg.max_order_size_usd;
+        self.max_position_shares = cfg.max_position_shares;
+        self.max_position_usd = cfg.max_position_usd;
+        self.max_portfolio_shares = cfg.max_portfolio_shares;
+        self.max_portfolio_usd = cfg.max_portfolio_usd;
+        self.soft_limit_ratio = cfg.soft_limit_ratio;
+    }
+
+    /// [软上限] 单市场或组合持仓达到 硬上限 x soft_limit_ratio：该市场只报减仓一侧
+    /// 持仓与中间价直接读组合层 (Portfolio)，不另存一份
+    pub fn reduce_only(&self, portfolio: &Portfolio, market_id: u64) -> bool {
+        let (shares, mid) = portfolio.position(market_id).unwrap_or((0.0, 0.0));
+        let summary = portfolio.summary();
+        let ratio = self.soft_limit_ratio;
+        at_limit(shares.abs(), self.max_position_shares, ratio)
+            || at_limit(shares.abs() * mid, self.max_position_usd, ratio)
+            || at_limit(summary.gross_inventory_shares, self.max_portfolio_shares, ratio)
+            || at_limit(summary.gross_exposure_usd, self.max_portfolio_usd, ratio)
+    }
+
+    // [硬上限] 同一方向已承诺的报价 (committed_shares，带符号) 加上这个信号全部成交后，
+    // 持仓的绝对值变大且超过任一硬上限
+    fn breaches_position_limit(&self, portfolio: &Portfolio, signal: &TradeSignal, committed_shares: f64) -> bool {
+        let price = signal.price.to_f64().unwrap_or(0.0);
+        if price <= 0.0 {
+            return false;
+        }
+        let (shares, mid) = portfolio.position(signal.symbol_id).unwrap_or((0.0, price));
+        let mark = if mid > 0.0 { mid } else { price };
+        let delta = signal.size_usd.to_f64().unwrap_or(0.0) / price;
+        let base = shares + committed_shares;
+        let after = match signal.side {
+            Side::Buy => base + delta,
+            Side::Sell => base - delta,
+        };
+        if after.abs() <= base.abs() {
+            return false; // 减仓永远放行
+        }
+
+        let summary = portfolio.summary();
+        let added = after.abs() - shares.abs();
+        above_limit(after.abs(), self.max_position_shares)
+            || above_limit(after.abs() * mark, self.max_position_usd)
+            || above_limit(summary.gross_inventory_shares + added, self.max_portfolio_shares)
+            || above_limit(summary.gross_exposure_usd + added * mark, self.max_portfolio_usd)
+    }
+
     /// [检查 1] 信号合规性检查 (Pre-Trade Check)
     /// 如果返回 false，Engine 必须丢弃该信号
     pub fn check_signal(&self, signal: &TradeSignal) -> bool {
+        self.check(signal, None)
+    }
+
+    /// [检查 1b] 报价信号：在 check_signal 之外再检查持仓硬上限
+    /// committed_shares 为同一市场、同一方向上已通过审查的其他档位 (全部成交时的带符号份额)，
+    /// 整个阶梯一起计入，避免每档单独放行、合起来超限
+    pub fn check_quote(&self, signal: &TradeSignal, portfolio: &Portfolio, committed_shares: f64) -> bool {
+        self.check(signal, Some((portfolio, committed_shares)))
+    }
+
+    fn check(&self, signal: &TradeSignal, position: Option<(&Portfolio, f64)>) -> bool {
+        let exchange = exchange_label(signal.target_exchange);
+        let reject = |reason: &str| {
+            SIGNALS_REJECTED.with_label_values(&[exchange, reason]).inc();
+            false
+        };
+
         // 1. 熔断状态检查
         if self.is_kill_switch_active {
             // 只有撤单信号(逻辑一般不在这里处理)或者特殊平仓单可以通过
             // 但为了安全，熔断后拒绝一切新开仓
-            return false; 
+            return reject("kill_switch");
         }
 
         // 2. 肥手指检查
         let size_f64 = signal.size_usd.try_into().unwrap_or(0.0);
         if size_f64 > self.max_order_size_usd {
-            eprintln!("🛡️ [RISK REJECT] Order size ${:.2} > Max ${:.2}", size_f64, self.max_order_size_usd);
-            return false;
+            warn!(size_usd = size_f64, max_usd = self.max_order_size_usd, "🛡️ Risk reject: order size above limit");
+            return reject("order_size");
         }
 
         // 3. 价格异常检查 (防止预言机攻击或数据错误导致报出离谱价格)
         if signal.side == Side::Buy && signal.price > self.stop_loss_price_ceiling {
-            eprintln!("🛡️ [RISK REJECT] Buying above ceiling: {}", signal.price);
-            return false;
+            warn!(price = %signal.price, ceiling = %self.stop_loss_price_ceiling, "🛡️ Risk reject: buying above ceiling");
+            return reject("price_ceiling");
         }
         if signal.side == Side::Sell && signal.price < self.stop_loss_price_floor {
-            eprintln!("🛡️ [RISK REJECT] Selling below floor: {}", signal.price);
-            return false;
+            warn!(price = %signal.price, floor = %self.stop_loss_price_floor, "🛡️ Risk reject: selling below floor");
+            return reject("price_floor");
+        }
+
+        // 4. 持仓硬上限：拒绝任何会继续加仓的报价 (对冲单本身是在降低敞口)
+        let breaches = |(portfolio, committed)| self.breaches_position_limit(portfolio, signal, committed);
+        if signal.logic_tag == LOGIC_TAG_QUOTE && signal.target_exchange == Exchange::OpinionLabs && position.is_some_and(breaches) {
+            warn!(market_id = signal.symbol_id, side = ?signal.side, size_usd = %signal.size_usd, "🛡️ Risk reject: position limit");
+            return reject("position_limit");
         }
 
+        SIGNALS_ACCEPTED.with_label_values(&[exchange]).inc();
         true
     }
 
@@ -80,17 +177,117 @@ impl RiskManager {
             // 回撤 = 最高点 - 当前点
             self.current_drawdown = self.peak_equity_pnl - self.total_pnl;
         }
+        TOTAL_PNL.set(self.total_pnl);
+        DRAWDOWN.set(self.current_drawdown);
 
         // 检查阈值
         if self.current_drawdown > self.max_drawdown_usd {
             self.is_kill_switch_active = true;
-            eprintln!("\n🚨🚨🚨 [KILL SWITCH TRIGGERED] 🚨🚨🚨");
-            eprintln!("Reason: Max Drawdown Exceeded");
-            eprintln!("Current Drawdown: ${:.4} (Limit: ${:.2})", self.current_drawdown, self.max_drawdown_usd);
-            eprintln!("Total PnL: ${:.4}", self.total_pnl);
+            self.kill_switch_at_ns = Some(self.clock.now_ns());
+            KILL_SWITCH.set(1);
+            error!(
+                reason = "max_drawdown_exceeded",
+                drawdown_usd = self.current_drawdown,
+                limit_usd = self.max_drawdown_usd,
+                total_pnl = self.total_pnl,
+                "🚨🚨🚨 KILL SWITCH TRIGGERED 🚨🚨🚨"
+            );
             return true;
         }
 
         false
     }
-}
\ No newline at end of file
+}
+
+// 限额为 0 表示不限制
+fn at_limit(value: f64, limit: f64, ratio: f64) -> bool {
+    limit > 0.0 && value >= limit * ratio
+}
+
+fn above_limit(value: f64, limit: f64) -> bool {
+    limit > 0.0 && value > limit
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::StageStamps;
+    use crate::infrastructure::clock::SimClock;
+    use crate::model::strategy::{build_strategy, StrategyConfig};
+    use crate::venues::rules::MarketRules;
+    use std::sync::Arc;
+
+    fn manager(cfg: RiskConfig) -> RiskManager {
+        RiskManager::new(&cfg, Arc::new(SimClock::default()))
+    }
+
+    fn quote(market_id: u64, side: Side, price: Decimal, size_usd: Decimal) -> TradeSignal {
+        TradeSignal {
+            strategy_id: 1,
+            target_exchange: Exchange::OpinionLabs,
+            symbol_id: market_id,
+            side,
+            price,
+            size_usd,
+            logic_tag: LOGIC_TAG_QUOTE,
+            post_only: false,
+            level: 0,
+            created_at_ns: 0,
+            trace_id: 0,
+            stamps: StageStamps::default(),
+        }
+    }
+
+    // 每个市场 (Opinion Market ID) 一个策略，参考行情 ID = Opinion ID + 100
+    fn portfolio(positions: &[(u64, f64, f64)]) -> Portfolio {
+        let mut p = Portfolio::new();
+        for &(market_id, shares, mid) in positions {
+            let mut strategy = build_strategy(market_id, StrategyConfig::default(), MarketRules::default(), None, Arc::new(SimClock::default()));
+            strategy.on_fill(shares, 0.0);
+            p.add_market(market_id + 100, strategy);
+            p.mark(market_id + 100, mid);
+        }
+        p
+    }
+
+    #[test]
+    fn soft_limit_switches_market_to_reduce_only() {
+        let risk = manager(RiskConfig { max_position_shares: 100.0, ..Default::default() });
+        assert!(!risk.reduce_only(&portfolio(&[(1, 79.0, 0.5)]), 1));
+        assert!(risk.reduce_only(&portfolio(&[(1, -80.0, 0.5)]), 1));
+    }
+
+    #[test]
+    fn hard_limit_rejects_only_signals_that_add_to_the_position() {
+        let risk = manager(RiskConfig { max_position_usd: 100.0, ..Default::default() });
+        let p = portfolio(&[(1, 180.0, 0.5)]); // 90 U
+        // 再买 20 U (40 份) -> 110 U，超过上限
+        assert!(!risk.check_quote(&quote(1, Side::Buy, dec!(0.5), dec!(20)), &p, 0.0));
+        assert!(risk.check_quote(&quote(1, Side::Buy, dec!(0.5), dec!(5)), &p, 0.0));
+        assert!(risk.check_quote(&quote(1, Side::Sell, dec!(0.5), dec!(50)), &p, 0.0));
+        // 不带持仓视图的检查 (对冲单) 不看持仓上限
+        assert!(risk.check_signal(&quote(1, Side::Buy, dec!(0.5), dec!(20))));
+    }
+
+    #[test]
+    fn ladder_levels_on_the_same_side_count_together() {
+        let risk = manager(RiskConfig { max_position_usd: 100.0, ..Default::default() });
+        let p = portfolio(&[(1, 100.0, 0.5)]); // 50 U
+        let level = quote(1, Side::Buy, dec!(0.5), dec!(30));
+        // 单独一档 (+30 U) 放行；前一档已承诺 60 份 (30 U) 时合计 110 U，拒绝
+        assert!(risk.check_quote(&level, &p, 0.0));
+        assert!(!risk.check_quote(&level, &p, 60.0));
+        // 另一侧的承诺在减仓，不占用加仓额度
+        assert!(risk.check_quote(&quote(1, Side::Sell, dec!(0.5), dec!(30)), &p, -60.0));
+    }
+
+    #[test]
+    fn portfolio_limit_counts_all_markets() {
+        let risk = manager(RiskConfig { max_position_usd: 0.0, max_portfolio_usd: 100.0, ..Default::default() });
+        // 50 U + 40 U (空头也计入总敞口)，市场 3 空仓
+        let p = portfolio(&[(1, 100.0, 0.5), (2, -80.0, 0.5), (3, 0.0, 0.5)]);
+        assert!(risk.reduce_only(&p, 3)); // 90 U >= 80 U 软上限，空仓的市场也不再加仓
+        assert!(!risk.check_quote(&quote(3, Side::Buy, dec!(0.5), dec!(20)), &p, 0.0));
+        assert!(risk.check_quote(&quote(2, Side::Buy, dec!(0.5), dec!(20)), &p, 0.0));
+    }
+}
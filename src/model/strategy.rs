@@ -0,0 +1,361 @@
+// File: src/model/strategy.rs
+// 可插拔的报价模型：引擎只依赖 Strategy trait，具体模型由 [strategy] model 选择
+//   - as   : Avellaneda-Stoikov (as_logic.rs)
+//   - glft : Guéant-Lehalle-Fernandez-Tapia 闭式近似 (glft.rs)
+//   - grid : 中间价两侧固定价差的网格 (grid.rs)，作为对照基准
+// 账本 (库存 / 现金 / 盯市 / 持久化)、下单金额和报价阶梯对所有模型通用，放在这里
+use rust_decimal::Decimal;
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal_macros::dec;
+use serde::{Serialize, Deserialize};
+use std::sync::mpsc::Sender;
+use tracing::info;
+
+use crate::core::Side;
+use crate::infrastructure::clock::SharedClock;
+use crate::model::as_logic::OpinionGridStrategy;
+use crate::model::glft::GlftStrategy;
+use crate::model::grid::GridStrategy;
+use crate::venues::rules::MarketRules;
+
+// --- 配置部分 ---
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StrategyModel {
+    #[default]
+    As,
+    Glft,
+    Grid,
+}
+
+/// 保留价与价差的计算口径 (目前只有 as 模型支持 logit)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PriceSpace {
+    #[default]
+    Linear, // 直接在价格上加减
+    Logit,  // 在对数几率空间里计算，再映射回概率
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StrategyConfig {
+    pub model: StrategyModel,
+    pub price_space: PriceSpace,
+    pub risk_aversion_gamma: f64,
+    pub liquidity_k: f64,
+    pub min_spread_bps: u32,
+    pub tick_size: f64,
+    pub max_inventory_usd: f64,
+    // 下单金额
+    pub size_inventory_skew: f64,
+    pub size_vol_target: f64,
+    // 报价阶梯
+    pub ladder_levels: u32,
+    pub ladder_step_ticks: u32,
+    pub ladder_step_vol_mult: f64,
+    pub ladder_size_decay: f64,
+    // 模型专用
+    pub glft_arrival_a: f64,
+    pub grid_half_spread_ticks: u32,
+    // 时间相关
+    pub maturity_timestamp_ms: i64,
+    pub terminal_dumping_factor: f64,
+    pub closing_window_seconds: i64,
+}
+
+impl Default for StrategyConfig {
+    // 手工参数配置 (单个市场未单独配置时使用)
+    fn default() -> Self {
+        Self {
+            model: StrategyModel::As,  // 报价模型 (改模型需要重启)
+            price_space: PriceSpace::Linear, // 报价口径：linear | logit (靠近 0 / 1 的市场建议 logit)
+            risk_aversion_gamma: 0.05, // 风险厌恶系数
+            liquidity_k: 5000.0,       // 市场流动性估算
+            min_spread_bps: 50,        // 最小价差 0.5% (覆盖 Gas 和 手续费)
+            tick_size: 0.01,           // 价格最小跳动单位
+            max_inventory_usd: 2000.0, // 库存归一化基准 (只用于金额倾斜计算)；真正的持仓限额在 [risk]
+
+            // 下单金额 (Part 2)
+            size_inventory_skew: 1.0, // 金额随库存倾斜的力度：加仓侧 x (1 - skew * 库存占比)，减仓侧对称放大
+            size_vol_target: 0.02,    // 波动率高于该值时两侧金额按 target / sigma 缩小 (0 = 不按波动率缩放)
+
+            // 报价阶梯：每边 N 档，档距取 "tick 倍数" 与 "波动率 x 中间价 x 系数" 中较大者
+            ladder_levels: 1,           // 每边档数 (1 = 只报最优档)
+            ladder_step_ticks: 1,       // 相邻两档至少相隔几个 tick
+            ladder_step_vol_mult: 0.0,  // 波动率档距系数 (0 = 只按 tick)
+            ladder_size_decay: 0.5,     // 每往外一档，金额乘以该系数
+
+            // 模型专用参数
+            glft_arrival_a: 140.0,      // GLFT: 成交强度 A (λ(δ) = A·e^(-kδ))
+            grid_half_spread_ticks: 2,  // 网格: 中间价两侧各让出几个 tick
+
+            // 时间相关参数 (Part 3)
+            // 请替换为真实的市场结束时间戳 (毫秒)
+            maturity_timestamp_ms: 1735689599000,
+            terminal_dumping_factor: 10.0, // 临近结束时风险厌恶翻 10 倍
+            closing_window_seconds: 3600,  // 最后 1 小时进入清仓模式
+        }
+    }
+}
+
+/// 报价阶梯中的一档
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct QuoteLevel {
+    pub price: Decimal,
+    pub size_usd: Decimal,
+}
+
+/// 报价意图：双边报价阶梯，第 0 档离保留价最近 (某一边为空表示该边不报 / 撤掉)
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Quote {
+    pub bids: Vec<QuoteLevel>,
+    pub asks: Vec<QuoteLevel>,
+}
+
+impl Quote {
+    /// [单边报价] 只保留减仓一侧 (空仓时两侧都会加仓，全部撤掉)
+    pub fn reduce_only(&mut self, inventory_shares: f64) {
+        if inventory_shares >= 0.0 {
+            self.bids.clear();
+        }
+        if inventory_shares <= 0.0 {
+            self.asks.clear();
+        }
+    }
+
+    /// [不穿价] 把报价限制在对手盘口以内 (买价 < 卖一，卖价 > 买一)，避免吃单付手续费
+    /// 被截到同一价格的档位合并成一档 (金额相加，总报价量不变)；返回是否有报价被调整
+    pub fn keep_passive(&mut self, best_bid: Option<Decimal>, best_ask: Option<Decimal>, rules: &MarketRules) -> bool {
+        let mut clamped = false;
+        for (levels, touch, side) in [(&mut self.bids, best_ask, Side::Buy), (&mut self.asks, best_bid, Side::Sell)] {
+            let Some(touch) = touch else { continue };
+            let limit = rules.passive_limit(touch, side);
+            let crosses = |price: Decimal| match side {
+                Side::Buy => price >= touch,
+                Side::Sell => price <= touch,
+            };
+            for l in levels.iter_mut().filter(|l| crosses(l.price)) {
+                l.price = limit;
+                clamped = true;
+            }
+            // 对手价贴着价格边界时截断后仍然穿价：这一边不报
+            levels.retain(|l| !crosses(l.price));
+            levels.dedup_by(|outer, inner| {
+                let merge = outer.price == inner.price;
+                if merge {
+                    inner.size_usd += outer.size_usd;
+                }
+                merge
+            });
+        }
+        clamped
+    }
+}
+
+// --- 持久化状态结构 (写入磁盘的内容，每个市场一条) ---
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PersistState {
+    pub market_id: u64, // Opinion Market ID
+    pub inventory_shares: f64,
+    pub cash_balance: f64, // 账户里的现金余额 (Realized PnL 累积)
+    pub timestamp: i64,
+}
+
+/// 单个市场的账本：所有模型共用
+pub struct Ledger {
+    market_id: u64, // Opinion Market ID (报价与成交所在的市场)
+
+    // 核心状态
+    pub current_inventory_shares: f64,
+    pub current_cash_balance: f64, // 内存中的现金余额
+
+    // 辅助状态：用于计算权益变动
+    last_equity_mark: Option<f64>,
+
+    // IO 通道
+    persist_sender: Option<Sender<PersistState>>,
+
+    // 时间来源 (实盘 / 回测 / 回放)
+    clock: SharedClock,
+}
+
+impl Ledger {
+    pub fn new(market_id: u64, sender: Option<Sender<PersistState>>, clock: SharedClock) -> Self {
+        Self {
+            market_id,
+            current_inventory_shares: 0.0,
+            current_cash_balance: 0.0, // 初始为 0，等待 restore
+            last_equity_mark: None,
+            persist_sender: sender,
+            clock,
+        }
+    }
+
+    pub fn market_id(&self) -> u64 {
+        self.market_id
+    }
+
+    pub fn now_ms(&self) -> i64 {
+        self.clock.now_ms()
+    }
+
+    /// [系统启动时调用] 恢复之前的账本
+    pub fn restore_state(&mut self, saved_inv: f64, saved_cash: f64) {
+        self.current_inventory_shares = saved_inv;
+        self.current_cash_balance = saved_cash;
+        info!(market_id = self.market_id, inventory = saved_inv, cash = saved_cash, "♻️ State restored");
+    }
+
+    /// [成交回调] 更新库存和现金，并触发异步写入
+    pub fn on_fill(&mut self, change_shares: f64, net_cash_flow: f64) {
+        self.current_inventory_shares += change_shares;
+        self.current_cash_balance += net_cash_flow;
+
+        // ⚡️ 异步 IO：状态存盘
+        if let Some(tx) = &self.persist_sender {
+            // 这里我们忽略 send 错误，因为在极高频下如果 channel 满了，我们选择丢弃旧状态
+            // 但对于资金状态，最好保证 buffer 足够大
+            let _ = tx.send(PersistState {
+                market_id: self.market_id,
+                inventory_shares: self.current_inventory_shares,
+                cash_balance: self.current_cash_balance,
+                timestamp: self.clock.now_ms() / 1000,
+            });
+        }
+    }
+
+    /// [核心风控计算] 计算权益变动 (Mark-to-Market PnL)
+    /// 公式：Total Equity = Cash + (Inventory * MidPrice)
+    /// 返回值：PnL Change (相对于上一次计算的变动值)
+    pub fn calculate_equity_change(&mut self, current_mid_price: f64) -> f64 {
+        let position_value = self.current_inventory_shares * current_mid_price;
+        let current_equity = self.current_cash_balance + position_value;
+
+        // 启动 (或恢复状态) 后的第一次计算只建立基准值，不产生 PnL 跳变
+        // 用 None 而不是 0.0 表示未建立：基准权益恰好为 0 时 (空仓启动)，第一笔成交的盈亏不能被当成校准吞掉
+        let pnl_change = match self.last_equity_mark {
+            Some(mark) => current_equity - mark,
+            None => 0.0,
+        };
+
+        // 更新水位线
+        self.last_equity_mark = Some(current_equity);
+
+        pnl_change
+    }
+}
+
+/// 报价模型：引擎 / 回测只通过这些回调驱动策略，回调返回报价意图
+pub trait Strategy: Send {
+    fn ledger(&self) -> &Ledger;
+    fn ledger_mut(&mut self) -> &mut Ledger;
+    fn config(&self) -> &StrategyConfig;
+
+    /// [热更新] 替换策略参数，保留波动率窗口与账本状态
+    fn set_config(&mut self, cfg: StrategyConfig);
+
+    /// [行情回调] 参考中间价更新；base_size_usd 为该市场配置的单笔下单金额
+    fn on_book(&mut self, mid: Decimal, base_size_usd: Decimal) -> Quote;
+
+    /// [成交回调] Some = 成交后立即用新的报价意图替换当前挂单 (不必等下一次行情)
+    /// 默认：只记账，下一次行情再按新库存报价
+    fn on_fill(&mut self, change_shares: f64, net_cash_flow: f64) -> Option<Quote> {
+        self.ledger_mut().on_fill(change_shares, net_cash_flow);
+        None
+    }
+
+    /// [定时回调] 行情静默时也会被调用；Some = 用新的报价意图替换当前挂单
+    /// 默认：市场到期后撤掉全部报价
+    fn on_timer(&mut self, now_ms: i64) -> Option<Quote> {
+        (now_ms >= self.config().maturity_timestamp_ms).then(Quote::default)
+    }
+
+    /// [退出回调] 引擎停止前最后一次报价意图 (默认全部撤掉)
+    fn on_shutdown(&mut self) -> Quote {
+        Quote::default()
+    }
+
+    fn market_id(&self) -> u64 {
+        self.ledger().market_id()
+    }
+}
+
+/// 按 cfg.model 创建报价模型
+/// rules 为报价市场的交易规则 (tick 表、最小下单量)
+pub fn build_strategy(market_id: u64, cfg: StrategyConfig, rules: MarketRules, sender: Option<Sender<PersistState>>, clock: SharedClock) -> Box<dyn Strategy> {
+    let ledger = Ledger::new(market_id, sender, clock);
+    match cfg.model {
+        StrategyModel::As => Box::new(OpinionGridStrategy::new(cfg, rules, ledger)),
+        StrategyModel::Glft => Box::new(GlftStrategy::new(cfg, rules, ledger)),
+        StrategyModel::Grid => Box::new(GridStrategy::new(cfg, rules, ledger)),
+    }
+}
+
+/// [报价阶梯] 由第 0 档的原始买卖价生成双边阶梯 (下单金额按库存 / 波动率 / 剩余时间调整)
+/// 原始价格只在这里转换一次成 Decimal，之后全部按市场的 tick 表精确取整：买价向下、卖价向上
+pub fn quote_ladders(cfg: &StrategyConfig, rules: &MarketRules, inventory_shares: f64, (raw_bid, raw_ask): (f64, f64), base_size_usd: Decimal, (mid, sigma): (f64, f64), time_left_ms: i64) -> Quote {
+    let rules = rules.with_default_tick(cfg.tick_size);
+    let (bid_size_usd, ask_size_usd) = order_sizes(cfg, inventory_shares, base_size_usd, mid, sigma, time_left_ms);
+    let vol_step = Decimal::from_f64(cfg.ladder_step_vol_mult * sigma * mid).unwrap_or(dec!(0)).round_dp(8);
+    Quote {
+        bids: ladder(cfg, &rules, Side::Buy, raw_bid, vol_step, bid_size_usd),
+        asks: ladder(cfg, &rules, Side::Sell, raw_ask, vol_step, ask_size_usd),
+    }
+}
+
+// 从第 0 档向外逐档移动 max(ladder_step_ticks 个当前价位的 tick, vol_step)，金额按 ladder_size_decay 逐档衰减
+fn ladder(cfg: &StrategyConfig, rules: &MarketRules, side: Side, first_price: f64, vol_step: Decimal, first_size_usd: Decimal) -> Vec<QuoteLevel> {
+    let mut levels: Vec<QuoteLevel> = Vec::new();
+    // 先截到 8 位小数去掉 f64 尾差 (0.46999999999999997 不应该被向下取整成 0.46)
+    let Some(mut raw) = Decimal::from_f64(first_price).map(|p| p.round_dp(8)) else { return levels };
+    for i in 0..cfg.ladder_levels {
+        let price = rules.round(raw, side);
+        let decay = Decimal::from_f64_retain(cfg.ladder_size_decay.powi(i as i32)).unwrap_or(dec!(0));
+        let size_usd = (first_size_usd * decay).round_dp(2);
+        // 金额衰减到 0 / 不足最小下单量，或者价格被边界截断后和上一档重合：更外侧的档位都没有意义
+        if size_usd.is_zero() || !rules.meets_min_size(price, size_usd) || levels.last().is_some_and(|l| l.price == price) {
+            break;
+        }
+        levels.push(QuoteLevel { price, size_usd });
+        let step = (rules.tick_at(price) * Decimal::from(cfg.ladder_step_ticks)).max(vol_step);
+        raw = match side {
+            Side::Buy => price - step,
+            Side::Sell => price + step,
+        };
+    }
+    levels
+}
+
+/// [下单金额] 库存感知：加仓一侧随库存占比收缩、减仓一侧放大；波动率高时整体缩小；
+/// 清仓窗口内加仓一侧随剩余时间线性收缩到 0
+fn order_sizes(cfg: &StrategyConfig, inventory_shares: f64, base_size_usd: Decimal, mid: f64, sigma: f64, time_left_ms: i64) -> (Decimal, Decimal) {
+    let base = base_size_usd.to_f64().unwrap_or(0.0);
+
+    // 库存占比 u ∈ [-1, 1] (多头为正)
+    let inventory_ratio = (inventory_shares * mid / cfg.max_inventory_usd).clamp(-1.0, 1.0);
+    let skew = cfg.size_inventory_skew * inventory_ratio;
+    let mut bid_mult = (1.0 - skew).max(0.0);
+    let mut ask_mult = (1.0 + skew).max(0.0);
+
+    // 波动率缩放 (只缩小不放大)
+    let vol_mult = if cfg.size_vol_target > 0.0 && sigma > cfg.size_vol_target {
+        cfg.size_vol_target / sigma
+    } else {
+        1.0
+    };
+
+    // 清仓窗口：加仓一侧 (空仓时两侧都算) 随剩余时间收缩
+    let window_ms = cfg.closing_window_seconds * 1000;
+    if time_left_ms < window_ms {
+        let time_mult = time_left_ms as f64 / window_ms as f64;
+        if inventory_shares >= 0.0 {
+            bid_mult *= time_mult;
+        }
+        if inventory_shares <= 0.0 {
+            ask_mult *= time_mult;
+        }
+    }
+
+    let to_usd = |mult: f64| Decimal::from_f64_retain(base * mult * vol_mult).unwrap_or(dec!(0)).round_dp(2);
+    (to_usd(bid_mult), to_usd(ask_mult))
+}
@@ -0,0 +1,251 @@
+// File: src/reload.rs
+// 运行时参数热更新：策略参数 (StrategyConfig) 与风控限额 (RiskConfig)
+// 来源: 1) 配置文件变化 (后台线程轮询 mtime)  2) 总线上的 "CT" 控制消息 (JSON)
+// 引擎在两个 tick 之间整体应用 (全部校验通过才生效)，并把新旧值写入审计日志
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+use rust_decimal::Decimal;
+
+use crate::config::{validate_order_size, validate_risk, validate_strategy, AppConfig, RiskConfig};
+use crate::model::strategy::StrategyConfig;
+
+pub enum ParamUpdate {
+    /// 配置文件变化：每个市场 (Opinion Market ID) 完整的新参数，已通过 AppConfig 校验
+    Reload { source: String, strategies: HashMap<u64, StrategyConfig>, risk: RiskConfig },
+    /// 控制消息：增量修改
+    Patch(ParamPatch),
+}
+
+/// 控制消息格式 (JSON)，例如:
+/// {"source": "ops", "market_id": 1, "strategy": {"risk_aversion_gamma": 0.1}, "risk": {"max_drawdown_usd": 200}}
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ParamPatch {
+    pub source: String,
+    pub market_id: Option<u64>, // None = 所有市场
+    pub strategy: toml::Table,
+    pub risk: toml::Table,
+}
+
+/// 单个字段的变更记录 (审计日志的一行)
+#[derive(Debug, Serialize)]
+pub struct ParamChange {
+    pub target: String, // "market:<id>" 或 "risk"
+    pub field: String,
+    pub old: String,
+    pub new: String,
+}
+
+impl ParamPatch {
+    pub fn from_json(raw: &[u8]) -> Result<Self, String> {
+        serde_json::from_slice(raw).map_err(|e| format!("Invalid control message: {}", e))
+    }
+}
+
+// 辅助函数: 在当前参数上叠加增量，拒绝未知字段
+fn apply_table<T: Serialize + for<'de> Deserialize<'de>>(current: &T, patch: &toml::Table) -> Result<T, String> {
+    let mut table = toml::Table::try_from(current).map_err(|e| e.to_string())?;
+    for (key, value) in patch {
+        if !table.contains_key(key) {
+            return Err(format!("unknown field '{}'", key));
+        }
+        table.insert(key.clone(), value.clone());
+    }
+    table.try_into().map_err(|e: toml::de::Error| e.message().to_string())
+}
+
+/// 计算增量之后的新策略参数 (已校验)
+pub fn patch_strategy(current: &StrategyConfig, patch: &toml::Table) -> Result<StrategyConfig, String> {
+    let next = apply_table(current, patch)?;
+    validate_strategy(&next)?;
+    Ok(next)
+}
+
+/// 计算增量之后的新风控参数 (已校验)
+/// order_sizes: 运行中各市场 (Opinion Market ID) 的单笔下单金额，与 config check 做同样的交叉校验
+pub fn patch_risk(current: &RiskConfig, patch: &toml::Table, order_sizes: &[(u64, Decimal)]) -> Result<RiskConfig, String> {
+    let next = apply_table(current, patch)?;
+    validate_risk(&next)?;
+    for &(market_id, order_size_usd) in order_sizes {
+        validate_order_size(market_id, order_size_usd, &next)?;
+    }
+    Ok(next)
+}
+
+/// 对比新旧参数，列出所有变化的字段
+pub fn diff<T: Serialize>(target: &str, old: &T, new: &T) -> Vec<ParamChange> {
+    let (Ok(old), Ok(new)) = (toml::Table::try_from(old), toml::Table::try_from(new)) else { return Vec::new() };
+    new.iter()
+        .filter(|(key, value)| old.get(*key) != Some(*value))
+        .map(|(key, value)| ParamChange {
+            target: target.to_string(),
+            field: key.clone(),
+            old: old.get(key).map(|v| v.to_string()).unwrap_or_default(),
+            new: value.to_string(),
+        })
+        .collect()
+}
+
+/// 审计日志：每个变化字段一行 JSON，追加写入
+pub fn write_audit_log(path: &str, source: &str, changes: &[ParamChange]) {
+    let ts = chrono::Utc::now().to_rfc3339();
+    let mut file = match OpenOptions::new().create(true).append(true).open(path) {
+        Ok(f) => f,
+        Err(e) => {
+            warn!(path, error = %e, "⚠️ Cannot open audit log");
+            return;
+        }
+    };
+
+    for c in changes {
+        info!(source, scope = %c.target, field = %c.field, old = %c.old, new = %c.new, "🔧 Parameter changed");
+        let line = serde_json::json!({
+            "timestamp": ts,
+            "source": source,
+            "target": c.target,
+            "field": c.field,
+            "old": c.old,
+            "new": c.new,
+        });
+        let _ = writeln!(file, "{}", line);
+    }
+}
+
+/// 后台线程：轮询配置文件的修改时间，变化后重新加载并校验
+/// 校验失败只打印错误，继续沿用旧参数
+pub fn spawn_config_watcher(path: String, tx: mpsc::Sender<ParamUpdate>) {
+    thread::spawn(move || {
+        let mtime = |p: &str| fs::metadata(p).and_then(|m| m.modified()).ok();
+        let mut last_modified = mtime(&path);
+        info!(path = %path, "👀 Watching config file");
+
+        loop {
+            thread::sleep(Duration::from_secs(1));
+
+            let modified = mtime(&path);
+            if modified == last_modified {
+                continue;
+            }
+            last_modified = modified;
+
+            let update = AppConfig::load(&path).and_then(|cfg| {
+                let strategies = cfg.market_specs()?.into_iter()
+                    .map(|spec| (spec.opinion_market_id, spec.config))
+                    .collect();
+                Ok(ParamUpdate::Reload { source: format!("file:{}", path), strategies, risk: cfg.risk })
+            });
+
+            match update {
+                Ok(u) => {
+                    if tx.send(u).is_err() {
+                        break; // 引擎已退出
+                    }
+                }
+                Err(e) => error!(error = %e, "❌ Rejected config change"),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::temp_dir;
+
+    fn table(raw: &str) -> toml::Table {
+        toml::from_str(raw).unwrap()
+    }
+
+    #[test]
+    fn strategy_patch_applies_and_rejects_invalid_values() {
+        let current = StrategyConfig::default();
+        let next = patch_strategy(&current, &table("risk_aversion_gamma = 0.2\nmin_spread_bps = 80")).unwrap();
+        assert_eq!((next.risk_aversion_gamma, next.min_spread_bps), (0.2, 80));
+        assert_eq!(next.liquidity_k, current.liquidity_k);
+
+        assert!(patch_strategy(&current, &table("tick_size = 2.0")).unwrap_err().contains("tick_size"));
+        assert_eq!(patch_strategy(&current, &table("gamma = 0.2")).unwrap_err(), "unknown field 'gamma'");
+    }
+
+    #[test]
+    fn risk_patch_applies_and_rejects_invalid_limits() {
+        let current = RiskConfig::default();
+        let next = patch_risk(&current, &table("max_drawdown_usd = 250.0\nmax_position_usd = 0.0"), &[]).unwrap();
+        assert_eq!((next.max_drawdown_usd, next.max_position_usd), (250.0, 0.0));
+
+        assert!(patch_risk(&current, &table("max_drawdown_usd = -1.0"), &[]).unwrap_err().contains("max_drawdown_usd"));
+        assert!(patch_risk(&current, &table("max_portfolio_usd = -5.0"), &[]).unwrap_err().contains("max_portfolio_usd"));
+        assert!(patch_risk(&current, &table("max_order_size_usd = \"big\""), &[]).is_err());
+    }
+
+    #[test]
+    fn risk_patch_below_a_running_order_size_is_rejected_like_config_check() {
+        let current = RiskConfig::default();
+        let sizes = [(7, Decimal::from(50))];
+        assert_eq!(patch_risk(&current, &table("max_order_size_usd = 40.0"), &sizes).unwrap_err(),
+            "markets[7].order_size_usd 50 exceeds risk.max_order_size_usd 40");
+        assert_eq!(patch_risk(&current, &table("max_order_size_usd = 50.0"), &sizes).unwrap().max_order_size_usd, 50.0);
+    }
+
+    #[test]
+    fn control_message_parses_and_diff_lists_only_changes() {
+        let patch = ParamPatch::from_json(br#"{"source": "ops", "market_id": 1, "risk": {"max_drawdown_usd": 200.0}}"#).unwrap();
+        assert_eq!((patch.source.as_str(), patch.market_id), ("ops", Some(1)));
+        assert!(ParamPatch::from_json(br#"{"markets": 1}"#).is_err());
+
+        let old = RiskConfig::default();
+        let new = patch_risk(&old, &patch.risk, &[]).unwrap();
+        let changes = diff("risk", &old, &new);
+        assert_eq!(changes.len(), 1);
+        assert_eq!((changes[0].field.as_str(), changes[0].old.as_str(), changes[0].new.as_str()), ("max_drawdown_usd", "100.0", "200.0"));
+        assert!(diff("risk", &old, &old).is_empty());
+    }
+
+    #[test]
+    fn audit_log_appends_one_json_line_per_change() {
+        let dir = temp_dir("audit");
+        let path = dir.join("audit.log");
+        let path = path.to_str().unwrap();
+        let old = RiskConfig::default();
+        let new = patch_risk(&old, &table("max_drawdown_usd = 200.0\nsoft_limit_ratio = 0.5"), &[]).unwrap();
+
+        write_audit_log(path, "ops", &diff("risk", &old, &new));
+        write_audit_log(path, "file:config.toml", &diff("risk", &new, &old));
+        let lines: Vec<serde_json::Value> = fs::read_to_string(path).unwrap().lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!((lines[0]["source"].as_str(), lines[0]["target"].as_str()), (Some("ops"), Some("risk")));
+        assert_eq!((lines[2]["source"].as_str(), lines[2]["new"].as_str()), (Some("file:config.toml"), Some("100.0")));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn config_watcher_sends_valid_reloads_and_skips_invalid_ones() {
+        let dir = temp_dir("watch");
+        let path = dir.join("config.toml");
+        let market = "[[markets]]\npoly_asset_id = \"123\"\nopinion_market_id = 7\n";
+        fs::write(&path, market).unwrap();
+
+        let (tx, rx) = mpsc::channel();
+        spawn_config_watcher(path.to_str().unwrap().to_string(), tx);
+
+        // 无效的风控限额：整批拒绝，不发送更新
+        thread::sleep(Duration::from_millis(100));
+        fs::write(&path, format!("[risk]\nmax_drawdown_usd = -1.0\n{}", market)).unwrap();
+        assert!(rx.recv_timeout(Duration::from_millis(2500)).is_err());
+
+        fs::write(&path, format!("[risk]\nmax_drawdown_usd = 300.0\n[strategy]\nmin_spread_bps = 90\n{}", market)).unwrap();
+        let Ok(ParamUpdate::Reload { strategies, risk, .. }) = rx.recv_timeout(Duration::from_secs(5)) else { panic!("no reload") };
+        assert_eq!(risk.max_drawdown_usd, 300.0);
+        assert_eq!(strategies[&7].min_spread_bps, 90);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
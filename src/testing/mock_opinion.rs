@@ -0,0 +1,328 @@
+// File: src/testing/mock_opinion.rs
+// 本地模拟的 Opinion Labs HTTP 接口：校验 EIP-712 订单签名与 X-Signature 请求签名，在内存里维护挂单 / 成交
+// 公开盘口接口返回测试设置的快照
+// 可脚本化：固定延迟、按顺序注入的响应 (429 限流 / 拒单)、手动成交 (支持部分成交)
+use ethers::types::transaction::eip712::Eip712;
+use ethers::types::{Address, Signature, H256};
+use rust_decimal::Decimal;
+use std::collections::{HashMap, VecDeque};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::core::Side;
+use crate::venues::opinion::LimitOrder;
+
+/// 注入给下一次请求的响应 (按注入顺序逐个消耗)
+#[derive(Clone, Debug)]
+pub enum MockAction {
+    Status(u16),    // 直接返回该状态码 (例如 429 限流)
+    Reject(String), // 拒绝：400 + {"error": 原因}
+}
+
+#[derive(Clone, Debug)]
+pub struct MockOrder {
+    pub order_id: String,
+    pub client_order_id: String,
+    pub market_id: u64,
+    pub side: Side,
+    pub price: Decimal,
+    pub size: Decimal, // 与下单时的 size 同单位 (USD)
+    pub filled: Decimal,
+}
+
+#[derive(Default)]
+pub struct MockState {
+    pub orders: Vec<MockOrder>, // 仍在挂单的订单
+    pub fills: Vec<serde_json::Value>,
+    pub requests: Vec<String>, // "METHOD /path"，按到达顺序
+    pub bad_signatures: u32,
+    pub cancel_all_calls: u32,
+    pub books: HashMap<u64, serde_json::Value>, // 公开盘口 (GET /orderbook/{marketId})
+    latency: Duration,
+    script: VecDeque<MockAction>,
+    next_id: u64,
+}
+
+pub struct MockOpinion {
+    pub url: String,
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockOpinion {
+    /// 在随机端口上启动；只接受 maker 钱包签名的请求
+    pub async fn start(maker: Address) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let state = Arc::new(Mutex::new(MockState::default()));
+
+        let shared = state.clone();
+        tokio::spawn(async move {
+            loop {
+                let Ok((stream, _)) = listener.accept().await else { continue };
+                tokio::spawn(serve(stream, maker, shared.clone()));
+            }
+        });
+        Self { url, state }
+    }
+
+    /// 之后每个请求都先等待 latency 再处理
+    pub fn set_latency(&self, latency: Duration) {
+        self.state().latency = latency;
+    }
+
+    pub fn push(&self, action: MockAction) {
+        self.state().script.push_back(action);
+    }
+
+    pub fn state(&self) -> MutexGuard<'_, MockState> {
+        self.state.lock().unwrap()
+    }
+
+    /// 设置某个市场的盘口快照 (原样作为 GET /orderbook/{marketId} 的响应)
+    pub fn set_book(&self, market_id: u64, book: serde_json::Value) {
+        self.state().books.insert(market_id, book);
+    }
+
+    /// 成交某个挂单的一部分 (size 与订单同单位)；全部成交后从挂单列表移除
+    pub fn fill(&self, order_id: &str, size: Decimal) {
+        let mut state = self.state();
+        let Some(i) = state.orders.iter().position(|o| o.order_id == order_id) else { return };
+        let fill_id = format!("fill-{}", state.fills.len() + 1);
+        let order = &mut state.orders[i];
+        let size = size.min(order.size - order.filled);
+        order.filled += size;
+        let fill = serde_json::json!({
+            "fillId": fill_id,
+            "orderId": order.order_id,
+            "marketId": order.market_id,
+            "side": side_str(order.side),
+            "price": order.price.to_string(),
+            "size": size.to_string(),
+            "fee": "0",
+            "timestamp": chrono::Utc::now().timestamp_millis(),
+            "clientOrderId": order.client_order_id,
+        });
+        if order.filled >= order.size {
+            state.orders.remove(i);
+        }
+        state.fills.push(fill);
+    }
+}
+
+struct Request {
+    method: String,
+    path: String,
+    query: String,
+    headers: HashMap<String, String>,
+    body: Vec<u8>,
+}
+
+impl Request {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).map(|s| s.as_str())
+    }
+}
+
+// 读一个完整的 HTTP/1.1 请求 (头部 + Content-Length 长度的 body)
+async fn read_request(stream: &mut TcpStream) -> Option<Request> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let head_end = loop {
+        if let Some(i) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break i + 4;
+        }
+        let n = stream.read(&mut chunk).await.ok()?;
+        if n == 0 {
+            return None;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    };
+
+    let head = String::from_utf8_lossy(&buf[..head_end]).to_string();
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next()?.split(' ');
+    let method = request_line.next()?.to_string();
+    let target = request_line.next()?;
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let headers: HashMap<String, String> = lines
+        .filter_map(|l| l.split_once(':'))
+        .map(|(k, v)| (k.trim().to_ascii_lowercase(), v.trim().to_string()))
+        .collect();
+
+    let len = headers.get("content-length").and_then(|v| v.parse::<usize>().ok()).unwrap_or(0);
+    while buf.len() < head_end + len {
+        let n = stream.read(&mut chunk).await.ok()?;
+        if n == 0 {
+            return None;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+
+    Some(Request {
+        method,
+        path: path.to_string(),
+        query: query.to_string(),
+        headers,
+        body: buf[head_end..head_end + len].to_vec(),
+    })
+}
+
+async fn serve(mut stream: TcpStream, maker: Address, state: Arc<Mutex<MockState>>) {
+    let Some(req) = read_request(&mut stream).await else { return };
+
+    let (latency, action) = {
+        let mut s = state.lock().unwrap();
+        s.requests.push(format!("{} {}", req.method, req.path));
+        (s.latency, s.script.pop_front())
+    };
+    if !latency.is_zero() {
+        tokio::time::sleep(latency).await;
+    }
+
+    let (status, body) = match action {
+        Some(MockAction::Status(code)) => (code, serde_json::json!({ "error": "scripted" })),
+        Some(MockAction::Reject(reason)) => (400, serde_json::json!({ "error": reason })),
+        None => handle(&req, maker, &mut state.lock().unwrap()),
+    };
+
+    let body = body.to_string();
+    let response = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status, reason_phrase(status), body.len(), body
+    );
+    let _ = stream.write_all(response.as_bytes()).await;
+}
+
+fn handle(req: &Request, maker: Address, state: &mut MockState) -> (u16, serde_json::Value) {
+    let action = match (req.method.as_str(), req.path.as_str()) {
+        ("POST", "/order") => return post_order(req, maker, state),
+        // 盘口是公开接口，不需要签名
+        ("GET", p) if p.starts_with("/orderbook/") => {
+            let book = p["/orderbook/".len()..].parse::<u64>().ok().and_then(|id| state.books.get(&id));
+            return match book {
+                Some(book) => (200, book.clone()),
+                None => (404, serde_json::json!({ "error": "unknown market" })),
+            };
+        }
+        ("DELETE", "/orders") => "CANCEL_ALL".to_string(),
+        ("DELETE", p) if p.starts_with("/order/") => format!("CANCEL_{}", &p["/order/".len()..]),
+        ("GET", "/orders") => "OPEN_ORDERS".to_string(),
+        ("GET", "/balances") => "BALANCES".to_string(),
+        ("GET", "/fills") => "FILLS".to_string(),
+        _ => return (404, serde_json::json!({ "error": "not found" })),
+    };
+    if let Err(e) = verify_request(req, &action, maker) {
+        state.bad_signatures += 1;
+        return (401, serde_json::json!({ "error": e }));
+    }
+
+    match action.as_str() {
+        "CANCEL_ALL" => {
+            state.cancel_all_calls += 1;
+            let cancelled = state.orders.len();
+            state.orders.clear();
+            (200, serde_json::json!({ "cancelled": cancelled }))
+        }
+        "OPEN_ORDERS" => {
+            let orders: Vec<_> = state.orders.iter().map(|o| serde_json::json!({
+                "orderId": o.order_id,
+                "marketId": o.market_id,
+                "side": side_str(o.side),
+                "price": o.price.to_string(),
+                "size": o.size.to_string(),
+                "filled": o.filled.to_string(),
+            })).collect();
+            (200, serde_json::json!({ "orders": orders }))
+        }
+        "BALANCES" => {
+            let locked: Decimal = state.orders.iter().filter(|o| o.side == Side::Buy).map(|o| o.size - o.filled).sum();
+            (200, serde_json::json!({ "balances": [{ "asset": "USDC", "available": "100000", "locked": locked.to_string() }] }))
+        }
+        "FILLS" => {
+            let since = req.query.split('&')
+                .find_map(|kv| kv.strip_prefix("since="))
+                .and_then(|v| v.parse::<i64>().ok())
+                .unwrap_or(0);
+            let fills: Vec<_> = state.fills.iter().filter(|f| f["timestamp"].as_i64().unwrap_or(0) >= since).cloned().collect();
+            (200, serde_json::json!({ "fills": fills }))
+        }
+        cancel => {
+            let order_id = &cancel["CANCEL_".len()..];
+            let before = state.orders.len();
+            state.orders.retain(|o| o.order_id != order_id);
+            if state.orders.len() == before {
+                return (404, serde_json::json!({ "error": "unknown order" }));
+            }
+            (200, serde_json::json!({ "cancelled": 1 }))
+        }
+    }
+}
+
+fn post_order(req: &Request, maker: Address, state: &mut MockState) -> (u16, serde_json::Value) {
+    let body: serde_json::Value = match serde_json::from_slice(&req.body) {
+        Ok(v) => v,
+        Err(e) => return (400, serde_json::json!({ "error": e.to_string() })),
+    };
+    let order = match verify_order(&body, maker) {
+        Ok(order) => order,
+        Err(e) => {
+            state.bad_signatures += 1;
+            return (401, serde_json::json!({ "error": e }));
+        }
+    };
+
+    state.next_id += 1;
+    let order_id = format!("mock-{}", state.next_id);
+    state.orders.push(MockOrder {
+        order_id: order_id.clone(),
+        client_order_id: body["clientOrderId"].as_str().unwrap_or_default().to_string(),
+        market_id: order.market_id.as_u64(),
+        side: if order.side == 0 { Side::Buy } else { Side::Sell },
+        price: Decimal::from_i128_with_scale(order.price.as_u128() as i128, 6),
+        size: Decimal::from_i128_with_scale(order.size.as_u128() as i128, 6),
+        filled: Decimal::ZERO,
+    });
+    (200, serde_json::json!({ "orderId": order_id }))
+}
+
+// 订单签名：EIP-712 哈希恢复出的地址必须是 maker，且订单里的 maker 字段一致
+fn verify_order(body: &serde_json::Value, maker: Address) -> Result<LimitOrder, String> {
+    let order: LimitOrder = serde_json::from_value(body["order"].clone()).map_err(|e| format!("bad order: {}", e))?;
+    let signature = Signature::from_str(body["signature"].as_str().ok_or("missing signature")?)
+        .map_err(|e| format!("bad signature: {}", e))?;
+    let hash = order.encode_eip712().map_err(|e| format!("cannot hash order: {}", e))?;
+    let signer = signature.recover(H256::from(hash)).map_err(|e| format!("cannot recover signer: {}", e))?;
+    if signer != maker || order.maker != maker {
+        return Err(format!("order signed by {:?}, expected {:?}", signer, maker));
+    }
+    Ok(order)
+}
+
+// 请求签名：X-Signature 是 maker 对 "{ACTION}_{X-Timestamp}" 的个人签名
+fn verify_request(req: &Request, action: &str, maker: Address) -> Result<(), String> {
+    let signature = req.header("x-signature").ok_or("missing X-Signature")?;
+    let timestamp = req.header("x-timestamp").ok_or("missing X-Timestamp")?;
+    let signature = Signature::from_str(signature).map_err(|e| format!("bad X-Signature: {}", e))?;
+    signature.verify(format!("{}_{}", action, timestamp), maker).map_err(|e| format!("X-Signature mismatch: {}", e))
+}
+
+fn side_str(side: Side) -> &'static str {
+    match side {
+        Side::Buy => "BUY",
+        Side::Sell => "SELL",
+    }
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        429 => "Too Many Requests",
+        _ => "Error",
+    }
+}
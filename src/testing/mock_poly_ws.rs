@@ -0,0 +1,91 @@
+// File: src/testing/mock_poly_ws.rs
+// 本地模拟的 Polymarket WebSocket 行情：接受一个连接，收到订阅指令后按脚本推送消息
+// 脚本可以回放录制的消息 (fixtures/)，也可以注入 Ping、非法 JSON、乱序快照和断线
+use futures_util::{SinkExt, StreamExt};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio_tungstenite::tungstenite::protocol::Message;
+
+/// 脚本中的一步
+#[derive(Clone, Debug)]
+pub enum WsStep {
+    Text(String),      // 推送一条文本消息 (原样发送，可以是非法 JSON)
+    Ping(Vec<u8>),     // 心跳，客户端应回复同样内容的 Pong
+    Sleep(Duration),
+    Close,             // 正常关闭 (发送 Close 帧)
+    Drop,              // 直接断开 TCP，不发 Close 帧
+}
+
+impl WsStep {
+    /// 把录制文件 (每行一条消息，空行忽略) 转成脚本
+    pub fn fixture(lines: &str) -> Vec<WsStep> {
+        lines.lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .map(|l| WsStep::Text(l.to_string()))
+            .collect()
+    }
+}
+
+pub struct MockPolyWs {
+    pub url: String,
+    received: Arc<Mutex<Vec<Message>>>, // 客户端发来的消息 (订阅指令、Pong ...)
+}
+
+impl MockPolyWs {
+    /// 在随机端口上启动；脚本执行完仍未关闭时自动发送 Close
+    pub async fn start(script: Vec<WsStep>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let received = Arc::new(Mutex::new(Vec::new()));
+
+        let log = received.clone();
+        tokio::spawn(async move {
+            let Ok((stream, _)) = listener.accept().await else { return };
+            let Ok(ws) = tokio_tungstenite::accept_async(stream).await else { return };
+            let (mut write, mut read) = ws.split();
+
+            // 先等订阅指令，真实服务端在订阅之前不会推送任何数据
+            match read.next().await {
+                Some(Ok(msg)) => log.lock().unwrap().push(msg),
+                _ => return,
+            }
+            let reader_log = log.clone();
+            let reader = tokio::spawn(async move {
+                while let Some(Ok(msg)) = read.next().await {
+                    reader_log.lock().unwrap().push(msg);
+                }
+            });
+
+            for step in script {
+                let sent = match step {
+                    WsStep::Text(text) => write.send(Message::Text(text)).await,
+                    WsStep::Ping(payload) => write.send(Message::Ping(payload)).await,
+                    WsStep::Sleep(d) => {
+                        tokio::time::sleep(d).await;
+                        Ok(())
+                    }
+                    WsStep::Close => break,
+                    WsStep::Drop => {
+                        reader.abort();
+                        return; // write / read 两半都被丢弃，连接直接断开
+                    }
+                };
+                if sent.is_err() {
+                    return;
+                }
+            }
+            // 给客户端一点时间回复 Pong，再正常关闭
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            let _ = write.send(Message::Close(None)).await;
+            let _ = reader.await;
+        });
+
+        Self { url, received }
+    }
+
+    pub fn received(&self) -> MutexGuard<'_, Vec<Message>> {
+        self.received.lock().unwrap()
+    }
+}
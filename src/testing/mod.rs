@@ -0,0 +1,26 @@
+// File: src/testing/mod.rs
+// 测试专用：本地模拟场馆 (只在 cargo test 时编译)
+pub mod mock_opinion;
+pub mod mock_poly_ws;
+
+use ethers::signers::{LocalWallet, Signer};
+
+/// 测试钱包私钥 (公开的测试密钥，不持有任何资产)
+pub const TEST_PRIVATE_KEY: &str = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+
+pub fn test_wallet() -> LocalWallet {
+    TEST_PRIVATE_KEY.parse::<LocalWallet>().unwrap().with_chain_id(137u64)
+}
+
+/// 向系统申请一个当前空闲的本地端口
+pub fn free_port() -> u16 {
+    std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port()
+}
+
+/// 新建一个空的临时目录 (进程号 + 名字 + 纳秒时间，测试结束后由调用方删除)
+pub fn temp_dir(name: &str) -> std::path::PathBuf {
+    let nanos = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_nanos();
+    let dir = std::env::temp_dir().join(format!("mm-test-{}-{}-{}", std::process::id(), name, nanos));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
@@ -0,0 +1,148 @@
+// File: src/venues/mod.rs
+// 交易所适配层：所有场馆 (Venue) 都实现同一个 ExchangeAdapter 接口
+// 新增场馆只需要新建一个子模块并实现该 trait，执行层无需复制代码
+pub mod opinion;
+pub mod paper;
+pub mod polymarket;
+pub mod rules;
+
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+use crate::core::{Exchange, Side, StageStamps, TradeSignal};
+use crate::infrastructure::metrics::{exchange_label, HTTP_RESPONSES};
+use crate::venues::rules::MarketRules;
+
+/// 适配层统一错误类型 (跨 Task 传递，必须 Send + Sync)
+pub type VenueResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+/// 签名后的订单：签名 (CPU) 与发送 (IO) 分离的中间产物
+#[derive(Debug, Clone)]
+pub struct SignedOrder {
+    pub exchange: Exchange, // 由哪个场馆签名，Broadcaster 据此路由
+    pub payload: serde_json::Value,
+    pub order_id_tag: String, // 客户端订单号 (见 client_order_id)
+    pub trace_id: u64,
+    pub stamps: StageStamps,
+}
+
+/// 场馆上仍在挂单的订单
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenOrder {
+    pub order_id: String,
+    pub symbol_id: u64,
+    pub side: Side,
+    pub price: Decimal,
+    pub size: Decimal,
+    pub filled: Decimal,
+}
+
+/// 账户余额 (available 可用, locked 被挂单占用)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Balance {
+    pub asset: String,
+    pub available: Decimal,
+    pub locked: Decimal,
+}
+
+/// 成交回报
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Fill {
+    pub fill_id: String, // 场馆侧的成交 ID (成交监听器据此去重)
+    pub order_id: String,
+    pub symbol_id: u64,
+    pub side: Side,
+    pub price: Decimal,
+    pub size: Decimal,
+    pub fee: Decimal,
+    pub timestamp_ms: i64,
+    pub trace_id: u64, // 从客户端订单号解析 (场馆不回传时为 0)
+}
+
+#[async_trait]
+pub trait ExchangeAdapter: Send + Sync {
+    /// 该适配器对应的场馆 (用于按 TradeSignal.target_exchange 路由)
+    fn exchange(&self) -> Exchange;
+
+    /// 阶段一：纯 CPU 计算 (签名)
+    async fn create_signed_order(&self, signal: &TradeSignal) -> VenueResult<SignedOrder>;
+
+    /// 阶段二：纯网络 IO (发送)，返回场馆侧的订单 ID
+    async fn submit_order(&self, signed_order: SignedOrder) -> VenueResult<String>;
+
+    /// 一步下单：签名 + 发送 (非流水线场景使用)
+    #[allow(dead_code)]
+    async fn place_order(&self, signal: &TradeSignal) -> VenueResult<String> {
+        let signed = self.create_signed_order(signal).await?;
+        self.submit_order(signed).await
+    }
+
+    async fn cancel_order(&self, order_id: &str) -> VenueResult<()>;
+
+    /// 极速撤单 (Batch Cancel)：做市商保命键
+    async fn cancel_all(&self) -> VenueResult<()>;
+
+    async fn open_orders(&self) -> VenueResult<Vec<OpenOrder>>;
+
+    async fn balances(&self) -> VenueResult<Vec<Balance>>;
+
+    /// 拉取 since_ms (毫秒时间戳) 之后的成交
+    async fn fills(&self, since_ms: i64) -> VenueResult<Vec<Fill>>;
+
+    /// 某个市场的交易规则 (tick 表、最小下单量)；None = 场馆不提供，使用配置
+    async fn market_rules(&self, _symbol_id: u64) -> VenueResult<Option<MarketRules>> {
+        Ok(None)
+    }
+}
+
+// 辅助函数: 场馆 JSON 里的数字可能是字符串也可能是数字，统一转成 Decimal
+pub(crate) fn json_decimal(v: &serde_json::Value) -> Option<Decimal> {
+    match v {
+        serde_json::Value::String(s) => Decimal::from_str(s).ok(),
+        serde_json::Value::Number(n) => Decimal::from_str(&n.to_string()).ok(),
+        _ => None,
+    }
+}
+
+// 辅助函数: 解析买卖方向 ("BUY"/"SELL" 或 0/1)
+pub(crate) fn json_side(v: &serde_json::Value) -> Option<Side> {
+    match v {
+        serde_json::Value::String(s) if s.eq_ignore_ascii_case("buy") => Some(Side::Buy),
+        serde_json::Value::String(s) if s.eq_ignore_ascii_case("sell") => Some(Side::Sell),
+        serde_json::Value::Number(n) => match n.as_u64()? {
+            0 => Some(Side::Buy),
+            1 => Some(Side::Sell),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// 辅助函数: 客户端订单号 "<trace_id 十六进制>-<salt>"，场馆在成交回报里回传后可以还原追踪 ID
+pub(crate) fn client_order_id(trace_id: u64, salt: impl std::fmt::Display) -> String {
+    format!("{:016x}-{}", trace_id, salt)
+}
+
+// 辅助函数: 从客户端订单号还原追踪 ID (格式不符时为 0)
+pub(crate) fn trace_id_from_client_order_id(v: &serde_json::Value) -> u64 {
+    v.as_str()
+        .and_then(|s| s.split('-').next())
+        .and_then(|hex| u64::from_str_radix(hex, 16).ok())
+        .unwrap_or(0)
+}
+
+// 辅助函数: 发送 HTTP 请求并按 (场馆, 方法, 状态码) 计数；网络错误记为 status="error"
+pub(crate) async fn send_recorded(exchange: Exchange, req: reqwest::RequestBuilder) -> reqwest::Result<reqwest::Response> {
+    let (client, req) = req.build_split();
+    let req = req?;
+    let method = req.method().to_string();
+    let result = client.execute(req).await;
+    let status = match &result {
+        Ok(resp) => resp.status().as_u16().to_string(),
+        Err(_) => "error".to_string(),
+    };
+    HTTP_RESPONSES.with_label_values(&[exchange_label(exchange), &method, &status]).inc();
+    result
+}
@@ -0,0 +1,355 @@
+// File: src/venues/opinion.rs
+use async_trait::async_trait;
+use ethers::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::core::{Exchange, Side, TradeSignal};
+use crate::venues::{client_order_id, json_decimal, json_side, send_recorded, trace_id_from_client_order_id, Balance, ExchangeAdapter, Fill, OpenOrder, SignedOrder, VenueResult};
+
+// --- A. Opinion Labs 的订单结构 (EIP-712) ---
+#[derive(Debug, Clone, Eip712, EthAbiType, Serialize, Deserialize)]
+#[eip712(
+    name = "OpinionExchange",
+    version = "1",
+    chain_id = 137,
+    // ⚠️ 务必替换为真实合约地址
+    verifying_contract = "0x0000000000000000000000000000000000000000"
+)]
+pub struct LimitOrder {
+    pub salt: u128,
+    pub maker: Address,
+    pub market_id: U256,
+    pub side: u8,
+    pub price: U256,
+    pub size: U256,
+    pub expiration: u64,
+}
+
+// --- B. 执行网关 ---
+pub struct OpinionMakerGateway {
+    wallet: LocalWallet,
+    http_client: reqwest::Client,
+    api_url: String,
+}
+
+impl OpinionMakerGateway {
+    pub fn new(private_key: &str, api_url: &str) -> Self {
+        let wallet = private_key.parse::<LocalWallet>().unwrap()
+            .with_chain_id(137u64);
+
+        // [优化点 1] 激进的 HTTP 连接池配置
+        let client = reqwest::Client::builder()
+            .tcp_nodelay(true)           // 禁用 Nagle 算法，有数据立即发送
+            .pool_max_idle_per_host(100) // 保持更多空闲连接
+            .timeout(Duration::from_secs(2)) // 2秒超时，HFT 不需要等太久
+            .build()
+            .expect("Failed to create HTTP client");
+
+        Self {
+            wallet,
+            http_client: client,
+            api_url: api_url.to_string(),
+        }
+    }
+
+    // 签名消息格式需参考官方文档，这里假设为 "{ACTION}_{ts}"
+    // 返回 (签名, 时间戳)，用于 X-Signature / X-Timestamp 头
+    async fn sign_request(&self, action: &str) -> VenueResult<(String, u128)> {
+        let timestamp = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)?.as_millis();
+        let signature = self.wallet.sign_message(format!("{}_{}", action, timestamp)).await?;
+        Ok((signature.to_string(), timestamp))
+    }
+
+    // 带签名头的 GET 请求 (查询类接口)
+    async fn signed_get(&self, path: &str, action: &str) -> VenueResult<serde_json::Value> {
+        let (signature, timestamp) = self.sign_request(action).await?;
+        let req = self.http_client
+            .get(format!("{}{}", self.api_url, path))
+            .query(&[("maker", format!("{:?}", self.wallet.address()))])
+            .header("X-Signature", signature)
+            .header("X-Timestamp", timestamp.to_string());
+        let resp = send_recorded(Exchange::OpinionLabs, req).await?;
+
+        if !resp.status().is_success() {
+            return Err(format!("HTTP {}", resp.status()).into());
+        }
+        Ok(resp.json().await?)
+    }
+}
+
+#[async_trait]
+impl ExchangeAdapter for OpinionMakerGateway {
+    fn exchange(&self) -> Exchange {
+        Exchange::OpinionLabs
+    }
+
+    /// 阶段一：纯 CPU 计算 (签名)
+    /// 这个函数执行非常快，不涉及网络 IO
+    async fn create_signed_order(&self, signal: &TradeSignal) -> VenueResult<SignedOrder> {
+        let order_struct = LimitOrder {
+            // salt 取 u64 范围：JSON (serde_json::Value) 无法表示超过 u64 的整数
+            salt: rand::random::<u64>() as u128,
+            maker: self.wallet.address(),
+            market_id: U256::from(signal.symbol_id),
+            side: if signal.side == Side::Buy { 0 } else { 1 },
+            // [关键修复] 使用 6 位精度 (USDC)
+            price: ethers::utils::parse_units(signal.price, 6)?.into(),
+            size: ethers::utils::parse_units(signal.size_usd, 6)?.into(),
+            expiration: 0,
+        };
+
+        // 签名 (CPU 密集)
+        let signature = self.wallet.sign_typed_data(&order_struct).await?;
+
+        // 构建 Payload (clientOrderId 会在成交回报中原样返回，用于还原追踪 ID)
+        let order_id_tag = client_order_id(signal.trace_id, order_struct.salt);
+        let payload = serde_json::json!({
+            "order": order_struct,
+            "signature": signature.to_string(),
+            "strategy_tag": "RUST_MM_BOT",
+            "clientOrderId": order_id_tag,
+            // 只做 Maker：下单时会立即成交的订单由场馆拒绝
+            "postOnly": signal.post_only
+        });
+
+        Ok(SignedOrder {
+            exchange: Exchange::OpinionLabs,
+            payload,
+            order_id_tag,
+            trace_id: signal.trace_id,
+            stamps: signal.stamps,
+        })
+    }
+
+    /// 阶段二：纯网络 IO (发送)
+    /// 这里的耗时是不确定的 (50ms - 500ms)
+    async fn submit_order(&self, signed_order: SignedOrder) -> VenueResult<String> {
+        let req = self.http_client
+            .post(format!("{}/order", self.api_url))
+            .json(&signed_order.payload);
+        let resp = send_recorded(Exchange::OpinionLabs, req).await?;
+
+        if !resp.status().is_success() {
+            return Err(format!("HTTP {}", resp.status()).into());
+        }
+
+        // 优先使用场馆返回的 orderId (撤单需要)，解析失败则退回本地追踪 ID
+        let order_id = resp.json::<serde_json::Value>().await.ok()
+            .and_then(|v| v["orderId"].as_str().map(|s| s.to_string()))
+            .unwrap_or(signed_order.order_id_tag);
+        Ok(order_id)
+    }
+
+    async fn cancel_order(&self, order_id: &str) -> VenueResult<()> {
+        let (signature, timestamp) = self.sign_request(&format!("CANCEL_{}", order_id)).await?;
+
+        let req = self.http_client
+            .delete(format!("{}/order/{}", self.api_url, order_id))
+            .header("X-Signature", signature)
+            .header("X-Timestamp", timestamp.to_string());
+        let resp = send_recorded(Exchange::OpinionLabs, req).await?;
+
+        if !resp.status().is_success() {
+            return Err(format!("HTTP {}", resp.status()).into());
+        }
+        Ok(())
+    }
+
+    /// 极速撤单 (Batch Cancel)
+    /// 做市商最关键的功能：一键撤回所有报价
+    async fn cancel_all(&self) -> VenueResult<()> {
+        // 假设撤单只需要签一个时间戳
+        let (signature, timestamp) = self.sign_request("CANCEL_ALL").await?;
+
+        let req = self.http_client
+            .delete(format!("{}/orders", self.api_url))
+            .header("X-Signature", signature)
+            .header("X-Timestamp", timestamp.to_string());
+        let resp = send_recorded(Exchange::OpinionLabs, req).await?;
+
+        if !resp.status().is_success() {
+            return Err(format!("HTTP {}", resp.status()).into());
+        }
+        Ok(())
+    }
+
+    async fn open_orders(&self) -> VenueResult<Vec<OpenOrder>> {
+        let v = self.signed_get("/orders", "OPEN_ORDERS").await?;
+        let orders = v["orders"].as_array().map(|arr| {
+            arr.iter().filter_map(|o| Some(OpenOrder {
+                order_id: o["orderId"].as_str()?.to_string(),
+                symbol_id: o["marketId"].as_u64()?,
+                side: json_side(&o["side"])?,
+                price: json_decimal(&o["price"])?,
+                size: json_decimal(&o["size"])?,
+                filled: json_decimal(&o["filled"]).unwrap_or_default(),
+            })).collect()
+        }).unwrap_or_default();
+        Ok(orders)
+    }
+
+    async fn balances(&self) -> VenueResult<Vec<Balance>> {
+        let v = self.signed_get("/balances", "BALANCES").await?;
+        let balances = v["balances"].as_array().map(|arr| {
+            arr.iter().filter_map(|b| Some(Balance {
+                asset: b["asset"].as_str()?.to_string(),
+                available: json_decimal(&b["available"])?,
+                locked: json_decimal(&b["locked"]).unwrap_or_default(),
+            })).collect()
+        }).unwrap_or_default();
+        Ok(balances)
+    }
+
+    async fn fills(&self, since_ms: i64) -> VenueResult<Vec<Fill>> {
+        let v = self.signed_get(&format!("/fills?since={}", since_ms), "FILLS").await?;
+        let fills = v["fills"].as_array().map(|arr| {
+            arr.iter().filter_map(|f| Some(Fill {
+                fill_id: f["fillId"].as_str()?.to_string(),
+                order_id: f["orderId"].as_str()?.to_string(),
+                symbol_id: f["marketId"].as_u64()?,
+                side: json_side(&f["side"])?,
+                price: json_decimal(&f["price"])?,
+                size: json_decimal(&f["size"])?,
+                fee: json_decimal(&f["fee"]).unwrap_or_default(),
+                timestamp_ms: f["timestamp"].as_i64().unwrap_or(0),
+                trace_id: trace_id_from_client_order_id(&f["clientOrderId"]),
+            })).collect()
+        }).unwrap_or_default();
+        Ok(fills)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::StageStamps;
+    use crate::testing::mock_opinion::{MockAction, MockOpinion};
+    use crate::testing::{test_wallet, TEST_PRIVATE_KEY};
+    use rust_decimal_macros::dec;
+
+    fn quote(side: Side, trace_id: u64) -> TradeSignal {
+        TradeSignal {
+            strategy_id: 1,
+            target_exchange: Exchange::OpinionLabs,
+            symbol_id: 7,
+            side,
+            price: dec!(0.45),
+            size_usd: dec!(50),
+            logic_tag: 1,
+            post_only: false,
+            level: 0,
+            created_at_ns: 0,
+            trace_id,
+            stamps: StageStamps::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn signed_order_is_accepted_and_listed() {
+        let mock = MockOpinion::start(test_wallet().address()).await;
+        let gw = OpinionMakerGateway::new(TEST_PRIVATE_KEY, &mock.url);
+
+        let order_id = gw.place_order(&quote(Side::Buy, 0xabc)).await.unwrap();
+        assert_eq!(order_id, "mock-1");
+        {
+            let state = mock.state();
+            assert_eq!(state.bad_signatures, 0);
+            let order = &state.orders[0];
+            assert_eq!((order.market_id, order.side, order.price, order.size), (7, Side::Buy, dec!(0.45), dec!(50)));
+            assert!(order.client_order_id.starts_with("0000000000000abc-"));
+        }
+
+        // 查询接口带 X-Signature
+        let open = gw.open_orders().await.unwrap();
+        assert_eq!(open.len(), 1);
+        assert_eq!(open[0].order_id, "mock-1");
+        assert_eq!(gw.balances().await.unwrap()[0].locked, dec!(50));
+    }
+
+    #[tokio::test]
+    async fn post_only_flag_is_sent_in_the_order_payload() {
+        let gw = OpinionMakerGateway::new(TEST_PRIVATE_KEY, "http://127.0.0.1:9");
+        for post_only in [false, true] {
+            let signed = gw.create_signed_order(&TradeSignal { post_only, ..quote(Side::Buy, 1) }).await.unwrap();
+            assert_eq!(signed.payload["postOnly"], post_only);
+        }
+    }
+
+    #[tokio::test]
+    async fn order_signed_by_another_wallet_is_rejected() {
+        let other = LocalWallet::new(&mut rand::thread_rng());
+        let mock = MockOpinion::start(other.address()).await;
+        let gw = OpinionMakerGateway::new(TEST_PRIVATE_KEY, &mock.url);
+
+        let err = gw.place_order(&quote(Side::Sell, 1)).await.unwrap_err();
+        assert!(err.to_string().contains("401"), "{}", err);
+        assert!(gw.cancel_all().await.is_err());
+        let state = mock.state();
+        assert_eq!(state.bad_signatures, 2);
+        assert!(state.orders.is_empty());
+    }
+
+    #[tokio::test]
+    async fn scripted_rate_limit_and_reject() {
+        let mock = MockOpinion::start(test_wallet().address()).await;
+        let gw = OpinionMakerGateway::new(TEST_PRIVATE_KEY, &mock.url);
+        mock.push(MockAction::Status(429));
+        mock.push(MockAction::Reject("insufficient balance".to_string()));
+
+        let err = gw.place_order(&quote(Side::Buy, 1)).await.unwrap_err();
+        assert!(err.to_string().contains("429"), "{}", err);
+        let err = gw.place_order(&quote(Side::Buy, 2)).await.unwrap_err();
+        assert!(err.to_string().contains("400"), "{}", err);
+        assert!(gw.place_order(&quote(Side::Buy, 3)).await.is_ok());
+        assert_eq!(mock.state().orders.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn scripted_latency_delays_response() {
+        let mock = MockOpinion::start(test_wallet().address()).await;
+        let gw = OpinionMakerGateway::new(TEST_PRIVATE_KEY, &mock.url);
+        mock.set_latency(Duration::from_millis(150));
+
+        let started = std::time::Instant::now();
+        gw.place_order(&quote(Side::Buy, 1)).await.unwrap();
+        assert!(started.elapsed() >= Duration::from_millis(150));
+    }
+
+    #[tokio::test]
+    async fn partial_fills_are_reported_with_trace_id() {
+        let mock = MockOpinion::start(test_wallet().address()).await;
+        let gw = OpinionMakerGateway::new(TEST_PRIVATE_KEY, &mock.url);
+        let order_id = gw.place_order(&quote(Side::Sell, 0x42)).await.unwrap();
+
+        mock.fill(&order_id, dec!(20));
+        let fills = gw.fills(0).await.unwrap();
+        assert_eq!(fills.len(), 1);
+        assert_eq!((fills[0].side, fills[0].size, fills[0].trace_id), (Side::Sell, dec!(20), 0x42));
+        assert_eq!(gw.open_orders().await.unwrap()[0].filled, dec!(20));
+
+        // 剩余部分成交后订单不再挂着
+        mock.fill(&order_id, dec!(100));
+        assert_eq!(gw.fills(0).await.unwrap()[1].size, dec!(30));
+        assert!(gw.open_orders().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn cancel_single_and_all() {
+        let mock = MockOpinion::start(test_wallet().address()).await;
+        let gw = OpinionMakerGateway::new(TEST_PRIVATE_KEY, &mock.url);
+        let first = gw.place_order(&quote(Side::Buy, 1)).await.unwrap();
+        gw.place_order(&quote(Side::Sell, 2)).await.unwrap();
+        gw.place_order(&quote(Side::Sell, 3)).await.unwrap();
+
+        gw.cancel_order(&first).await.unwrap();
+        assert!(gw.cancel_order(&first).await.is_err());
+        assert_eq!(mock.state().orders.len(), 2);
+
+        gw.cancel_all().await.unwrap();
+        let state = mock.state();
+        assert!(state.orders.is_empty());
+        assert_eq!(state.cancel_all_calls, 1);
+        assert_eq!(state.bad_signatures, 0);
+    }
+}
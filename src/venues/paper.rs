@@ -0,0 +1,427 @@
+// File: src/venues/paper.rs
+// 模拟盘 (Paper Trading)：与真实网关实现同一个 ExchangeAdapter，订单只存在于内存，不动用任何资金
+// 成交规则以参考市场 (Polymarket) 的盘口为准：
+//   - 下单时已穿过参考盘口的对手价：按挂单价立即成交 (对冲单是 IOC，未成交部分直接取消)
+//   - 挂单之后参考盘口越过挂单价 (买单: 参考卖一 < 挂单价；卖单: 参考买一 > 挂单价)：按挂单价全部成交
+//     只是触及 (卖一 == 买价) 不成交：同价位前面还有排队，不知道是否轮到我们 (与回测 QueueFillModel 的击穿规则一致)
+// 成交记入模拟账户，并以 InventoryUpdate 发布到 [paper] fill_bind (引擎在模拟盘模式下额外订阅该端点)
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tracing::info;
+
+use crate::config::AppConfig;
+use crate::core::{Exchange, OrderBookUpdate, Side, TradeSignal, LOGIC_TAG_HEDGE};
+use crate::gateway::fill_feed::inventory_update;
+use crate::infrastructure::logging::TraceId;
+use crate::infrastructure::messaging::{ZmqPublisher, ZmqSubscriber};
+use crate::infrastructure::metrics::DECODE_FAILURES;
+use crate::venues::polymarket::parse_asset_id;
+use crate::venues::{client_order_id, json_decimal, json_side, trace_id_from_client_order_id, Balance, ExchangeAdapter, Fill, OpenOrder, SignedOrder, VenueResult};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PaperConfig {
+    pub enabled: bool,
+    pub fill_bind: String,    // 执行层发布模拟成交 (PUB bind)
+    pub fill_connect: String, // 引擎订阅模拟成交 (SUB connect)
+    pub fee_bps: u32,         // 模拟手续费 (按成交额)
+    pub initial_usd: Decimal, // 每个模拟场馆的初始 USDC
+}
+
+impl Default for PaperConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            fill_bind: "tcp://*:5557".to_string(),
+            fill_connect: "tcp://localhost:5557".to_string(),
+            fee_bps: 0,
+            initial_usd: dec!(10000),
+        }
+    }
+}
+
+struct PaperOrder {
+    open: OpenOrder,
+    ref_id: u64, // 撮合所依据的参考盘口
+    trace_id: u64,
+}
+
+#[derive(Default)]
+struct PaperAccount {
+    orders: Vec<PaperOrder>,
+    tops: HashMap<u64, (Decimal, Decimal)>, // 参考盘口 symbol_id -> (买一, 卖一)
+    fills: Vec<Fill>,
+    cash: Decimal,
+    next_id: u64,
+}
+
+impl PaperAccount {
+    // 参考盘口已越过挂单价的订单按挂单价全部成交，返回新增成交
+    // taker_order: 刚提交的订单，触及对手价即成交 (吃单)；其余是挂着的单，必须被击穿
+    fn match_orders(&mut self, ref_id: u64, fee_rate: Decimal, taker_order: Option<&str>) -> Vec<Fill> {
+        let Some(&(best_bid, best_ask)) = self.tops.get(&ref_id) else { return Vec::new() };
+        let now_ms = chrono::Utc::now().timestamp_millis();
+        let fill_seq = self.fills.len();
+        let mut new_fills = Vec::new();
+        self.orders.retain(|o| {
+            let crossed = o.ref_id == ref_id && if taker_order == Some(o.open.order_id.as_str()) {
+                crosses(o.open.side, o.open.price, best_bid, best_ask)
+            } else {
+                trades_through(o.open.side, o.open.price, best_bid, best_ask)
+            };
+            if crossed {
+                let size = o.open.size - o.open.filled;
+                new_fills.push(Fill {
+                    fill_id: format!("paper-fill-{}", fill_seq + new_fills.len() + 1),
+                    order_id: o.open.order_id.clone(),
+                    symbol_id: o.open.symbol_id,
+                    side: o.open.side,
+                    price: o.open.price,
+                    size,
+                    fee: (o.open.price * size * fee_rate).round_dp(6),
+                    timestamp_ms: now_ms,
+                    trace_id: o.trace_id,
+                });
+            }
+            !crossed
+        });
+
+        for fill in &new_fills {
+            let gross = fill.price * fill.size;
+            match fill.side {
+                Side::Buy => self.cash -= gross + fill.fee,
+                Side::Sell => self.cash += gross - fill.fee,
+            }
+        }
+        self.fills.extend(new_fills.iter().cloned());
+        new_fills
+    }
+}
+
+// 辅助函数: 限价单是否穿过参考盘口 (买价 >= 卖一 / 卖价 <= 买一)
+fn crosses(side: Side, price: Decimal, best_bid: Decimal, best_ask: Decimal) -> bool {
+    match side {
+        Side::Buy => !best_ask.is_zero() && best_ask <= price,
+        Side::Sell => !best_bid.is_zero() && best_bid >= price,
+    }
+}
+
+// 辅助函数: 参考盘口是否越过挂单价 (卖一 < 买价 / 买一 > 卖价)，挂单成交的条件
+fn trades_through(side: Side, price: Decimal, best_bid: Decimal, best_ask: Decimal) -> bool {
+    match side {
+        Side::Buy => !best_ask.is_zero() && best_ask < price,
+        Side::Sell => !best_bid.is_zero() && best_bid > price,
+    }
+}
+
+pub struct PaperExchange {
+    exchange: Exchange,
+    ref_ids: HashMap<u64, u64>, // 下单 symbol_id -> 参考盘口 symbol_id
+    fee_rate: Decimal,
+    fill_pub: ZmqPublisher,
+    account: Mutex<PaperAccount>,
+}
+
+impl PaperExchange {
+    fn new(exchange: Exchange, ref_ids: HashMap<u64, u64>, paper: &PaperConfig, fill_pub: ZmqPublisher) -> Self {
+        Self {
+            exchange,
+            ref_ids,
+            fee_rate: Decimal::from(paper.fee_bps) / dec!(10000),
+            fill_pub,
+            account: Mutex::new(PaperAccount { cash: paper.initial_usd, ..Default::default() }),
+        }
+    }
+
+    /// [行情回调] 更新参考盘口并撮合挂单
+    pub fn on_book(&self, update: &OrderBookUpdate) {
+        if update.exchange != Exchange::Polymarket {
+            return;
+        }
+        let best_bid = update.bids.first().map(|&(p, _)| p).unwrap_or(Decimal::ZERO);
+        let best_ask = update.asks.first().map(|&(p, _)| p).unwrap_or(Decimal::ZERO);
+        let fills = {
+            let mut account = self.account.lock().unwrap();
+            account.tops.insert(update.symbol_id, (best_bid, best_ask));
+            account.match_orders(update.symbol_id, self.fee_rate, None)
+        };
+        self.publish(&fills);
+    }
+
+    fn publish(&self, fills: &[Fill]) {
+        for fill in fills {
+            info!(trace_id = %TraceId(fill.trace_id), exchange = ?self.exchange, order_id = %fill.order_id,
+                symbol_id = fill.symbol_id, side = ?fill.side, price = %fill.price, size = %fill.size, "💵 Paper fill");
+            self.fill_pub.send_inventory_update(&inventory_update(self.exchange, fill));
+        }
+    }
+}
+
+#[async_trait]
+impl ExchangeAdapter for PaperExchange {
+    fn exchange(&self) -> Exchange {
+        self.exchange
+    }
+
+    async fn create_signed_order(&self, signal: &TradeSignal) -> VenueResult<SignedOrder> {
+        if !self.ref_ids.contains_key(&signal.symbol_id) {
+            return Err(format!("Unknown paper symbol_id {}", signal.symbol_id).into());
+        }
+        if signal.price <= Decimal::ZERO {
+            return Err(format!("Invalid price {}", signal.price).into());
+        }
+
+        // 与 Polymarket 网关相同的换算：shares = USD / price，精度 2 位
+        let order_id_tag = client_order_id(signal.trace_id, rand::random::<u64>());
+        let payload = serde_json::json!({
+            "marketId": signal.symbol_id,
+            "side": if signal.side == Side::Buy { "BUY" } else { "SELL" },
+            "price": signal.price.to_string(),
+            "size": (signal.size_usd / signal.price).round_dp(2).to_string(),
+            "ioc": signal.logic_tag == LOGIC_TAG_HEDGE,
+            "postOnly": signal.post_only,
+            "clientOrderId": order_id_tag
+        });
+
+        Ok(SignedOrder {
+            exchange: self.exchange,
+            payload,
+            order_id_tag,
+            trace_id: signal.trace_id,
+            stamps: signal.stamps,
+        })
+    }
+
+    async fn submit_order(&self, signed_order: SignedOrder) -> VenueResult<String> {
+        let p = &signed_order.payload;
+        let (Some(symbol_id), Some(side), Some(price), Some(size)) =
+            (p["marketId"].as_u64(), json_side(&p["side"]), json_decimal(&p["price"]), json_decimal(&p["size"]))
+        else {
+            return Err("Malformed paper order".into());
+        };
+        let ref_id = *self.ref_ids.get(&symbol_id)
+            .ok_or_else(|| format!("Unknown paper symbol_id {}", symbol_id))?;
+
+        let (order_id, fills) = {
+            let mut account = self.account.lock().unwrap();
+            // post-only：按当前参考盘口会立即成交的订单直接拒绝
+            let post_only = p["postOnly"].as_bool().unwrap_or(false);
+            if post_only && account.tops.get(&ref_id).is_some_and(|&(best_bid, best_ask)| crosses(side, price, best_bid, best_ask)) {
+                return Err(format!("Post-only order would cross: {:?} @ {}", side, price).into());
+            }
+            account.next_id += 1;
+            let order_id = format!("paper-{}", account.next_id);
+            account.orders.push(PaperOrder {
+                open: OpenOrder { order_id: order_id.clone(), symbol_id, side, price, size, filled: Decimal::ZERO },
+                ref_id,
+                trace_id: trace_id_from_client_order_id(&p["clientOrderId"]),
+            });
+            let fills = account.match_orders(ref_id, self.fee_rate, Some(&order_id));
+            // IOC：没有立即成交的部分直接取消
+            if p["ioc"].as_bool().unwrap_or(false) {
+                account.orders.retain(|o| o.open.order_id != order_id);
+            }
+            (order_id, fills)
+        };
+        self.publish(&fills);
+        Ok(order_id)
+    }
+
+    async fn cancel_order(&self, order_id: &str) -> VenueResult<()> {
+        let mut account = self.account.lock().unwrap();
+        let before = account.orders.len();
+        account.orders.retain(|o| o.open.order_id != order_id);
+        if account.orders.len() == before {
+            return Err(format!("Unknown paper order {}", order_id).into());
+        }
+        Ok(())
+    }
+
+    async fn cancel_all(&self) -> VenueResult<()> {
+        self.account.lock().unwrap().orders.clear();
+        Ok(())
+    }
+
+    async fn open_orders(&self) -> VenueResult<Vec<OpenOrder>> {
+        Ok(self.account.lock().unwrap().orders.iter().map(|o| o.open.clone()).collect())
+    }
+
+    async fn balances(&self) -> VenueResult<Vec<Balance>> {
+        let account = self.account.lock().unwrap();
+        // 买单占用 USDC (卖单占用的是份额，这里不单独列出)
+        let locked: Decimal = account.orders.iter()
+            .filter(|o| o.open.side == Side::Buy)
+            .map(|o| o.open.price * (o.open.size - o.open.filled))
+            .sum();
+        Ok(vec![Balance { asset: "USDC".to_string(), available: account.cash - locked, locked }])
+    }
+
+    async fn fills(&self, since_ms: i64) -> VenueResult<Vec<Fill>> {
+        let account = self.account.lock().unwrap();
+        Ok(account.fills.iter().filter(|f| f.timestamp_ms >= since_ms).cloned().collect())
+    }
+}
+
+/// 创建模拟盘场馆 (Opinion 报价 + Polymarket 对冲)，并在后台线程订阅参考行情驱动撮合
+pub fn start_paper_venues(cfg: &AppConfig) -> HashMap<Exchange, Arc<dyn ExchangeAdapter>> {
+    let fill_pub = ZmqPublisher::new(&cfg.paper.fill_bind);
+
+    // Opinion 市场按配置映射到参考盘口；Polymarket 对冲单本身就在参考盘口上
+    let pairs: Vec<(u64, u64)> = cfg.markets.iter()
+        .filter_map(|m| Some((m.opinion_market_id, parse_asset_id(&m.poly_asset_id)?.low_u64())))
+        .collect();
+    let opinion = Arc::new(PaperExchange::new(Exchange::OpinionLabs, pairs.iter().copied().collect(), &cfg.paper, fill_pub.clone()));
+    let poly = Arc::new(PaperExchange::new(Exchange::Polymarket, pairs.iter().map(|&(_, r)| (r, r)).collect(), &cfg.paper, fill_pub));
+
+    let venues = [opinion.clone(), poly.clone()];
+    let endpoint = cfg.bus.market_data_connect.clone();
+    std::thread::spawn(move || {
+        let sub = ZmqSubscriber::new(&endpoint, "MD");
+        loop {
+            let Some(msg) = sub.recv_raw_bytes() else { continue };
+            match bincode::deserialize::<OrderBookUpdate>(&msg) {
+                Ok(update) => venues.iter().for_each(|v| v.on_book(&update)),
+                Err(_) => DECODE_FAILURES.with_label_values(&["MD"]).inc(),
+            }
+        }
+    });
+
+    info!(markets = pairs.len(), fill_bind = %cfg.paper.fill_bind, "📝 Paper trading: orders are simulated, no real capital at risk");
+    HashMap::from([
+        (Exchange::OpinionLabs, opinion as Arc<dyn ExchangeAdapter>),
+        (Exchange::Polymarket, poly as Arc<dyn ExchangeAdapter>),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{StageStamps, LOGIC_TAG_QUOTE};
+    use crate::testing::free_port;
+
+    const OPINION_ID: u64 = 7;
+    const REF_ID: u64 = 123;
+
+    fn venue(exchange: Exchange, symbol_id: u64) -> PaperExchange {
+        let paper = PaperConfig { fee_bps: 10, initial_usd: dec!(1000), ..Default::default() };
+        let fill_pub = ZmqPublisher::new(&format!("tcp://127.0.0.1:{}", free_port()));
+        PaperExchange::new(exchange, HashMap::from([(symbol_id, REF_ID)]), &paper, fill_pub)
+    }
+
+    fn book(best_bid: Decimal, best_ask: Decimal) -> OrderBookUpdate {
+        OrderBookUpdate {
+            exchange: Exchange::Polymarket,
+            symbol_id: REF_ID,
+            timestamp_ns: 0,
+            trace_id: 0,
+            bids: [(best_bid, dec!(100))].into_iter().collect(),
+            asks: [(best_ask, dec!(100))].into_iter().collect(),
+            stamps: StageStamps::default(),
+        }
+    }
+
+    async fn place(v: &PaperExchange, side: Side, price: Decimal, logic_tag: u8, post_only: bool) -> VenueResult<String> {
+        let signal = TradeSignal {
+            strategy_id: 1,
+            target_exchange: v.exchange,
+            symbol_id: *v.ref_ids.keys().next().unwrap(),
+            side,
+            price,
+            size_usd: dec!(10),
+            logic_tag,
+            post_only,
+            level: 0,
+            created_at_ns: 0,
+            trace_id: 42,
+            stamps: StageStamps::default(),
+        };
+        let order = v.create_signed_order(&signal).await?;
+        v.submit_order(order).await
+    }
+
+    #[tokio::test]
+    async fn resting_order_fills_only_when_the_reference_trades_through() {
+        let v = venue(Exchange::OpinionLabs, OPINION_ID);
+        v.on_book(&book(dec!(0.48), dec!(0.52)));
+        let order_id = place(&v, Side::Buy, dec!(0.50), LOGIC_TAG_QUOTE, false).await.unwrap();
+
+        // 参考卖一触及买价：不成交
+        v.on_book(&book(dec!(0.47), dec!(0.50)));
+        assert_eq!(v.open_orders().await.unwrap().len(), 1);
+        assert!(v.fills(0).await.unwrap().is_empty());
+
+        // 卖一跌破买价：按挂单价全部成交 (20 份)，扣 10bp 手续费
+        v.on_book(&book(dec!(0.47), dec!(0.49)));
+        let fills = v.fills(0).await.unwrap();
+        assert_eq!(fills.len(), 1);
+        let f = &fills[0];
+        assert_eq!((f.fill_id.as_str(), f.order_id.as_str(), f.side, f.price, f.size, f.fee, f.trace_id),
+            ("paper-fill-1", order_id.as_str(), Side::Buy, dec!(0.50), dec!(20), dec!(0.01), 42));
+        assert!(v.open_orders().await.unwrap().is_empty());
+        assert_eq!(v.balances().await.unwrap()[0].available, dec!(1000) - dec!(10) - dec!(0.01));
+    }
+
+    #[tokio::test]
+    async fn order_crossing_on_submit_fills_immediately() {
+        let v = venue(Exchange::OpinionLabs, OPINION_ID);
+        v.on_book(&book(dec!(0.48), dec!(0.52)));
+        // 卖价 == 参考买一：提交时就是吃单
+        place(&v, Side::Sell, dec!(0.48), LOGIC_TAG_QUOTE, false).await.unwrap();
+        let fills = v.fills(0).await.unwrap();
+        assert_eq!((fills.len(), fills[0].side, fills[0].price), (1, Side::Sell, dec!(0.48)));
+        assert!(v.open_orders().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn ioc_hedge_never_rests() {
+        let v = venue(Exchange::Polymarket, REF_ID);
+        v.on_book(&book(dec!(0.48), dec!(0.52)));
+        place(&v, Side::Buy, dec!(0.53), LOGIC_TAG_HEDGE, false).await.unwrap();
+        assert_eq!(v.fills(0).await.unwrap().len(), 1);
+
+        // 没有穿价的 IOC 直接取消：之后行情越过也不会成交
+        place(&v, Side::Buy, dec!(0.50), LOGIC_TAG_HEDGE, false).await.unwrap();
+        assert!(v.open_orders().await.unwrap().is_empty());
+        v.on_book(&book(dec!(0.40), dec!(0.45)));
+        assert_eq!(v.fills(0).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn cancelled_orders_do_not_fill() {
+        let v = venue(Exchange::OpinionLabs, OPINION_ID);
+        v.on_book(&book(dec!(0.48), dec!(0.52)));
+        let first = place(&v, Side::Buy, dec!(0.50), LOGIC_TAG_QUOTE, false).await.unwrap();
+        place(&v, Side::Sell, dec!(0.51), LOGIC_TAG_QUOTE, false).await.unwrap();
+        place(&v, Side::Sell, dec!(0.55), LOGIC_TAG_QUOTE, false).await.unwrap();
+
+        v.cancel_order(&first).await.unwrap();
+        assert!(v.cancel_order(&first).await.is_err(), "second cancel of the same order");
+        assert_eq!(v.open_orders().await.unwrap().len(), 2);
+        v.cancel_all().await.unwrap();
+        assert!(v.open_orders().await.unwrap().is_empty());
+
+        v.on_book(&book(dec!(0.60), dec!(0.62)));
+        v.on_book(&book(dec!(0.40), dec!(0.42)));
+        assert!(v.fills(0).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn post_only_order_that_would_cross_is_rejected() {
+        let v = venue(Exchange::OpinionLabs, OPINION_ID);
+        v.on_book(&book(dec!(0.48), dec!(0.52)));
+        let err = place(&v, Side::Buy, dec!(0.52), LOGIC_TAG_QUOTE, true).await.unwrap_err();
+        assert!(err.to_string().contains("Post-only"), "{}", err);
+        assert!(place(&v, Side::Sell, dec!(0.48), LOGIC_TAG_QUOTE, true).await.is_err());
+        assert!(v.open_orders().await.unwrap().is_empty());
+        assert!(v.fills(0).await.unwrap().is_empty());
+
+        // 不穿价的 post-only 正常挂单，占用买单资金
+        place(&v, Side::Buy, dec!(0.50), LOGIC_TAG_QUOTE, true).await.unwrap();
+        let balance = &v.balances().await.unwrap()[0];
+        assert_eq!((balance.locked, balance.available), (dec!(10), dec!(990)));
+    }
+}
@@ -0,0 +1,496 @@
+// File: src/venues/polymarket.rs
+// Polymarket CLOB 执行适配器 (用于对冲：把 Opinion 上被动成交的风险甩到更深的 Polymarket 订单簿)
+use async_trait::async_trait;
+use base64::Engine;
+use ethers::prelude::*;
+use hmac::{Hmac, Mac};
+use reqwest::header::{HeaderMap, HeaderValue};
+use rust_decimal::Decimal;
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::core::{Exchange, Side, TradeSignal, LOGIC_TAG_HEDGE};
+use crate::venues::rules::MarketRules;
+use crate::venues::{client_order_id, json_decimal, json_side, send_recorded, Balance, ExchangeAdapter, Fill, OpenOrder, SignedOrder, VenueResult};
+
+const CHAIN_ID: u64 = 137;
+const CLOB_AUTH_MESSAGE: &str = "This message attests that I control the given wallet";
+
+// --- A. L1 认证结构 (EIP-712)：用私钥证明钱包所有权，换取 L2 API Key ---
+#[derive(Debug, Clone, Eip712, EthAbiType)]
+#[eip712(name = "ClobAuthDomain", version = "1", chain_id = 137)]
+pub struct ClobAuth {
+    pub address: Address,
+    pub timestamp: String,
+    pub nonce: U256,
+    pub message: String,
+}
+
+// --- B. CTF Exchange 订单结构 (EIP-712) ---
+// 字段名会被 derive 宏转为 camelCase (token_id -> tokenId)，与链上合约的 Order 类型一致
+#[derive(Debug, Clone, Eip712, EthAbiType)]
+#[eip712(
+    name = "Polymarket CTF Exchange",
+    version = "1",
+    chain_id = 137,
+    // ⚠️ 标准市场的 CTF Exchange；Neg-Risk 市场使用另一个合约地址
+    verifying_contract = "0x4bFb41d5B3570DeFd03C39a9A4D8dE6Bd8B8982E"
+)]
+pub struct CtfOrder {
+    pub salt: U256,
+    pub maker: Address,
+    pub signer: Address,
+    pub taker: Address,
+    pub token_id: U256,
+    pub maker_amount: U256,
+    pub taker_amount: U256,
+    pub expiration: U256,
+    pub nonce: U256,
+    pub fee_rate_bps: U256,
+    pub side: u8,
+    pub signature_type: u8,
+}
+
+/// L2 API 凭证 (通过 L1 签名派生)
+#[derive(Debug, Clone)]
+pub struct ApiCreds {
+    pub api_key: String,
+    pub secret: String,
+    pub passphrase: String,
+}
+
+impl ApiCreds {
+    /// 从环境变量读取 (POLY_API_KEY / POLY_API_SECRET / POLY_PASSPHRASE)
+    pub fn from_env() -> Option<Self> {
+        Some(Self {
+            api_key: std::env::var("POLY_API_KEY").ok()?,
+            secret: std::env::var("POLY_API_SECRET").ok()?,
+            passphrase: std::env::var("POLY_PASSPHRASE").ok()?,
+        })
+    }
+}
+
+/// Polymarket 的 Asset ID (Token ID) 既可能是十进制也可能是 0x 十六进制
+pub fn parse_asset_id(raw: &str) -> Option<U256> {
+    match raw.strip_prefix("0x") {
+        Some(hex) => U256::from_str_radix(hex, 16).ok(),
+        None => U256::from_dec_str(raw).ok(),
+    }
+}
+
+// 辅助函数: /data/trades 中的一笔成交 -> 我方成交
+// 我方是 taker 时取 taker_order_id 与 taker 的方向；我方是 maker 时 (trader_side = MAKER)
+// 取 maker_orders 中属于我方 (owner = API Key 或 maker_address = 钱包地址) 的条目，每个挂单各一笔
+fn parse_trade(t: &serde_json::Value, api_key: &str, address: &str) -> Vec<Fill> {
+    let Some(trade_id) = t["id"].as_str() else { return Vec::new() };
+    let match_time = t["match_time"].as_str().and_then(|s| i64::from_str(s).ok()).unwrap_or(0);
+    let fill = |order_id: &str, leg: &serde_json::Value, size: &serde_json::Value| -> Option<Fill> {
+        let price = json_decimal(&leg["price"])?;
+        let size = json_decimal(size)?;
+        let fee_bps = json_decimal(&leg["fee_rate_bps"]).unwrap_or_default();
+        Some(Fill {
+            fill_id: format!("{}:{}", trade_id, order_id),
+            order_id: order_id.to_string(),
+            symbol_id: parse_asset_id(leg["asset_id"].as_str()?)?.low_u64(),
+            side: json_side(&leg["side"])?,
+            price,
+            size,
+            fee: price * size * fee_bps / Decimal::from(10_000),
+            timestamp_ms: match_time * 1000,
+            trace_id: 0, // CLOB 成交回报不带客户端订单号，靠 order_id 与发送日志关联
+        })
+    };
+
+    if t["trader_side"].as_str() == Some("MAKER") {
+        let ours = |m: &&serde_json::Value| {
+            m["owner"].as_str() == Some(api_key)
+                || m["maker_address"].as_str().is_some_and(|a| a.eq_ignore_ascii_case(address))
+        };
+        t["maker_orders"].as_array().into_iter().flatten()
+            .filter(ours)
+            .filter_map(|m| fill(m["order_id"].as_str()?, m, &m["matched_amount"]))
+            .collect()
+    } else {
+        t["taker_order_id"].as_str()
+            .and_then(|order_id| fill(order_id, t, &t["size"]))
+            .into_iter()
+            .collect()
+    }
+}
+
+// 辅助函数: L2 请求签名 (secret 与签名均为 base64url)
+fn l2_signature(secret: &str, timestamp: &str, method: &str, path: &str, body: &str) -> VenueResult<String> {
+    let b64 = base64::engine::general_purpose::URL_SAFE;
+    let mut mac = Hmac::<Sha256>::new_from_slice(&b64.decode(secret)?)?;
+    mac.update(format!("{}{}{}{}", timestamp, method, path, body).as_bytes());
+    Ok(b64.encode(mac.finalize().into_bytes()))
+}
+
+// --- C. 执行网关 ---
+pub struct PolymarketClobGateway {
+    wallet: LocalWallet,
+    creds: ApiCreds,
+    http_client: reqwest::Client,
+    api_url: String,
+    // 内部 symbol_id (Token ID 低 64 位) -> 完整 Token ID，签名时必须使用完整 ID
+    token_ids: HashMap<u64, U256>,
+}
+
+impl PolymarketClobGateway {
+    pub fn new(private_key: &str, api_url: &str, creds: ApiCreds, asset_ids: &[String]) -> Self {
+        let wallet = private_key.parse::<LocalWallet>().unwrap()
+            .with_chain_id(CHAIN_ID);
+
+        let client = reqwest::Client::builder()
+            .tcp_nodelay(true)
+            .pool_max_idle_per_host(100)
+            .timeout(Duration::from_secs(2))
+            .build()
+            .expect("Failed to create HTTP client");
+
+        let token_ids = asset_ids.iter()
+            .filter_map(|raw| parse_asset_id(raw))
+            .map(|id| (id.low_u64(), id))
+            .collect();
+
+        Self {
+            wallet,
+            creds,
+            http_client: client,
+            api_url: api_url.to_string(),
+            token_ids,
+        }
+    }
+
+    /// 初始化执行通道：优先使用环境变量中的 API 凭证，否则用私钥走 L1 签名派生
+    pub async fn connect(private_key: &str, api_url: &str, asset_ids: &[String]) -> VenueResult<Self> {
+        let creds = match ApiCreds::from_env() {
+            Some(c) => c,
+            None => Self::derive_api_creds(private_key, api_url).await?,
+        };
+        Ok(Self::new(private_key, api_url, creds, asset_ids))
+    }
+
+    /// [L1] 用钱包签名派生 API 凭证 (GET /auth/derive-api-key)
+    /// 凭证不存在时可改用 POST /auth/api-key 创建
+    pub async fn derive_api_creds(private_key: &str, api_url: &str) -> VenueResult<ApiCreds> {
+        let wallet = private_key.parse::<LocalWallet>()?.with_chain_id(CHAIN_ID);
+        let timestamp = chrono::Utc::now().timestamp().to_string();
+
+        let auth = ClobAuth {
+            address: wallet.address(),
+            timestamp: timestamp.clone(),
+            nonce: U256::zero(),
+            message: CLOB_AUTH_MESSAGE.to_string(),
+        };
+        let signature = wallet.sign_typed_data(&auth).await?;
+
+        let resp = reqwest::Client::new()
+            .get(format!("{}/auth/derive-api-key", api_url))
+            .header("POLY_ADDRESS", format!("{:?}", wallet.address()))
+            .header("POLY_SIGNATURE", format!("0x{}", signature))
+            .header("POLY_TIMESTAMP", timestamp)
+            .header("POLY_NONCE", "0")
+            .send()
+            .await?;
+
+        if !resp.status().is_success() {
+            return Err(format!("HTTP {}", resp.status()).into());
+        }
+
+        let v: serde_json::Value = resp.json().await?;
+        Ok(ApiCreds {
+            api_key: v["apiKey"].as_str().ok_or("missing apiKey")?.to_string(),
+            secret: v["secret"].as_str().ok_or("missing secret")?.to_string(),
+            passphrase: v["passphrase"].as_str().ok_or("missing passphrase")?.to_string(),
+        })
+    }
+
+    // [L2] HMAC-SHA256(secret, timestamp + method + path + body)，base64url 编码
+    // 注意：path 不包含 query string
+    fn l2_headers(&self, method: &str, path: &str, body: &str) -> VenueResult<HeaderMap> {
+        let timestamp = chrono::Utc::now().timestamp().to_string();
+        let signature = l2_signature(&self.creds.secret, &timestamp, method, path, body)?;
+
+        let mut headers = HeaderMap::new();
+        headers.insert("POLY_ADDRESS", HeaderValue::from_str(&format!("{:?}", self.wallet.address()))?);
+        headers.insert("POLY_SIGNATURE", HeaderValue::from_str(&signature)?);
+        headers.insert("POLY_TIMESTAMP", HeaderValue::from_str(&timestamp)?);
+        headers.insert("POLY_API_KEY", HeaderValue::from_str(&self.creds.api_key)?);
+        headers.insert("POLY_PASSPHRASE", HeaderValue::from_str(&self.creds.passphrase)?);
+        Ok(headers)
+    }
+
+    // 带 L2 签名的请求，body 必须与参与签名的字符串完全一致
+    async fn l2_request(&self, method: reqwest::Method, path: &str, query: &[(&str, String)], body: Option<String>) -> VenueResult<serde_json::Value> {
+        let body = body.unwrap_or_default();
+        let headers = self.l2_headers(method.as_str(), path, &body)?;
+
+        let mut req = self.http_client
+            .request(method, format!("{}{}", self.api_url, path))
+            .headers(headers)
+            .query(query);
+        if !body.is_empty() {
+            req = req.header("Content-Type", "application/json").body(body);
+        }
+
+        let resp = send_recorded(Exchange::Polymarket, req).await?;
+        if !resp.status().is_success() {
+            return Err(format!("HTTP {}: {}", resp.status(), resp.text().await.unwrap_or_default()).into());
+        }
+        Ok(resp.json().await.unwrap_or(serde_json::Value::Null))
+    }
+}
+
+#[async_trait]
+impl ExchangeAdapter for PolymarketClobGateway {
+    fn exchange(&self) -> Exchange {
+        Exchange::Polymarket
+    }
+
+    async fn create_signed_order(&self, signal: &TradeSignal) -> VenueResult<SignedOrder> {
+        let token_id = *self.token_ids.get(&signal.symbol_id)
+            .ok_or_else(|| format!("Unknown Polymarket symbol_id {}", signal.symbol_id))?;
+
+        if signal.price <= Decimal::ZERO {
+            return Err(format!("Invalid price {}", signal.price).into());
+        }
+
+        // CLOB 以 "份额" 计量：shares = USD / price，精度 2 位
+        let shares = (signal.size_usd / signal.price).round_dp(2);
+        let notional = (shares * signal.price).round_dp(4);
+
+        // BUY: 付出 USDC 换取份额；SELL: 付出份额换取 USDC (均为 6 位精度)
+        let (maker_amount, taker_amount) = match signal.side {
+            Side::Buy => (notional, shares),
+            Side::Sell => (shares, notional),
+        };
+
+        let order_struct = CtfOrder {
+            salt: U256::from(rand::random::<u64>()),
+            maker: self.wallet.address(),
+            signer: self.wallet.address(),
+            taker: Address::zero(),
+            token_id,
+            maker_amount: ethers::utils::parse_units(maker_amount, 6)?.into(),
+            taker_amount: ethers::utils::parse_units(taker_amount, 6)?.into(),
+            expiration: U256::zero(),
+            nonce: U256::zero(),
+            fee_rate_bps: U256::zero(),
+            side: if signal.side == Side::Buy { 0 } else { 1 },
+            signature_type: 0, // EOA
+        };
+
+        // 签名 (CPU 密集)
+        let signature = self.wallet.sign_typed_data(&order_struct).await?;
+
+        let is_hedge = signal.logic_tag == LOGIC_TAG_HEDGE;
+        let payload = serde_json::json!({
+            "order": {
+                "salt": order_struct.salt.as_u64(),
+                "maker": format!("{:?}", order_struct.maker),
+                "signer": format!("{:?}", order_struct.signer),
+                "taker": format!("{:?}", order_struct.taker),
+                "tokenId": order_struct.token_id.to_string(),
+                "makerAmount": order_struct.maker_amount.to_string(),
+                "takerAmount": order_struct.taker_amount.to_string(),
+                "expiration": order_struct.expiration.to_string(),
+                "nonce": order_struct.nonce.to_string(),
+                "feeRateBps": order_struct.fee_rate_bps.to_string(),
+                "side": if signal.side == Side::Buy { "BUY" } else { "SELL" },
+                "signatureType": order_struct.signature_type,
+                "signature": format!("0x{}", signature),
+            },
+            "owner": self.creds.api_key,
+            // 对冲单吃流动性：FAK (Fill-And-Kill = IOC)，未成交部分立即撤销
+            "orderType": if is_hedge { "FAK" } else { "GTC" },
+            // post-only 只对挂单 (GTC) 有效
+            "postOnly": signal.post_only && !is_hedge
+        });
+
+        Ok(SignedOrder {
+            exchange: Exchange::Polymarket,
+            payload,
+            order_id_tag: client_order_id(signal.trace_id, order_struct.salt),
+            trace_id: signal.trace_id,
+            stamps: signal.stamps,
+        })
+    }
+
+    async fn submit_order(&self, signed_order: SignedOrder) -> VenueResult<String> {
+        let v = self.l2_request(reqwest::Method::POST, "/order", &[], Some(signed_order.payload.to_string())).await?;
+
+        if v["success"].as_bool() == Some(false) {
+            return Err(format!("Order rejected: {}", v["errorMsg"]).into());
+        }
+        Ok(v["orderID"].as_str().map(|s| s.to_string()).unwrap_or(signed_order.order_id_tag))
+    }
+
+    async fn cancel_order(&self, order_id: &str) -> VenueResult<()> {
+        let body = serde_json::json!({ "orderID": order_id }).to_string();
+        self.l2_request(reqwest::Method::DELETE, "/order", &[], Some(body)).await?;
+        Ok(())
+    }
+
+    async fn cancel_all(&self) -> VenueResult<()> {
+        self.l2_request(reqwest::Method::DELETE, "/cancel-all", &[], None).await?;
+        Ok(())
+    }
+
+    async fn open_orders(&self) -> VenueResult<Vec<OpenOrder>> {
+        let v = self.l2_request(reqwest::Method::GET, "/data/orders", &[], None).await?;
+        // 新版接口返回 { data: [...], next_cursor }，旧版直接返回数组
+        let arr = v["data"].as_array().or_else(|| v.as_array()).cloned().unwrap_or_default();
+
+        Ok(arr.iter().filter_map(|o| {
+            let original = json_decimal(&o["original_size"])?;
+            Some(OpenOrder {
+                order_id: o["id"].as_str()?.to_string(),
+                symbol_id: parse_asset_id(o["asset_id"].as_str()?)?.low_u64(),
+                side: json_side(&o["side"])?,
+                price: json_decimal(&o["price"])?,
+                size: original,
+                filled: json_decimal(&o["size_matched"]).unwrap_or_default(),
+            })
+        }).collect())
+    }
+
+    async fn balances(&self) -> VenueResult<Vec<Balance>> {
+        let query = [("asset_type", "COLLATERAL".to_string()), ("signature_type", "0".to_string())];
+        let v = self.l2_request(reqwest::Method::GET, "/balance-allowance", &query, None).await?;
+
+        // 余额以 USDC 最小单位 (6 位) 返回
+        let raw = json_decimal(&v["balance"]).unwrap_or_default();
+        Ok(vec![Balance {
+            asset: "USDC".to_string(),
+            available: raw / Decimal::from(1_000_000),
+            locked: Decimal::ZERO,
+        }])
+    }
+
+    async fn fills(&self, since_ms: i64) -> VenueResult<Vec<Fill>> {
+        // after 以秒为单位：往前多取一秒，保证 since_ms 所在的整秒都在结果里 (不依赖 after 是否包含边界)
+        // 重复返回的成交由成交监听器按成交 ID 去重
+        let query = [("after", (since_ms / 1000 - 1).to_string())];
+        let v = self.l2_request(reqwest::Method::GET, "/data/trades", &query, None).await?;
+        let arr = v["data"].as_array().or_else(|| v.as_array()).cloned().unwrap_or_default();
+
+        let address = format!("{:?}", self.wallet.address());
+        Ok(arr.iter()
+            .flat_map(|t| parse_trade(t, &self.creds.api_key, &address))
+            .filter(|f| f.timestamp_ms >= since_ms)
+            .collect())
+    }
+
+    async fn market_rules(&self, symbol_id: u64) -> VenueResult<Option<MarketRules>> {
+        let token_id = self.token_ids.get(&symbol_id)
+            .ok_or_else(|| format!("Unknown Polymarket symbol_id {}", symbol_id))?;
+        // 订单簿快照里带有当前 tick_size 与 min_order_size (份额)；CLOB 全价格区间同一个 tick
+        let v = self.l2_request(reqwest::Method::GET, "/book", &[("token_id", token_id.to_string())], None).await?;
+        let Some(tick) = json_decimal(&v["tick_size"]) else { return Ok(None) };
+        let min_size = json_decimal(&v["min_order_size"]).unwrap_or_default();
+        Ok(Some(MarketRules::uniform(tick, min_size)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{StageStamps, LOGIC_TAG_QUOTE};
+    use crate::testing::{test_wallet, TEST_PRIVATE_KEY};
+    use rust_decimal_macros::dec;
+
+    fn gateway() -> PolymarketClobGateway {
+        let creds = ApiCreds { api_key: "key-1".to_string(), secret: "dGVzdC1zZWNyZXQtMDEyMzQ1Njc4OQ==".to_string(), passphrase: "p".to_string() };
+        PolymarketClobGateway::new(TEST_PRIVATE_KEY, "http://127.0.0.1:9", creds, &["123".to_string()])
+    }
+
+    fn signal(side: Side, price: Decimal, size_usd: Decimal) -> TradeSignal {
+        TradeSignal {
+            strategy_id: 1,
+            target_exchange: Exchange::Polymarket,
+            symbol_id: 123,
+            side,
+            price,
+            size_usd,
+            logic_tag: LOGIC_TAG_HEDGE,
+            post_only: false,
+            level: 0,
+            created_at_ns: 0,
+            trace_id: 0,
+            stamps: StageStamps::default(),
+        }
+    }
+
+    #[test]
+    fn l2_signature_matches_reference_hmac() {
+        // 参考值：HMAC-SHA256(base64url_decode(secret), "1700000000POST/order{\"a\":1}")，base64url 编码
+        let sig = l2_signature("dGVzdC1zZWNyZXQtMDEyMzQ1Njc4OQ==", "1700000000", "POST", "/order", r#"{"a":1}"#).unwrap();
+        assert_eq!(sig, "CuUROkpGVIaxtarL3_ikQ8_9IxSokPZw_kotpK_v3vQ=");
+        assert!(l2_signature("not base64!", "1", "GET", "/", "").is_err());
+    }
+
+    #[tokio::test]
+    async fn order_amounts_are_in_six_decimal_units() {
+        let gw = gateway();
+        // $50 @ 0.45 -> 111.11 份，名义金额 49.9995 USDC
+        let buy = gw.create_signed_order(&signal(Side::Buy, dec!(0.45), dec!(50))).await.unwrap();
+        let order = &buy.payload["order"];
+        assert_eq!((order["makerAmount"].as_str(), order["takerAmount"].as_str()), (Some("49999500"), Some("111110000")));
+        assert_eq!(order["side"], "BUY");
+        assert_eq!(order["tokenId"], "123");
+        assert_eq!(order["maker"], format!("{:?}", test_wallet().address()));
+        assert_eq!(buy.payload["orderType"], "FAK");
+
+        let sell = gw.create_signed_order(&signal(Side::Sell, dec!(0.45), dec!(50))).await.unwrap();
+        let order = &sell.payload["order"];
+        assert_eq!((order["makerAmount"].as_str(), order["takerAmount"].as_str()), (Some("111110000"), Some("49999500")));
+
+        assert!(gw.create_signed_order(&signal(Side::Buy, dec!(0), dec!(50))).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn post_only_is_sent_for_resting_quotes_but_never_for_hedges() {
+        let gw = gateway();
+        let quote = TradeSignal { logic_tag: LOGIC_TAG_QUOTE, post_only: true, ..signal(Side::Buy, dec!(0.45), dec!(50)) };
+        let signed = gw.create_signed_order(&quote).await.unwrap();
+        assert_eq!((&signed.payload["orderType"], &signed.payload["postOnly"]), (&"GTC".into(), &true.into()));
+
+        // FAK 对冲单即使带了 post_only 也不能发 postOnly (会被场馆拒绝或永远不成交)
+        let hedge = TradeSignal { post_only: true, ..signal(Side::Buy, dec!(0.45), dec!(50)) };
+        let signed = gw.create_signed_order(&hedge).await.unwrap();
+        assert_eq!((&signed.payload["orderType"], &signed.payload["postOnly"]), (&"FAK".into(), &false.into()));
+    }
+
+    #[test]
+    fn trades_are_parsed_from_our_side() {
+        let address = format!("{:?}", test_wallet().address());
+        let taker = serde_json::json!({
+            "id": "t1", "taker_order_id": "our-taker", "asset_id": "123", "side": "BUY",
+            "price": "0.45", "size": "100", "fee_rate_bps": "100", "match_time": "1700000000",
+            "trader_side": "TAKER",
+            "maker_orders": [{ "order_id": "theirs", "owner": "other", "asset_id": "123", "side": "SELL", "price": "0.45", "matched_amount": "100" }]
+        });
+        let fills = parse_trade(&taker, "key-1", &address);
+        assert_eq!(fills.len(), 1);
+        assert_eq!((fills[0].fill_id.as_str(), fills[0].order_id.as_str(), fills[0].side), ("t1:our-taker", "our-taker", Side::Buy));
+        assert_eq!((fills[0].size, fills[0].fee, fills[0].timestamp_ms), (dec!(100), dec!(0.45), 1_700_000_000_000));
+
+        // 我方是 maker：方向、订单号、价格与数量都取我方的 maker_orders 条目
+        let maker = serde_json::json!({
+            "id": "t2", "taker_order_id": "their-taker", "asset_id": "123", "side": "BUY",
+            "price": "0.46", "size": "80", "match_time": "1700000001", "trader_side": "MAKER",
+            "maker_orders": [
+                { "order_id": "ours-1", "owner": "key-1", "asset_id": "123", "side": "SELL", "price": "0.45", "matched_amount": "30" },
+                { "order_id": "theirs", "owner": "other", "asset_id": "123", "side": "SELL", "price": "0.46", "matched_amount": "20" },
+                { "order_id": "ours-2", "maker_address": address.to_uppercase().replace("0X", "0x"), "asset_id": "123", "side": "SELL", "price": "0.46", "matched_amount": "30" }
+            ]
+        });
+        let fills = parse_trade(&maker, "key-1", &address);
+        let summary: Vec<_> = fills.iter().map(|f| (f.order_id.as_str(), f.side, f.price, f.size)).collect();
+        assert_eq!(summary, vec![("ours-1", Side::Sell, dec!(0.45), dec!(30)), ("ours-2", Side::Sell, dec!(0.46), dec!(30))]);
+        assert_eq!(fills[0].fill_id, "t2:ours-1");
+    }
+}
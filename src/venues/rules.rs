@@ -0,0 +1,199 @@
+// File: src/venues/rules.rs
+// 市场交易规则：分段 tick 表 + 最小下单量
+// 场馆在价格边界附近会换成更细的 tick (例如价格 < 0.04 或 > 0.96 时 tick = 0.001)
+// 价格全部用 Decimal 精确取整，方向感知：买价向下取整、卖价向上取整 (取整永远不会让报价更激进)
+use rust_decimal::Decimal;
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+use crate::config::AppConfig;
+use crate::core::{Exchange, Side};
+use crate::venues::polymarket::parse_asset_id;
+use crate::venues::ExchangeAdapter;
+
+/// tick 表中的一段：价格 < up_to (且不低于上一段的 up_to) 时使用 tick
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TickBand {
+    pub up_to: Decimal,
+    pub tick: Decimal,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MarketRules {
+    pub ticks: Vec<TickBand>,     // 按 up_to 升序，最后一段 up_to = 1；为空表示使用策略的 tick_size
+    pub min_size_shares: Decimal, // 最小下单份额 (0 = 不限)
+}
+
+impl MarketRules {
+    /// 整个价格区间使用同一个 tick
+    pub fn uniform(tick: Decimal, min_size_shares: Decimal) -> Self {
+        Self { ticks: vec![TickBand { up_to: Decimal::ONE, tick }], min_size_shares }
+    }
+
+    /// 没有配置 tick 表时按 default_tick (strategy.tick_size) 补成统一 tick
+    pub fn with_default_tick(&self, default_tick: f64) -> Self {
+        if !self.ticks.is_empty() {
+            return self.clone();
+        }
+        let tick = Decimal::from_f64(default_tick).unwrap_or(dec!(0.01));
+        Self::uniform(tick, self.min_size_shares)
+    }
+
+    /// 该价格所在区间的 tick
+    pub fn tick_at(&self, price: Decimal) -> Decimal {
+        self.ticks.iter()
+            .find(|b| price < b.up_to)
+            .or(self.ticks.last())
+            .map(|b| b.tick)
+            .unwrap_or(dec!(0.01))
+    }
+
+    /// 最低可报价格 (第一个 tick) 与最高可报价格 (1 - 最后一个 tick)
+    pub fn price_bounds(&self) -> (Decimal, Decimal) {
+        let first = self.ticks.first().map(|b| b.tick).unwrap_or(dec!(0.01));
+        let last = self.ticks.last().map(|b| b.tick).unwrap_or(dec!(0.01));
+        (first, Decimal::ONE - last)
+    }
+
+    /// 按方向取整到 tick，并限制在可报价格范围内
+    pub fn round(&self, price: Decimal, side: Side) -> Decimal {
+        let tick = self.tick_at(price);
+        let units = price / tick;
+        let units = match side {
+            Side::Buy => units.floor(),
+            Side::Sell => units.ceil(),
+        };
+        let (min, max) = self.price_bounds();
+        (units * tick).clamp(min, max).normalize()
+    }
+
+    /// 不吃单的最激进价格：买单严格低于对手卖一 (touch)，卖单严格高于对手买一
+    /// touch 本身不在 tick 网格上时同样适用；被价格边界截断后可能仍然穿价，调用方需要再检查
+    pub fn passive_limit(&self, touch: Decimal, side: Side) -> Decimal {
+        // touch 在网格上时取整会落回 touch 本身，再往里挪一个最小 tick (跨分段时取整会对齐到新网格)
+        let min_tick = self.ticks.iter().map(|b| b.tick).min().unwrap_or(dec!(0.01));
+        match side {
+            Side::Buy => Some(self.round(touch, side)).filter(|p| *p < touch).unwrap_or_else(|| self.round(touch - min_tick, side)),
+            Side::Sell => Some(self.round(touch, side)).filter(|p| *p > touch).unwrap_or_else(|| self.round(touch + min_tick, side)),
+        }
+    }
+
+    /// 按 USD 金额下单时，换算出的份额是否达到最小下单量
+    pub fn meets_min_size(&self, price: Decimal, size_usd: Decimal) -> bool {
+        self.min_size_shares.is_zero() || (!price.is_zero() && size_usd / price >= self.min_size_shares)
+    }
+}
+
+/// 校验 tick 表：tick ∈ (0, 1)，up_to 严格升序且最后一段覆盖到 1
+pub fn validate_ticks(ticks: &[TickBand]) -> Result<(), String> {
+    let mut prev = Decimal::ZERO;
+    for band in ticks {
+        if band.tick <= Decimal::ZERO || band.tick >= Decimal::ONE {
+            return Err(format!("tick must be in (0, 1), got {}", band.tick));
+        }
+        if band.up_to <= prev || band.up_to > Decimal::ONE {
+            return Err(format!("up_to must be ascending within (0, 1], got {} after {}", band.up_to, prev));
+        }
+        prev = band.up_to;
+    }
+    if !ticks.is_empty() && prev != Decimal::ONE {
+        return Err(format!("the last tick band must end at up_to = 1, got {}", prev));
+    }
+    Ok(())
+}
+
+/// 执行层使用的交易规则：先用配置 (Opinion 市场)，场馆能提供的 (例如 Polymarket /book) 以场馆为准
+pub async fn load_market_rules(cfg: &AppConfig, venues: &HashMap<Exchange, Arc<dyn ExchangeAdapter>>) -> HashMap<(Exchange, u64), MarketRules> {
+    let mut rules = HashMap::new();
+    let mut symbols = Vec::new();
+    for spec in cfg.market_specs().unwrap_or_default() {
+        rules.insert((Exchange::OpinionLabs, spec.opinion_market_id), spec.rules.with_default_tick(spec.config.tick_size));
+        symbols.push((Exchange::OpinionLabs, spec.opinion_market_id));
+        if let Some(id) = parse_asset_id(&spec.poly_asset_id) {
+            symbols.push((Exchange::Polymarket, id.low_u64()));
+        }
+    }
+
+    for (exchange, symbol_id) in symbols {
+        let Some(venue) = venues.get(&exchange) else { continue };
+        match venue.market_rules(symbol_id).await {
+            Ok(Some(fetched)) => {
+                info!(exchange = ?exchange, symbol_id, ticks = ?fetched.ticks, min_size_shares = %fetched.min_size_shares, "📏 Market rules from venue");
+                rules.insert((exchange, symbol_id), fetched);
+            }
+            Ok(None) => {}
+            Err(e) => warn!(exchange = ?exchange, symbol_id, error = %e, "⚠️ Fetch market rules failed, using config"),
+        }
+    }
+    rules
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 价格 < 0.04 或 >= 0.96 时 tick = 0.001
+    fn banded() -> MarketRules {
+        MarketRules {
+            ticks: vec![
+                TickBand { up_to: dec!(0.04), tick: dec!(0.001) },
+                TickBand { up_to: dec!(0.96), tick: dec!(0.01) },
+                TickBand { up_to: dec!(1), tick: dec!(0.001) },
+            ],
+            min_size_shares: dec!(5),
+        }
+    }
+
+    #[test]
+    fn rounds_bids_down_and_asks_up_exactly() {
+        let rules = banded();
+        assert_eq!(rules.round(dec!(0.4712), Side::Buy), dec!(0.47));
+        assert_eq!(rules.round(dec!(0.4712), Side::Sell), dec!(0.48));
+        assert_eq!(rules.round(dec!(0.47), Side::Sell), dec!(0.47));
+        assert_eq!(rules.round(dec!(0.47), Side::Sell).to_string(), "0.47");
+    }
+
+    #[test]
+    fn uses_fine_ticks_near_the_bounds() {
+        let rules = banded();
+        assert_eq!(rules.round(dec!(0.0317), Side::Buy), dec!(0.031));
+        assert_eq!(rules.round(dec!(0.9712), Side::Sell), dec!(0.972));
+        assert_eq!(rules.price_bounds(), (dec!(0.001), dec!(0.999)));
+        assert_eq!(rules.round(dec!(0.0002), Side::Buy), dec!(0.001));
+        assert_eq!(rules.round(dec!(0.9995), Side::Sell), dec!(0.999));
+    }
+
+    #[test]
+    fn passive_limit_is_one_valid_price_inside_the_touch() {
+        let rules = banded();
+        assert_eq!(rules.passive_limit(dec!(0.5), Side::Buy), dec!(0.49));
+        assert_eq!(rules.passive_limit(dec!(0.5), Side::Sell), dec!(0.51));
+        // 跨过 tick 分段：0.04 下方是 0.001 的网格
+        assert_eq!(rules.passive_limit(dec!(0.04), Side::Buy), dec!(0.039));
+        // 对手价不在网格上
+        assert_eq!(rules.passive_limit(dec!(0.505), Side::Buy), dec!(0.50));
+        assert_eq!(rules.passive_limit(dec!(0.505), Side::Sell), dec!(0.51));
+    }
+
+    #[test]
+    fn min_size_is_checked_in_shares() {
+        let rules = banded();
+        assert!(rules.meets_min_size(dec!(0.5), dec!(2.5)));
+        assert!(!rules.meets_min_size(dec!(0.5), dec!(2.49)));
+        assert!(MarketRules::uniform(dec!(0.01), dec!(0)).meets_min_size(dec!(0.5), dec!(0.01)));
+    }
+
+    #[test]
+    fn rejects_malformed_tables() {
+        assert!(validate_ticks(&banded().ticks).is_ok());
+        assert!(validate_ticks(&banded().ticks[..2]).is_err());
+        let mut unsorted = banded().ticks;
+        unsorted.swap(0, 1);
+        assert!(validate_ticks(&unsorted).is_err());
+    }
+}